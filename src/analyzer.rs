@@ -0,0 +1,107 @@
+use crate::cleaner;
+use crate::targets::{CacheFormat, CacheTarget};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Entries shown per level of the treemap; smaller ones are lumped together
+const MAX_NODES: usize = 100;
+
+// One rectangle of the treemap
+#[derive(Serialize)]
+pub struct Node {
+    pub name: String,
+    // None for the entries lumped together as "other"
+    pub path: Option<String>,
+    pub size_bytes: u64,
+    pub file_count: u64,
+    pub is_dir: bool,
+}
+
+// `path`, which must be one of the target's paths or inside one
+fn within_target(target: &CacheTarget, path: &Path) -> Result<PathBuf, String> {
+    cleaner::check_path(path)?;
+    // Resolved as well, so a link inside the target cannot lead out of it
+    let resolved = fs::canonicalize(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let inside = |root: &PathBuf| {
+        path.starts_with(root) && fs::canonicalize(root).is_ok_and(|root| resolved.starts_with(root))
+    };
+    if !target.paths.iter().any(inside) {
+        return Err(format!("{} is not part of {}", path.display(), target.name));
+    }
+    Ok(path.to_path_buf())
+}
+
+fn node(path: &Path, target: &CacheTarget) -> Option<Node> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    let name = path
+        .file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    if meta.is_dir() {
+        // Reuses the listings of the last scan where folders did not change
+        let stats = cleaner::scan_path_limited(path, target.scan_limits);
+        Some(Node {
+            name,
+            path: Some(path.display().to_string()),
+            size_bytes: stats.size_bytes,
+            file_count: stats.file_count,
+            is_dir: true,
+        })
+    } else if meta.is_file() {
+        Some(Node {
+            name,
+            path: Some(path.display().to_string()),
+            size_bytes: meta.len(),
+            file_count: 1,
+            is_dir: false,
+        })
+    } else {
+        None
+    }
+}
+
+// What is directly inside `path`, or the target's own paths without one, the
+// largest first
+pub fn children(target: &CacheTarget, path: Option<&Path>) -> Result<Vec<Node>, String> {
+    let entries: Vec<PathBuf> = match path {
+        None => target.paths.clone(),
+        Some(path) => {
+            let path = within_target(target, path)?;
+            fs::read_dir(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .flatten()
+                .map(|entry| entry.path())
+                .collect()
+        }
+    };
+    let mut nodes: Vec<_> = entries.iter().filter_map(|entry| node(entry, target)).collect();
+    nodes.sort_by_key(|node| Reverse(node.size_bytes));
+    if nodes.len() > MAX_NODES {
+        let rest = nodes.split_off(MAX_NODES - 1);
+        nodes.push(Node {
+            name: format!("{} smaller items", rest.len()),
+            path: None,
+            size_bytes: rest.iter().map(|node| node.size_bytes).sum(),
+            file_count: rest.iter().map(|node| node.file_count).sum(),
+            is_dir: false,
+        });
+    }
+    Ok(nodes)
+}
+
+// The target narrowed to one of its folders or files, cleaned by the
+// target's own strategy and rules
+pub fn narrow(target: &CacheTarget, path: &Path) -> Result<CacheTarget, String> {
+    if target.report_only {
+        return Err(format!("{} is only reported, never cleaned", target.name));
+    }
+    // Their index has to match every entry, so they go as a whole
+    if target.format != CacheFormat::Files && !target.paths.iter().any(|root| root == path) {
+        return Err(format!("{} can only be cleaned as a whole", target.name));
+    }
+    let path = within_target(target, path)?;
+    let mut narrowed = target.clone();
+    narrowed.paths = vec![path];
+    Ok(narrowed)
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+// Starting the app when the user logs in: a value under the Run key on
+// Windows, a LaunchAgent on macOS and an XDG autostart entry on Linux. The
+// entry passes AT_LOGIN so the app can tell such starts from the user's.
+
+pub const AT_LOGIN: &str = "--at-login";
+
+fn exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+#[cfg(target_os = "windows")]
+const VALUE_NAME: &str = "MemoryCacheManager";
+
+// Add or remove the app's login entry
+#[cfg(target_os = "windows")]
+pub fn sync(enabled: bool) -> Result<(), String> {
+    use crate::targets::tool_output;
+
+    if !enabled {
+        // Fails when there is no value, which is the state wanted anyway
+        let _ = tool_output("reg", &["delete", RUN_KEY, "/v", VALUE_NAME, "/f"]);
+        return Ok(());
+    }
+    let command = format!("\"{}\" {}", exe()?.display(), AT_LOGIN);
+    tool_output("reg", &["add", RUN_KEY, "/v", VALUE_NAME, "/t", "REG_SZ", "/d", &command, "/f"])
+        .map(|_| ())
+        .ok_or_else(|| "Failed to add the app to the programs started at login".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn entry_path() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    return crate::targets::home_dir().map(|p| p.join("Library/LaunchAgents/com.memorycache.manager.plist"));
+
+    #[cfg(not(target_os = "macos"))]
+    return crate::targets::app_config_dir().map(|p| p.join("autostart/memory-cache-manager.desktop"));
+}
+
+#[cfg(target_os = "macos")]
+fn entry() -> Result<String, String> {
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.memorycache.manager</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        exe()?.display(),
+        AT_LOGIN
+    ))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn entry() -> Result<String, String> {
+    Ok(format!(
+        "[Desktop Entry]\nType=Application\nName=Memory Cache Manager\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\n",
+        exe()?.display(),
+        AT_LOGIN
+    ))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn sync(enabled: bool) -> Result<(), String> {
+    let path = entry_path().ok_or("Could not find the folder of programs started at login")?;
+    if !enabled {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove {}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, entry()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
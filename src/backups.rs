@@ -0,0 +1,91 @@
+use crate::cleaner;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct DeviceBackup {
+    // Backup folder name (the device UDID)
+    pub id: String,
+    pub device_name: String,
+    pub last_backup_date: String,
+    pub size_bytes: u64,
+}
+
+// iTunes/Apple Devices backup roots; the Store build of iTunes uses the second one
+fn backup_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            roots.push(PathBuf::from(appdata).join("Apple Computer\\MobileSync\\Backup"));
+        }
+        if let Some(profile) = std::env::var_os("USERPROFILE") {
+            roots.push(PathBuf::from(profile).join("Apple\\MobileSync\\Backup"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(home) = std::env::var_os("HOME") {
+        roots.push(PathBuf::from(home).join("Library/Application Support/MobileSync/Backup"));
+    }
+
+    roots.retain(|root| root.is_dir());
+    roots
+}
+
+// Value following <key>`key`</key> in an XML property list
+fn plist_value(plist: &str, key: &str) -> Option<String> {
+    let key_tag = format!("<key>{}</key>", key);
+    let after_key = &plist[plist.find(&key_tag)? + key_tag.len()..];
+    let value_start = after_key.find('>')? + 1;
+    let value_end = after_key.find("</")?;
+    (value_start <= value_end).then(|| after_key[value_start..value_end].trim().to_string())
+}
+
+pub fn list_backups() -> Vec<DeviceBackup> {
+    let mut backups = Vec::new();
+    for root in backup_roots() {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            let info = fs::read_to_string(path.join("Info.plist")).unwrap_or_default();
+            backups.push(DeviceBackup {
+                id: entry.file_name().to_string_lossy().into_owned(),
+                device_name: plist_value(&info, "Device Name")
+                    .unwrap_or_else(|| "Unknown device".to_string()),
+                last_backup_date: plist_value(&info, "Last Backup Date").unwrap_or_default(),
+                size_bytes: cleaner::scan_dir(&path).size_bytes,
+            });
+        }
+    }
+
+    // Oldest first, since those are the usual candidates for deletion
+    backups.sort_by(|a, b| a.last_backup_date.cmp(&b.last_backup_date));
+    backups
+}
+
+pub fn delete_backup(id: &str) -> Result<u64, String> {
+    // Only bare folder names are accepted, never paths
+    if id.is_empty() || id.contains(['/', '\\']) || id == "." || id == ".." {
+        return Err(format!("Invalid backup id: {}", id));
+    }
+
+    let path = backup_roots()
+        .into_iter()
+        .map(|root| root.join(id))
+        .find(|path| path.is_dir())
+        .ok_or_else(|| format!("Backup not found: {}", id))?;
+
+    let size = cleaner::scan_dir(&path).size_bytes;
+    fs::remove_dir_all(&path).map_err(|e| format!("Failed to delete backup: {}", e))?;
+    Ok(size)
+}
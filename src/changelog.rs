@@ -0,0 +1,107 @@
+use crate::config_file;
+use crate::targets;
+use crate::Config;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+// What changed in each release, shown once after the app is updated. Targets
+// carry the version that added them, so those added since the version the
+// user last saw are offered with the notes.
+
+#[derive(Serialize)]
+pub struct Release {
+    pub version: &'static str,
+    pub notes: &'static [&'static str],
+}
+
+// Newest first
+const RELEASES: &[Release] = &[Release {
+    version: "1.0.0",
+    notes: &[
+        "Quarantine folder: cleaned files can be moved aside and restored until they expire",
+        "Suggestions for app cache folders no target covers",
+        "Time limit for scheduled and low-disk cleans, cleaning the fastest-freeing targets first",
+        "PowerShell module with Get-CacheStatus and Invoke-CacheClean",
+        "Color-blind safe status colors",
+    ],
+}];
+
+// A target added since the version last seen
+#[derive(Serialize)]
+pub struct NewTarget {
+    pub id: String,
+    pub name: String,
+    // Set for targets that are off by default
+    pub warning: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct WhatsNew {
+    pub version: &'static str,
+    pub releases: Vec<&'static Release>,
+    pub targets: Vec<NewTarget>,
+}
+
+const CURRENT: &str = env!("CARGO_PKG_VERSION");
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("seen-version.txt"))
+}
+
+// "1.10.0" comes after "1.9.2"
+fn is_newer(version: &str, than: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> { version.split('.').map(|part| part.parse().unwrap_or(0)).collect() };
+    parse(version) > parse(than)
+}
+
+// The version whose notes were last seen. Installs from before there were
+// notes count as older than every release.
+fn seen_version() -> Option<String> {
+    let seen = state_path().and_then(|path| fs::read_to_string(path).ok());
+    match seen {
+        Some(version) => Some(version.trim().to_string()),
+        None if config_file::has_user_config() => Some("0".to_string()),
+        None => None,
+    }
+}
+
+pub fn mark_seen() {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, CURRENT);
+}
+
+// The releases and targets new since the version last seen, or None when
+// there are none; a first start has nothing new
+pub fn pending(config: &Config) -> Option<WhatsNew> {
+    let Some(seen) = seen_version() else {
+        mark_seen();
+        return None;
+    };
+    if !is_newer(CURRENT, &seen) {
+        return None;
+    }
+    let releases = RELEASES
+        .iter()
+        .filter(|release| is_newer(release.version, &seen) && !is_newer(release.version, CURRENT))
+        .collect();
+    let targets = targets::builtin_targets(config)
+        .into_iter()
+        .filter(|target| !target.report_only && target.added_in.is_some_and(|version| is_newer(version, &seen)))
+        .map(|target| NewTarget {
+            id: target.id,
+            name: target.name,
+            warning: target.warning,
+        })
+        .collect();
+    Some(WhatsNew {
+        version: CURRENT,
+        releases,
+        targets,
+    })
+}
@@ -0,0 +1,282 @@
+use crate::cleaner::{self, Candidate, CleanStats, Exclusions, Strategy};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Chromium's disk caches keep an index that must agree with their entry
+// files, so deleting arbitrary files as the generic strategies do leaves the
+// browser with a broken cache. Here a cache is either reset as a whole, or,
+// with the simple cache backend, trimmed one entry (all of its files) at a
+// time. After a partial clean the index is removed so the browser rebuilds it.
+
+enum Backend {
+    // One file per entry stream ("<hash>_0", "<hash>_1", "<hash>_s") plus
+    // index-dir/the-real-index, which the browser can rebuild from them
+    Simple,
+    // "index" and data_0..data_3 block files with f_* files for large entries;
+    // single entries cannot be removed without the browser's help
+    Blockfile,
+}
+
+// Newer versions keep the backend files in Cache_Data below the cache folder
+fn data_dir(path: &Path) -> PathBuf {
+    let nested = path.join("Cache_Data");
+    if nested.is_dir() {
+        nested
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn backend(dir: &Path) -> Option<Backend> {
+    if dir.join("data_0").is_file() {
+        Some(Backend::Blockfile)
+    } else if dir.join("index-dir").is_dir() {
+        Some(Backend::Simple)
+    } else {
+        None
+    }
+}
+
+// Remove the index of every cache at or below `path`, so the browser
+// rebuilds it from the entries still there instead of trusting a stale one
+fn invalidate_indexes(path: &Path) {
+    match backend(path) {
+        Some(Backend::Simple) => {
+            let _ = fs::remove_file(path.join("index-dir").join("the-real-index"));
+        }
+        Some(Backend::Blockfile) => {
+            let _ = fs::remove_file(path.join("index"));
+        }
+        None => {}
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name() != "index-dir" && entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            invalidate_indexes(&entry.path());
+        }
+    }
+}
+
+struct Entry {
+    files: Vec<(PathBuf, fs::Metadata)>,
+    size: u64,
+    last_used: SystemTime,
+}
+
+// The entries of a simple cache, keyed by the hash their file names start with
+fn simple_entries(dir: &Path) -> BTreeMap<String, Entry> {
+    let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+    let Ok(listing) = fs::read_dir(dir) else {
+        return entries;
+    };
+    for file in listing.flatten() {
+        let name = file.file_name().to_string_lossy().into_owned();
+        let Some((hash, stream)) = name.split_once('_') else {
+            continue;
+        };
+        let is_entry_file = hash.len() == 16
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+            && matches!(stream, "0" | "1" | "s");
+        let Ok(meta) = file.metadata() else {
+            continue;
+        };
+        if !is_entry_file || !meta.is_file() {
+            continue;
+        }
+        let entry = entries.entry(hash.to_string()).or_insert(Entry {
+            files: Vec::new(),
+            size: 0,
+            last_used: SystemTime::UNIX_EPOCH,
+        });
+        entry.size += meta.len();
+        entry.last_used = entry.last_used.max(cleaner::last_used(&meta));
+        entry.files.push((file.path(), meta));
+    }
+    entries
+}
+
+// What trimming ranks: each entry of a simple cache, with the folder that
+// holds it, and each other cache as a whole, since its entries cannot be
+// removed separately
+enum Unit {
+    Entry(PathBuf, Entry),
+    Cache { path: PathBuf, size: u64, last_used: SystemTime },
+}
+
+impl Unit {
+    fn size(&self) -> u64 {
+        match self {
+            Unit::Entry(_, entry) => entry.size,
+            Unit::Cache { size, .. } => *size,
+        }
+    }
+
+    fn last_used(&self) -> SystemTime {
+        match self {
+            Unit::Entry(_, entry) => entry.last_used,
+            Unit::Cache { last_used, .. } => *last_used,
+        }
+    }
+}
+
+// The latest use of any file at or below `path`
+fn last_used_in(path: &Path) -> SystemTime {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return SystemTime::UNIX_EPOCH;
+    };
+    if !meta.is_dir() {
+        return cleaner::last_used(&meta);
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| last_used_in(&entry.path()))
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+// Units beyond `budget` across every cache of a target together, least
+// recently used last to be kept, the same ranking TrimToSize gives plain files
+fn over_budget(paths: &[PathBuf], budget: u64) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for path in paths {
+        let dir = data_dir(&cleaner::verbatim(path));
+        match backend(&dir) {
+            Some(Backend::Simple) => {
+                units.extend(simple_entries(&dir).into_values().map(|entry| Unit::Entry(dir.clone(), entry)));
+            }
+            _ => units.push(Unit::Cache {
+                size: total_size(path),
+                last_used: last_used_in(&cleaner::verbatim(path)),
+                path: path.clone(),
+            }),
+        }
+    }
+    units.sort_by_key(|unit| Reverse(unit.last_used()));
+    let mut kept_bytes = 0u64;
+    let mut full = false;
+    units.retain(|unit| {
+        if !full && kept_bytes + unit.size() <= budget {
+            kept_bytes += unit.size();
+            return false;
+        }
+        full = true;
+        unit.size() > 0
+    });
+    units
+}
+
+// An entry is kept whole when any of its files is excluded or too recent
+fn is_kept(entry: &Entry, exclusions: &Exclusions) -> bool {
+    entry.files.iter().any(|(path, meta)| {
+        exclusions.excludes(path) || exclusions.is_too_recent(cleaner::last_used(meta)) || cleaner::is_protected(path)
+    })
+}
+
+fn total_size(path: &Path) -> u64 {
+    cleaner::scan_path(path).size_bytes
+}
+
+// Trim a target's caches to `budget` between them. A blockfile cache (or one
+// whose layout is not recognised) that does not fit is reset, since its
+// entries cannot be removed separately.
+fn trim(paths: &[PathBuf], budget: u64, exclusions: &Exclusions) -> CleanStats {
+    let mut stats = CleanStats::default();
+    let mut trimmed: Vec<PathBuf> = Vec::new();
+    for unit in over_budget(paths, budget) {
+        if cleaner::is_cancelled() {
+            break;
+        }
+        match unit {
+            Unit::Entry(dir, entry) => {
+                if is_kept(&entry, exclusions) {
+                    stats.files_skipped += entry.files.len() as u64;
+                    stats.skipped_bytes += entry.files.iter().map(|(_, meta)| meta.len()).sum::<u64>();
+                    continue;
+                }
+                cleaner::record_dir(&dir);
+                let deleted = stats.files_deleted;
+                for (file, meta) in &entry.files {
+                    cleaner::remove_file(file, meta, None, exclusions, &mut stats);
+                }
+                if stats.files_deleted > deleted && !trimmed.contains(&dir) {
+                    trimmed.push(dir);
+                }
+            }
+            Unit::Cache { path, .. } => stats.add(wipe(&path, exclusions)),
+        }
+    }
+    for dir in trimmed {
+        invalidate_indexes(&dir);
+    }
+    stats
+}
+
+// Empty a cache. Without exclusions the folder is reset in one step;
+// otherwise the files that are not kept are deleted and the indexes dropped.
+fn wipe(path: &Path, exclusions: &Exclusions) -> CleanStats {
+    if exclusions.is_empty() {
+        return cleaner::reset_dir(path);
+    }
+    let stats = cleaner::clean_dir(path, None, exclusions);
+    invalidate_indexes(path);
+    stats
+}
+
+pub fn clean(paths: &[PathBuf], strategy: Strategy, exclusions: &Exclusions) -> CleanStats {
+    if let Strategy::TrimToSize(budget) = strategy {
+        return trim(paths, budget, exclusions);
+    }
+    let mut stats = CleanStats::default();
+    for path in paths {
+        if cleaner::is_cancelled() {
+            break;
+        }
+        stats.add(wipe(path, exclusions));
+    }
+    stats
+}
+
+// Delete exactly the files a user reviewed, then drop the indexes so the
+// browser does not look for them
+pub fn clean_files(roots: &[PathBuf], files: &[PathBuf], exclusions: &Exclusions) -> CleanStats {
+    let stats = cleaner::clean_files(roots, files, exclusions);
+    for root in roots {
+        invalidate_indexes(&cleaner::verbatim(root));
+    }
+    stats
+}
+
+// The files clean would delete, without deleting any
+pub fn preview(paths: &[PathBuf], strategy: Strategy, exclusions: &Exclusions) -> Vec<Candidate> {
+    let Strategy::TrimToSize(budget) = strategy else {
+        return cleaner::preview_target(paths, Strategy::DeleteAll, exclusions);
+    };
+    let mut candidates = Vec::new();
+    for unit in over_budget(paths, budget) {
+        match unit {
+            Unit::Entry(_, entry) if !is_kept(&entry, exclusions) => {
+                candidates.extend(entry.files.into_iter().map(|(path, meta)| Candidate {
+                    path,
+                    size: meta.len(),
+                    modified: meta
+                        .modified()
+                        .ok()
+                        .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                        .map_or(0, |since| since.as_secs()),
+                }));
+            }
+            Unit::Entry(..) => {}
+            Unit::Cache { path, .. } => {
+                candidates.extend(cleaner::preview_target(&[path], Strategy::DeleteAll, exclusions));
+            }
+        }
+    }
+    candidates
+}
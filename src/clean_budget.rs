@@ -0,0 +1,149 @@
+use crate::clean_history::Recorder;
+use crate::cleaner::{self, CleanStats};
+use crate::config_file;
+use crate::targets::{self, CacheTarget};
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Cleans limited in time, automatic ones or from the command line: targets
+// go in order of how fast their last clean freed space, the clean stops
+// between files when the time is up, and the targets an automatic clean did
+// not finish go first in the next one.
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Default)]
+struct Budgets {
+    // Bytes per second each target's last clean freed
+    rates: BTreeMap<String, f64>,
+    // Targets the last automatic clean stopped before finishing
+    carried_over: Vec<String>,
+}
+
+// A target's place in the order automatic cleans go in
+#[derive(Serialize)]
+pub struct Priority {
+    pub id: String,
+    // None until the target was first cleaned
+    pub bytes_per_second: Option<f64>,
+    pub carried_over: bool,
+}
+
+fn path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("clean_rates.json"))
+}
+
+fn read_all() -> Budgets {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(budgets: &Budgets) {
+    let Some(path) = path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(budgets) {
+        let _ = fs::write(path, text);
+    }
+}
+
+fn load() -> Budgets {
+    let _guard = FILE_LOCK.lock().unwrap();
+    read_all()
+}
+
+// Carried over first, then the most bytes freed per second; targets never
+// cleaned before go last
+fn sort(targets: &mut [CacheTarget], budgets: &Budgets) {
+    let carried_over = |target: &CacheTarget| budgets.carried_over.contains(&target.id);
+    let rate = |target: &CacheTarget| budgets.rates.get(&target.id).copied().unwrap_or(-1.0);
+    targets.sort_by(|a, b| carried_over(b).cmp(&carried_over(a)).then(rate(b).total_cmp(&rate(a))));
+}
+
+// Put `targets` in the order a clean under a time limit should take them
+pub fn order(targets: &mut [CacheTarget]) {
+    sort(targets, &load());
+}
+
+// Every cleanable target in the order automatic cleans take them
+pub fn priorities(config: &Config) -> Vec<Priority> {
+    let budgets = load();
+    let mut targets: Vec<_> = targets::builtin_targets(config)
+        .into_iter()
+        .filter(|target| !target.report_only)
+        .collect();
+    sort(&mut targets, &budgets);
+    targets
+        .into_iter()
+        .map(|target| Priority {
+            bytes_per_second: budgets.rates.get(&target.id).copied(),
+            carried_over: budgets.carried_over.contains(&target.id),
+            id: target.id,
+        })
+        .collect()
+}
+
+// Remember how fast cleaning a target freed space
+pub fn record_rate(id: &str, freed_bytes: u64, elapsed: Duration) {
+    let rate = freed_bytes as f64 / elapsed.as_secs_f64().max(0.001);
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut budgets = read_all();
+    budgets.rates.insert(id.to_string(), rate);
+    write_all(&budgets);
+}
+
+// Ids of the targets the last automatic clean ran out of time for. Each kind
+// of automatic clean takes them again only if they pass its own filters.
+pub fn carried_over() -> Vec<String> {
+    load().carried_over
+}
+
+// Clean `targets` in order on this thread's clean, stopping it when `budget`
+// is up. Returns what was freed and how many targets the time ran out for,
+// which the next plan puts first; a clean cancelled otherwise carries
+// nothing over.
+pub fn clean(targets: &[CacheTarget], budget: Option<Duration>, history: &Recorder) -> (CleanStats, usize) {
+    let (done, done_received) = mpsc::channel::<()>();
+    let run = cleaner::current_run();
+    let watchdog = budget.map(|budget| {
+        std::thread::spawn(move || {
+            let timed_out = done_received.recv_timeout(budget) == Err(RecvTimeoutError::Timeout);
+            if let Some(run) = run.filter(|_| timed_out) {
+                run.cancel();
+            }
+            timed_out
+        })
+    });
+    let finished = Mutex::new(Vec::new());
+    let stats = targets::clean_targets(targets, history, |target| finished.lock().unwrap().push(target.id.clone()));
+    drop(done);
+
+    let timed_out = watchdog.is_some_and(|watchdog| watchdog.join().unwrap_or(false));
+    let finished = finished.into_inner().unwrap();
+    let carried_over: Vec<_> = if timed_out {
+        targets::cleanable(targets)
+            .into_iter()
+            .filter(|target| !finished.contains(&target.id))
+            .map(|target| target.id.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut budgets = read_all();
+    let count = carried_over.len();
+    budgets.carried_over = carried_over;
+    write_all(&budgets);
+    (stats, count)
+}
@@ -0,0 +1,110 @@
+// Signals other programs on the machine can use to pause while cleans run,
+// e.g. backup tools and indexers. These names are a stable interface:
+//
+// - Window messages registered as "MemoryCacheManager.CleanStarted" and
+//   "MemoryCacheManager.CleanFinished" are broadcast to top-level windows.
+//   wParam is the process id of the cleaning instance; lParam is 0 when a
+//   clean starts and the MiB it freed when it finishes.
+// - The manual-reset event "Local\MemoryCacheManager.Cleaning" is signaled
+//   while any clean runs, and "Local\MemoryCacheManager.Idle" while none
+//   does, for programs without a window to wait on.
+//
+// Other platforms have no equivalent yet.
+
+// Cleans running in this process; the events follow it crossing 0
+#[cfg(target_os = "windows")]
+static RUNNING: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(target_os = "windows")]
+struct Events {
+    started: u32,
+    finished: u32,
+    // Kept open for as long as the app runs, so the names stay valid
+    cleaning: isize,
+    idle: isize,
+}
+
+#[cfg(target_os = "windows")]
+fn events() -> &'static Events {
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::System::Threading::CreateEventW;
+    use windows::Win32::UI::WindowsAndMessaging::RegisterWindowMessageW;
+
+    static EVENTS: std::sync::OnceLock<Events> = std::sync::OnceLock::new();
+    EVENTS.get_or_init(|| {
+        let event = |name: PCWSTR, signaled: bool| {
+            unsafe { CreateEventW(None, true, signaled, name) }.map_or(0, |handle| handle.0)
+        };
+        Events {
+            started: unsafe { RegisterWindowMessageW(w!("MemoryCacheManager.CleanStarted")) },
+            finished: unsafe { RegisterWindowMessageW(w!("MemoryCacheManager.CleanFinished")) },
+            cleaning: event(w!("Local\\MemoryCacheManager.Cleaning"), false),
+            idle: event(w!("Local\\MemoryCacheManager.Idle"), true),
+        }
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn broadcast(message: u32, value: isize) {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{PostMessageW, HWND_BROADCAST};
+
+    if message != 0 {
+        let _ = unsafe {
+            PostMessageW(
+                HWND_BROADCAST,
+                message,
+                WPARAM(std::process::id() as usize),
+                LPARAM(value),
+            )
+        };
+    }
+}
+
+// Signal `on` and clear `off`
+#[cfg(target_os = "windows")]
+fn switch_events(on: isize, off: isize) {
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Threading::{ResetEvent, SetEvent};
+
+    unsafe {
+        let _ = ResetEvent(HANDLE(off));
+        let _ = SetEvent(HANDLE(on));
+    }
+}
+
+// Create the events up front, so programs find them before the first clean
+#[cfg(target_os = "windows")]
+pub fn init() {
+    events();
+}
+
+#[cfg(target_os = "windows")]
+pub fn started() {
+    let events = events();
+    if RUNNING.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+        switch_events(events.cleaning, events.idle);
+    }
+    broadcast(events.started, 0);
+}
+
+#[cfg(target_os = "windows")]
+pub fn finished(freed_bytes: u64) {
+    let events = events();
+    if RUNNING.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) == 1 {
+        switch_events(events.idle, events.cleaning);
+    }
+    broadcast(
+        events.finished,
+        isize::try_from(freed_bytes / (1024 * 1024)).unwrap_or(isize::MAX),
+    );
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn init() {}
+
+#[cfg(not(target_os = "windows"))]
+pub fn started() {}
+
+#[cfg(not(target_os = "windows"))]
+pub fn finished(_freed_bytes: u64) {}
@@ -0,0 +1,379 @@
+use crate::clean_events;
+use crate::clean_verification::{self, Discrepancy};
+use crate::cleaner::{self, CleanStats, FailedDelete, FailureKind};
+use crate::config_file;
+use crate::data_retention::{self, Policy};
+use crate::disk_space;
+use crate::locale;
+use crate::notifications::{self, Level};
+use crate::quarantine;
+use crate::regrowth;
+use crate::targets::CacheTarget;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Oldest runs are dropped beyond this
+const MAX_RUNS: usize = 200;
+
+// Failed files listed for the last clean; the rest are only counted
+const MAX_LISTED_FAILURES: usize = 1000;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+static LAST_FAILURES: Mutex<Option<FailureReport>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TargetRun {
+    pub id: String,
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+    pub stats: CleanStats,
+    // What the verification pass found wrong, when it ran and found anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discrepancy: Option<Discrepancy>,
+}
+
+// What a clean freed on one drive
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VolumeRun {
+    // The drive's root, e.g. "D:\" or "/home"
+    pub root: String,
+    pub freed_bytes: u64,
+    // Free space on the drive once the clean was over
+    pub free_bytes: u64,
+}
+
+// One finished clean, whatever started it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CleanRun {
+    pub started_at: u64,
+    pub duration_ms: u64,
+    // "window", "schedule", "low_disk", "emergency", "impact", "cli" or "tui"
+    pub source: String,
+    pub cancelled: bool,
+    pub targets: Vec<TargetRun>,
+    pub total: CleanStats,
+    // Per drive, the most freed first; absent from older history
+    #[serde(default)]
+    pub volumes: Vec<VolumeRun>,
+    // Whether the targets were checked again after the clean
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CleanFailure {
+    pub target: String,
+    pub path: PathBuf,
+    pub kind: FailureKind,
+    pub error: String,
+}
+
+// Why the files of the last clean could not be deleted
+#[derive(Serialize, Clone, Default)]
+pub struct FailureReport {
+    // Failed files per reason, the most common first
+    pub kinds: Vec<(FailureKind, u64)>,
+    pub files: Vec<CleanFailure>,
+    // Failed files beyond the listed ones, and those in folders that could
+    // not be emptied as a whole
+    pub unlisted: u64,
+}
+
+fn failure_report(failures: &BTreeMap<String, Vec<FailedDelete>>, files_failed: u64) -> FailureReport {
+    let mut counts = BTreeMap::new();
+    let mut files = Vec::new();
+    for (target, failed) in failures {
+        for file in failed {
+            *counts.entry(file.kind).or_insert(0u64) += 1;
+            if files.len() < MAX_LISTED_FAILURES {
+                files.push(CleanFailure {
+                    target: target.clone(),
+                    path: file.path.clone(),
+                    kind: file.kind,
+                    error: file.error.clone(),
+                });
+            }
+        }
+    }
+    let mut kinds: Vec<_> = counts.into_iter().collect();
+    kinds.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    FailureReport {
+        kinds,
+        unlisted: files_failed.saturating_sub(files.len() as u64),
+        files,
+    }
+}
+
+// The failures of the last clean this process ran
+pub fn last_failures() -> FailureReport {
+    LAST_FAILURES.lock().unwrap().clone().unwrap_or_default()
+}
+
+fn history_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("clean-history.json"))
+}
+
+fn read_all() -> Vec<CleanRun> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(runs: &[CleanRun]) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(runs) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Drop the runs the retention settings no longer keep
+pub fn compact(policy: &Policy) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut runs = read_all();
+    if data_retention::fit(&mut runs, policy, |run| run.started_at) {
+        write_all(&runs);
+    }
+}
+
+// Collects what each target of a clean freed and which files it could not
+// delete; pass every target's result through `track`, on the thread that
+// cleaned it, and call `finish` once the clean is over
+pub struct Recorder {
+    source: &'static str,
+    started_at: SystemTime,
+    started: Instant,
+    targets: Mutex<Vec<TargetRun>>,
+    // Bytes freed per volume id, with a path on the volume to resolve it by
+    volumes: Mutex<BTreeMap<Option<String>, (PathBuf, u64)>>,
+    failures: Mutex<BTreeMap<String, Vec<FailedDelete>>>,
+    // Files each target deleted, kept only for the verification pass
+    verify: bool,
+    deleted: Mutex<BTreeMap<String, Vec<PathBuf>>>,
+}
+
+impl Recorder {
+    pub fn start(source: &'static str) -> Self {
+        clean_events::started();
+        Self {
+            source,
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+            targets: Mutex::new(Vec::new()),
+            volumes: Mutex::new(BTreeMap::new()),
+            failures: Mutex::new(BTreeMap::new()),
+            verify: false,
+            deleted: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // Rescan the cleaned targets in `finish` and flag what did not go as
+    // the clean reported
+    pub fn verified(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    pub fn track(&self, target: &CacheTarget, stats: CleanStats) -> CleanStats {
+        let failed = cleaner::take_failed();
+        if !failed.is_empty() {
+            self.failures.lock().unwrap().entry(target.id.clone()).or_default().extend(failed);
+        }
+        let deleted = cleaner::take_deleted();
+        if self.verify {
+            self.deleted.lock().unwrap().entry(target.id.clone()).or_default().extend(deleted);
+        }
+        if let Some(path) = target.paths.first() {
+            let mut volumes = self.volumes.lock().unwrap();
+            let volume = volumes.entry(target.volume.clone()).or_insert_with(|| (path.clone(), 0));
+            volume.1 += stats.freed_bytes;
+        }
+        self.targets.lock().unwrap().push(TargetRun {
+            id: target.id.clone(),
+            name: target.name.clone(),
+            paths: target.paths.clone(),
+            stats,
+            discrepancy: None,
+        });
+        stats
+    }
+
+    // Record the clean and return it
+    pub fn finish(self) -> CleanRun {
+        let failures = self.failures.into_inner().unwrap();
+        let mut targets = self.targets.into_inner().unwrap();
+        if self.verify {
+            let deleted = self.deleted.into_inner().unwrap();
+            verify(&mut targets, &deleted, self.started_at);
+        }
+        regrowth::record_clean(&targets);
+        let mut total = CleanStats::default();
+        for target in &targets {
+            total.add(target.stats);
+        }
+        *LAST_FAILURES.lock().unwrap() = Some(failure_report(&failures, total.files_failed));
+        quarantine::record(failures);
+        let run = CleanRun {
+            started_at: self
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            source: self.source.to_string(),
+            cancelled: cleaner::is_cancelled(),
+            targets,
+            total,
+            volumes: volume_runs(self.volumes.into_inner().unwrap()),
+            verified: self.verify,
+        };
+        clean_events::finished(run.total.freed_bytes);
+
+        let _guard = FILE_LOCK.lock().unwrap();
+        let mut runs = read_all();
+        runs.push(run.clone());
+        let excess = runs.len().saturating_sub(MAX_RUNS);
+        runs.drain(..excess);
+        write_all(&runs);
+        run
+    }
+}
+
+fn volume_runs(freed: BTreeMap<Option<String>, (PathBuf, u64)>) -> Vec<VolumeRun> {
+    let mut volumes: Vec<_> = freed
+        .into_values()
+        .filter_map(|(path, freed_bytes)| {
+            // A folder the clean removed is looked up by its parent
+            let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+            let (root, free_bytes) = disk_space::drive_of(existing)?;
+            Some(VolumeRun {
+                root,
+                freed_bytes,
+                free_bytes,
+            })
+        })
+        .collect();
+    volumes.sort_by_key(|volume| std::cmp::Reverse(volume.freed_bytes));
+    volumes
+}
+
+// Check each cleaned target again and note what did not go as reported,
+// with a notification, as nobody may be watching the clean
+fn verify(targets: &mut [TargetRun], deleted: &BTreeMap<String, Vec<PathBuf>>, since: SystemTime) {
+    let (mut reappeared, mut undeleted) = (0, 0);
+    for target in targets.iter_mut() {
+        let deleted = deleted.get(&target.id).map_or(&[][..], Vec::as_slice);
+        let found = clean_verification::check(&target.paths, deleted, since);
+        if !found.is_empty() {
+            reappeared += found.reappeared_files;
+            undeleted += found.undeleted_files;
+            target.discrepancy = Some(found);
+        }
+    }
+    if reappeared + undeleted > 0 {
+        notifications::push(
+            Level::Warning,
+            format!(
+                "Clean verification: {} files reappeared right after the clean and {} files reported deleted are still there",
+                reappeared, undeleted
+            ),
+        );
+    }
+}
+
+// The most recent runs, newest first
+pub fn recent(count: usize) -> Vec<CleanRun> {
+    let runs = {
+        let _guard = FILE_LOCK.lock().unwrap();
+        read_all()
+    };
+    runs.into_iter().rev().take(count).collect()
+}
+
+// "2024-05-01T13:45:00Z" for seconds since the Unix epoch
+pub fn utc_timestamp(secs: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// One row per target of each run; `freed` repeats bytes_freed in the
+// configured units for reading without a spreadsheet formula
+fn to_csv(runs: &[CleanRun], config: &Config) -> String {
+    let mut csv = String::from(
+        "started_at,source,cancelled,target_id,target_name,paths,files_deleted,bytes_freed,files_failed,files_skipped,bytes_failed,bytes_skipped,files_reappeared,files_undeleted,freed\n",
+    );
+    for run in runs {
+        for target in &run.targets {
+            let paths: Vec<_> = target.paths.iter().map(|p| p.display().to_string()).collect();
+            let row = [
+                utc_timestamp(run.started_at),
+                run.source.clone(),
+                run.cancelled.to_string(),
+                csv_field(&target.id),
+                csv_field(&target.name),
+                csv_field(&paths.join(";")),
+                target.stats.files_deleted.to_string(),
+                target.stats.freed_bytes.to_string(),
+                target.stats.files_failed.to_string(),
+                target.stats.files_skipped.to_string(),
+                target.stats.failed_bytes.to_string(),
+                target.stats.skipped_bytes.to_string(),
+                target.discrepancy.as_ref().map_or(0, |found| found.reappeared_files).to_string(),
+                target.discrepancy.as_ref().map_or(0, |found| found.undeleted_files).to_string(),
+                csv_field(&locale::format_size(target.stats.freed_bytes, config)),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+// Write the whole history to `path`, as CSV when it ends in .csv and as JSON
+// otherwise
+pub fn export(path: &Path, config: &Config) -> Result<(), String> {
+    let runs = recent(MAX_RUNS);
+    let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let text = if is_csv {
+        to_csv(&runs, config)
+    } else {
+        serde_json::to_string_pretty(&runs).map_err(|e| format!("Failed to serialize history: {}", e))?
+    };
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A rescan of a target right after its clean, for unattended machines where
+// nobody looks at the folders: files that came straight back, and files the
+// clean counted as deleted that are still there
+
+// Paths of undeleted files kept with the run
+const LISTED_UNDELETED: usize = 10;
+
+// File systems stamp files from a coarser clock than SystemTime::now, so a
+// file made right after the clean started can look slightly older
+const CLOCK_SLACK: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Discrepancy {
+    // Files created in the target's folders since the clean started
+    pub reappeared_files: u64,
+    pub reappeared_bytes: u64,
+    // Of the deleted files remembered for the check, those still there and
+    // older than the clean
+    pub undeleted_files: u64,
+    pub undeleted: Vec<PathBuf>,
+}
+
+impl Discrepancy {
+    pub fn is_empty(&self) -> bool {
+        self.reappeared_files == 0 && self.undeleted_files == 0
+    }
+}
+
+// Creation time where the file system keeps one, else the last write
+fn created(meta: &fs::Metadata) -> SystemTime {
+    meta.created().or_else(|_| meta.modified()).unwrap_or(UNIX_EPOCH)
+}
+
+// Count the files below `path` created at or after `since`. Symlinks and
+// junctions are not followed.
+fn count_new(path: &Path, since: SystemTime, found: &mut Discrepancy) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if meta.is_file() {
+        if created(&meta) >= since {
+            found.reappeared_files += 1;
+            found.reappeared_bytes += meta.len();
+        }
+    } else if meta.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            count_new(&entry.path(), since, found);
+        }
+    }
+}
+
+// Check a target cleaned from `since`, given its paths and files the clean
+// reported deleted
+pub fn check(paths: &[PathBuf], deleted: &[PathBuf], since: SystemTime) -> Discrepancy {
+    let since = since - CLOCK_SLACK;
+    let mut found = Discrepancy::default();
+    for path in paths {
+        count_new(&crate::cleaner::verbatim(path), since, &mut found);
+    }
+    // Recreated files were counted above
+    let undeleted = deleted
+        .iter()
+        .filter(|path| fs::symlink_metadata(path).is_ok_and(|meta| created(&meta) < since));
+    for path in undeleted {
+        found.undeleted_files += 1;
+        if found.undeleted.len() < LISTED_UNDELETED {
+            let display = path.display().to_string();
+            found.undeleted.push(PathBuf::from(display.trim_start_matches(r"\\?\")));
+        }
+    }
+    found
+}
@@ -0,0 +1,1610 @@
+use crate::staging::{self, StagedFile};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
+
+const MB: u64 = 1024 * 1024;
+
+// How a target's files are chosen for deletion
+#[derive(Clone, Copy, PartialEq)]
+pub enum Strategy {
+    DeleteAll,
+    // Files modified more recently than this are left alone
+    DeleteOlderThan(Duration),
+    // Keep the N most recently modified files
+    KeepNewestN(usize),
+    // Delete the least recently used files until the target fits in this many bytes
+    TrimToSize(u64),
+}
+
+impl Strategy {
+    // Short note shown next to the target name; None for plain wipes
+    pub fn describe(&self) -> Option<String> {
+        match *self {
+            Strategy::DeleteAll => None,
+            Strategy::DeleteOlderThan(age) => {
+                Some(format!("older than {} days", age.as_secs() / (24 * 60 * 60)))
+            }
+            Strategy::KeepNewestN(count) => Some(format!("keeps newest {}", count)),
+            Strategy::TrimToSize(bytes) => Some(format!("trimmed to {} MiB", bytes / MB)),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct DirStats {
+    pub size_bytes: u64,
+    pub file_count: u64,
+    // Bytes per lowercase file extension ("" for files without one)
+    pub by_extension: BTreeMap<String, u64>,
+    // Set when sampling or a depth limit means the figures are not exact
+    pub approximate: bool,
+    // Directories that could not be listed for lack of permission
+    pub denied_dirs: u64,
+}
+
+impl DirStats {
+    pub fn add(&mut self, other: DirStats) {
+        self.size_bytes += other.size_bytes;
+        self.file_count += other.file_count;
+        for (extension, bytes) in other.by_extension {
+            *self.by_extension.entry(extension).or_default() += bytes;
+        }
+        self.approximate |= other.approximate;
+        self.denied_dirs += other.denied_dirs;
+    }
+
+    // Extrapolate from a sample of a directory's entries
+    fn scale(&mut self, factor: f64) {
+        self.size_bytes = (self.size_bytes as f64 * factor) as u64;
+        self.file_count = (self.file_count as f64 * factor) as u64;
+        for bytes in self.by_extension.values_mut() {
+            *bytes = (*bytes as f64 * factor) as u64;
+        }
+        self.approximate = true;
+    }
+
+    fn add_file(&mut self, path: &Path, size: u64) {
+        self.size_bytes += size;
+        self.file_count += 1;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        *self.by_extension.entry(extension).or_default() += size;
+    }
+}
+
+// Bounds for sizing very large trees
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct ScanLimits {
+    // Directory levels below the target path to descend into
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    // Size only a sample of the entries in large directories
+    #[serde(default)]
+    pub estimate: bool,
+    // Reuse a whole subtree's last size while its top directory's
+    // modification time and entry count are unchanged. Much faster on caches
+    // that rarely change, but misses changes further down until a full rescan.
+    #[serde(default)]
+    pub skip_unchanged: bool,
+    // Add entries up as they are read instead of listing directories first,
+    // so memory stays bounded on trees with millions of files. Every file is
+    // sized, without sampling, and listings are not kept for the next scan.
+    #[serde(default)]
+    pub streaming: bool,
+}
+
+// Directories with more entries than this are sampled in estimate mode
+const SAMPLE_SIZE: usize = 256;
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct CleanStats {
+    pub freed_bytes: u64,
+    pub files_deleted: u64,
+    pub files_failed: u64,
+    // Left in place for being newer than the minimum age
+    pub files_skipped: u64,
+    // The size of the failed and skipped files; absent from older history
+    #[serde(default)]
+    pub failed_bytes: u64,
+    #[serde(default)]
+    pub skipped_bytes: u64,
+}
+
+impl CleanStats {
+    pub fn add(&mut self, other: CleanStats) {
+        self.freed_bytes += other.freed_bytes;
+        self.files_deleted += other.files_deleted;
+        self.files_failed += other.files_failed;
+        self.files_skipped += other.files_skipped;
+        self.failed_bytes += other.failed_bytes;
+        self.skipped_bytes += other.skipped_bytes;
+    }
+}
+
+// Win32 path normalisation strips trailing dots and spaces and maps reserved
+// names like "aux.txt" to devices, so such files are only reachable through a
+// verbatim (\\?\) path. Verbatim paths also lift the MAX_PATH limit.
+#[cfg(target_os = "windows")]
+pub fn verbatim(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::path::{Component, PathBuf, Prefix};
+
+    let mut components = path.components();
+    let mut verbatim = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:", letter as char)),
+            Prefix::UNC(server, share) => {
+                let mut unc = OsString::from(r"\\?\UNC\");
+                unc.push(server);
+                unc.push(r"\");
+                unc.push(share);
+                PathBuf::from(unc)
+            }
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+
+    // Verbatim paths are not normalised, so ".." would be taken literally
+    for component in components {
+        if component == Component::ParentDir {
+            return Cow::Borrowed(path);
+        }
+        verbatim.push(component);
+    }
+    Cow::Owned(verbatim)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn verbatim(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+// Files and folders a target's clean leaves alone: those matching a glob
+// pattern, and with a minimum age, files used more recently than that. A
+// pattern without a slash matches names anywhere below the target ("*.log",
+// "GPUCache"); one with a slash matches trailing path components
+// ("Cache/index"). Backslashes count as slashes. With an owner, files and
+// folders of other users are kept too.
+#[derive(Clone, Default)]
+pub struct Exclusions {
+    names: GlobSet,
+    paths: GlobSet,
+    min_age: Option<Duration>,
+    owner: Option<u32>,
+}
+
+fn compile_glob(pattern: &str) -> Result<Glob, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(cfg!(target_os = "windows"))
+        .build()
+        .map_err(|e| format!("Invalid exclusion pattern \"{}\": {}", pattern, e))
+}
+
+impl Exclusions {
+    // Patterns that fail to parse are skipped; check them with validate first
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Self {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let pattern = pattern.trim().replace('\\', "/");
+            if pattern.is_empty() {
+                continue;
+            }
+            if pattern.contains('/') {
+                let anchored = format!("**/{}", pattern.trim_start_matches('/'));
+                if let Ok(glob) = compile_glob(&anchored) {
+                    paths.add(glob);
+                }
+            } else if let Ok(glob) = compile_glob(&pattern) {
+                names.add(glob);
+            }
+        }
+        Self {
+            names: names.build().unwrap_or_default(),
+            paths: paths.build().unwrap_or_default(),
+            min_age: None,
+            owner: None,
+        }
+    }
+
+    pub fn with_min_age(mut self, min_age: Option<Duration>) -> Self {
+        self.min_age = min_age;
+        self
+    }
+
+    // Keep what a user other than `uid` owns
+    pub fn with_owner(mut self, uid: Option<u32>) -> Self {
+        self.owner = uid;
+        self
+    }
+
+    pub fn owner(&self) -> Option<u32> {
+        self.owner
+    }
+
+    #[cfg(unix)]
+    pub fn keeps_owner_of(&self, meta: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        self.owner.is_some_and(|uid| meta.uid() != uid)
+    }
+
+    #[cfg(not(unix))]
+    pub fn keeps_owner_of(&self, _meta: &fs::Metadata) -> bool {
+        false
+    }
+
+    pub fn validate<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+        for pattern in patterns {
+            compile_glob(&pattern.trim().replace('\\', "/"))?;
+        }
+        Ok(())
+    }
+
+    // True when nothing under a target is kept
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.paths.is_empty() && self.min_age.is_none() && self.owner.is_none()
+    }
+
+    // True when the file was used more recently than the minimum age
+    pub fn is_too_recent(&self, last_used: SystemTime) -> bool {
+        self.min_age.is_some_and(|min_age| used_within(last_used, min_age))
+    }
+
+    pub fn excludes(&self, path: &Path) -> bool {
+        if self.names.is_empty() && self.paths.is_empty() {
+            return false;
+        }
+        if path.file_name().is_some_and(|name| self.names.is_match(name)) {
+            return true;
+        }
+        let display = path.to_string_lossy();
+        !self.paths.is_empty() && self.paths.is_match(display.trim_start_matches(r"\\?\").replace('\\', "/"))
+    }
+}
+
+// Names Windows maps to devices in every folder, whatever the extension
+#[cfg(target_os = "windows")]
+const DEVICE_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9", "lpt1",
+    "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// Refuse a path that came from outside the app (a custom folder, a reviewed
+// file list) and does not plainly name a file or folder: ".." segments that
+// climb out of where it appears to be, and on Windows device names, device
+// namespaces such as \\.\ and \\?\GLOBALROOT, and alternate data streams
+pub fn check_path(path: &Path) -> Result<(), String> {
+    for component in path.components() {
+        match component {
+            Component::ParentDir => return Err(format!("{} must not contain \"..\"", path.display())),
+            #[cfg(target_os = "windows")]
+            Component::Prefix(prefix) => {
+                use std::path::Prefix;
+                if !matches!(
+                    prefix.kind(),
+                    Prefix::Disk(_) | Prefix::VerbatimDisk(_) | Prefix::UNC(..) | Prefix::VerbatimUNC(..)
+                ) {
+                    return Err(format!("{} is a device path", path.display()));
+                }
+            }
+            #[cfg(target_os = "windows")]
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                if name.contains(':') {
+                    return Err(format!("{} names an alternate data stream", path.display()));
+                }
+                // Windows ignores the extension and trailing spaces: "nul .txt" is NUL too
+                let stem = name.split('.').next().unwrap_or_default().trim_end().to_lowercase();
+                if DEVICE_NAMES.contains(&stem.as_str()) {
+                    return Err(format!("{} contains the device name {}", path.display(), name));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// The app's own files (config, history, executable, WebView data). They are
+// never deleted, even when they sit under a target such as a temp folder.
+static PROTECTED_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// Verbatim form, lowercased on Windows where paths are case-insensitive
+pub fn comparable(path: &Path) -> PathBuf {
+    let path = verbatim(path);
+    #[cfg(target_os = "windows")]
+    return PathBuf::from(path.to_string_lossy().to_lowercase());
+    #[cfg(not(target_os = "windows"))]
+    return path.into_owned();
+}
+
+pub fn protect(path: &Path) {
+    PROTECTED_PATHS.lock().unwrap().push(comparable(path));
+}
+
+// The protected paths inside `root`, in comparable form
+pub fn protected_under(root: &Path) -> Vec<PathBuf> {
+    let root = comparable(root);
+    PROTECTED_PATHS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|protected| protected.starts_with(&root))
+        .cloned()
+        .collect()
+}
+
+// Folders holding space a clean already gave back, as far as the user is
+// concerned: the quarantine folder and the recycle bins. Scans step over them
+// when they meet them below a target's own paths, so they do not count
+// towards its size; a target whose paths are one of them still sizes it.
+static SKIPPED_IN_SCANS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+pub fn skip_in_scans(path: &Path) {
+    SKIPPED_IN_SCANS.lock().unwrap().push(comparable(path));
+}
+
+fn is_skipped_in_scans(dir: &Path) -> bool {
+    let skipped = SKIPPED_IN_SCANS.lock().unwrap();
+    !skipped.is_empty() && skipped.contains(&comparable(dir))
+}
+
+pub fn is_protected(path: &Path) -> bool {
+    let path = comparable(path);
+    PROTECTED_PATHS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|protected| path.starts_with(protected))
+}
+
+// Key for the volume `path` lives on (its mount root on Windows, the device
+// number elsewhere)
+#[cfg(target_os = "windows")]
+pub fn volume_id(path: &Path) -> Option<String> {
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetVolumePathNameW;
+
+    let mut root = [0u16; 1024];
+    unsafe { GetVolumePathNameW(&HSTRING::from(path.as_os_str()), &mut root).ok()? };
+    let len = root.iter().position(|&c| c == 0).unwrap_or(root.len());
+    Some(String::from_utf16_lossy(&root[..len]).to_lowercase())
+}
+
+#[cfg(unix)]
+pub fn volume_id(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|meta| meta.dev().to_string())
+}
+
+#[cfg(not(any(target_os = "windows", unix)))]
+pub fn volume_id(_path: &Path) -> Option<String> {
+    None
+}
+
+// Directories we may not list are counted, so callers can tell a partial
+// size from a complete one
+fn list_dir(path: &Path, stats: &mut DirStats) -> Option<fs::ReadDir> {
+    match fs::read_dir(path) {
+        Ok(entries) => Some(entries),
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                stats.denied_dirs += 1;
+            }
+            None
+        }
+    }
+}
+
+// Recursively size a directory. Symlinks and junctions are never followed,
+// and unreadable entries are skipped.
+pub fn scan_dir(path: &Path) -> DirStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    let mut stats = DirStats::default();
+    let Some(entries) = list_dir(path, &mut stats) else {
+        return stats;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if meta.is_dir() && !is_skipped_in_scans(&entry.path()) {
+            stats.add(scan_dir(&entry.path()));
+        } else if meta.is_file() {
+            stats.add_file(&entry.path(), meta.len());
+        }
+    }
+    stats
+}
+
+// Same result as scan_dir, with subdirectories at any depth spread across
+// threads
+pub fn scan_dir_parallel(path: &Path) -> DirStats {
+    let path = verbatim(path);
+    scan_tree(path.as_ref(), 0, &TreeScan::new(ScanLimits::default(), false))
+}
+
+// Running totals of the clean in flight, reported to the UI while it runs
+#[derive(Serialize, Default, Clone)]
+pub struct CleanProgress {
+    pub files_deleted: u64,
+    pub freed_bytes: u64,
+    // Random bytes written over files before deleting them
+    pub overwritten_bytes: u64,
+    pub current_dir: String,
+}
+
+// Where cleaned files go
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionMode {
+    #[default]
+    Permanent,
+    // The Recycle Bin on Windows, the Trash elsewhere; the space is only
+    // freed once it is emptied
+    RecycleBin,
+    // Contents overwritten with random bytes first, so they cannot be read
+    // back from the disk after deletion. SSDs and copy-on-write file systems
+    // may still keep the old blocks.
+    Overwrite,
+    // Moved to the app's quarantine folder, where they can be restored until
+    // the retention period ends
+    Staging,
+}
+
+// Bytes written at a time when overwriting
+const OVERWRITE_CHUNK: usize = 1024 * 1024;
+
+// What one clean shares between its threads: how it deletes files, whether
+// it was cancelled, how far it got and what it moved to the recycle bin.
+// Cleans from the window, schedules and low disk space can overlap, so each
+// has its own.
+pub struct CleanRun {
+    mode: DeletionMode,
+    // Checked between files, so a cancelled clean never leaves a file
+    // half-handled
+    cancelled: AtomicBool,
+    progress: Mutex<CleanProgress>,
+    recycled: Mutex<Vec<PathBuf>>,
+    staged: Mutex<Vec<StagedFile>>,
+}
+
+impl CleanRun {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Cleans the user started, from the window, the TUI or the command line,
+// which cancel_clean stops; automatic ones only stop for their time limit
+static USER_RUNS: Mutex<Vec<Weak<CleanRun>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // The clean this thread works for, if any
+    static RUN: RefCell<Option<Arc<CleanRun>>> = const { RefCell::new(None) };
+    // Files this thread failed to delete and why. Per thread because targets
+    // on different volumes are cleaned in parallel, one thread per volume,
+    // and each target's failures are collected right after it is cleaned.
+    static FAILED: RefCell<Vec<FailedDelete>> = const { RefCell::new(Vec::new()) };
+    // Files this thread deleted, up to MAX_REMEMBERED_DELETES, so a
+    // verification pass can check they are gone
+    static DELETED: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+// Deleted files remembered per target between take_deleted calls
+const MAX_REMEMBERED_DELETES: usize = 1000;
+
+// Why a file could not be deleted, for summing up a clean's failures
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    InUse,
+    AccessDenied,
+    ReadOnly,
+    Other,
+}
+
+impl FailureKind {
+    fn of(error: &std::io::Error) -> Self {
+        use std::io::ErrorKind;
+
+        // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION: open in a program
+        #[cfg(target_os = "windows")]
+        if matches!(error.raw_os_error(), Some(32 | 33)) {
+            return FailureKind::InUse;
+        }
+        match error.kind() {
+            ErrorKind::ResourceBusy | ErrorKind::ExecutableFileBusy => FailureKind::InUse,
+            ErrorKind::PermissionDenied => FailureKind::AccessDenied,
+            ErrorKind::ReadOnlyFilesystem => FailureKind::ReadOnly,
+            _ => FailureKind::Other,
+        }
+    }
+}
+
+pub struct FailedDelete {
+    pub path: PathBuf,
+    pub kind: FailureKind,
+    pub error: String,
+}
+
+// Start a clean on this thread that deletes files according to `mode`;
+// `by_user` for one cancel_clean may stop
+pub fn begin_batch(mode: DeletionMode, by_user: bool) -> Arc<CleanRun> {
+    let run = Arc::new(CleanRun {
+        mode,
+        cancelled: AtomicBool::new(false),
+        progress: Mutex::new(CleanProgress::default()),
+        recycled: Mutex::new(Vec::new()),
+        staged: Mutex::new(Vec::new()),
+    });
+    if by_user {
+        let mut runs = USER_RUNS.lock().unwrap();
+        runs.retain(|run| run.strong_count() > 0);
+        runs.push(Arc::downgrade(&run));
+    }
+    join_run(Some(run.clone()));
+    FAILED.with(|failed| failed.borrow_mut().clear());
+    DELETED.with(|deleted| deleted.borrow_mut().clear());
+    run
+}
+
+// End this thread's clean, returning the files it moved to the recycle bin
+// and those it moved to the quarantine folder
+pub fn end_batch() -> (Vec<PathBuf>, Vec<StagedFile>) {
+    RUN.with(|run| run.borrow_mut().take())
+        .map(|run| {
+            let recycled = std::mem::take(&mut *run.recycled.lock().unwrap());
+            let staged = std::mem::take(&mut *run.staged.lock().unwrap());
+            (recycled, staged)
+        })
+        .unwrap_or_default()
+}
+
+// The clean this thread works for, to hand to the threads it starts
+pub fn current_run() -> Option<Arc<CleanRun>> {
+    RUN.with(|run| run.borrow().clone())
+}
+
+// Work for `run` on this thread, started by the thread that began it
+pub fn join_run(run: Option<Arc<CleanRun>>) {
+    RUN.with(|current| *current.borrow_mut() = run);
+}
+
+fn with_run(action: impl FnOnce(&CleanRun)) {
+    RUN.with(|run| {
+        if let Some(run) = run.borrow().as_deref() {
+            action(run);
+        }
+    });
+}
+
+// The files this thread failed to delete since the last call, with the error
+pub fn take_failed() -> Vec<FailedDelete> {
+    FAILED.with(|failed| std::mem::take(&mut *failed.borrow_mut()))
+}
+
+// Files this thread deleted since the last call, the first
+// MAX_REMEMBERED_DELETES of them
+pub fn take_deleted() -> Vec<PathBuf> {
+    DELETED.with(|deleted| std::mem::take(&mut *deleted.borrow_mut()))
+}
+
+// Fill `path` with random bytes in place and flush them to the disk. The
+// bytes only have to differ from what was there, so a fast generator does.
+fn overwrite(path: &Path) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let len = file.metadata()?.len();
+    // xorshift64*, seeded differently for every file
+    let mut state = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+        ^ (len << 17)
+        | 1;
+    let mut chunk = vec![0u8; OVERWRITE_CHUNK.min(len as usize)];
+    let mut written = 0;
+    while written < len {
+        for word in chunk.chunks_mut(8) {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let random = state.wrapping_mul(0x2545_F491_4F6C_DD1D).to_le_bytes();
+            word.copy_from_slice(&random[..word.len()]);
+        }
+        let count = (len - written).min(chunk.len() as u64) as usize;
+        file.write_all(&chunk[..count])?;
+        written += count as u64;
+        with_run(|run| run.progress.lock().unwrap().overwritten_bytes += count as u64);
+    }
+    file.sync_all()
+}
+
+fn delete_file(path: &Path) -> bool {
+    // Deleted for good outside a clean, as by maintenance actions
+    let mode = current_run().map_or(DeletionMode::Permanent, |run| run.mode);
+    let result = match mode {
+        DeletionMode::Permanent => fs::remove_file(path).map_err(|e| (FailureKind::of(&e), e.to_string())),
+        DeletionMode::RecycleBin => trash::delete(path).map_err(|e| (FailureKind::Other, e.to_string())),
+        DeletionMode::Overwrite => overwrite(path)
+            .and_then(|()| fs::remove_file(path))
+            .map_err(|e| (FailureKind::of(&e), e.to_string())),
+        DeletionMode::Staging => staging::stage(path)
+            .map(|file| with_run(|run| run.staged.lock().unwrap().push(file)))
+            .map_err(|e| (FailureKind::of(&e), e.to_string())),
+    };
+    match result {
+        Ok(()) => {
+            if mode == DeletionMode::RecycleBin {
+                with_run(|run| run.recycled.lock().unwrap().push(path.to_path_buf()));
+            }
+            DELETED.with(|deleted| {
+                let mut deleted = deleted.borrow_mut();
+                if deleted.len() < MAX_REMEMBERED_DELETES {
+                    deleted.push(path.to_path_buf());
+                }
+            });
+            true
+        }
+        Err((kind, error)) => {
+            let display = path.display().to_string();
+            let path = PathBuf::from(display.trim_start_matches(r"\\?\"));
+            FAILED.with(|failed| failed.borrow_mut().push(FailedDelete { path, kind, error }));
+            false
+        }
+    }
+}
+
+// Stop every clean the user started after the file in progress
+pub fn cancel_clean() {
+    for run in USER_RUNS.lock().unwrap().iter().filter_map(Weak::upgrade) {
+        run.cancel();
+    }
+}
+
+// Whether this thread's clean was cancelled
+pub fn is_cancelled() -> bool {
+    current_run().is_some_and(|run| run.is_cancelled())
+}
+
+fn record_deleted(bytes: u64) {
+    with_run(|run| {
+        let mut progress = run.progress.lock().unwrap();
+        progress.files_deleted += 1;
+        progress.freed_bytes += bytes;
+    });
+}
+
+pub fn record_dir(path: &Path) {
+    let display = path.display().to_string();
+    with_run(|run| run.progress.lock().unwrap().current_dir = display.trim_start_matches(r"\\?\").to_string());
+}
+
+// Shown in place of the folder being cleaned while the clean waits
+pub fn record_waiting(reason: &str) {
+    with_run(|run| run.progress.lock().unwrap().current_dir = reason.to_string());
+}
+
+// Run this thread's clean, calling `report` with its progress every 200 ms
+// and once more when it finishes
+pub fn track_progress<T>(report: impl Fn(CleanProgress) + Sync, clean: impl FnOnce() -> T) -> T {
+    let run = current_run();
+    let progress = || run.as_ref().map(|run| run.progress.lock().unwrap().clone()).unwrap_or_default();
+
+    let done = AtomicBool::new(false);
+    let result = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                report(progress());
+                std::thread::sleep(Duration::from_millis(200));
+            }
+        });
+        let result = clean();
+        done.store(true, Ordering::Relaxed);
+        result
+    });
+    report(progress());
+    result
+}
+
+fn used_within(time: SystemTime, min_age: Duration) -> bool {
+    SystemTime::now().duration_since(time).is_ok_and(|age| age < min_age)
+}
+
+// Later of access and modification time; access times alone are often stale
+// (noatime/relatime mounts, NTFS last-access updates disabled)
+pub fn last_used(meta: &fs::Metadata) -> SystemTime {
+    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    meta.accessed().map_or(modified, |accessed| accessed.max(modified))
+}
+
+// True when the file was modified less than `min_age` ago
+fn is_too_recent(meta: &fs::Metadata, min_age: Option<Duration>) -> bool {
+    let Some(min_age) = min_age else {
+        return false;
+    };
+    meta.modified().is_ok_and(|modified| used_within(modified, min_age))
+}
+
+// Delete everything inside `path`, keeping the directory itself. Files that
+// are in use or protected are counted as failed and left in place. With
+// `min_age`, files modified more recently than that are kept, as are files
+// and folders matching `exclusions` and files newer than its minimum age.
+// Only regular files go: sockets, pipes and device nodes stay.
+pub fn clean_dir(path: &Path, min_age: Option<Duration>, exclusions: &Exclusions) -> CleanStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    let mut stats = CleanStats::default();
+    let Ok(entries) = fs::read_dir(path) else {
+        return stats;
+    };
+    record_dir(path);
+
+    for entry in entries.flatten() {
+        if is_cancelled() {
+            break;
+        }
+        let entry_path = entry.path();
+        if is_protected(&entry_path) || exclusions.excludes(&entry_path) {
+            continue;
+        }
+        let Ok(meta) = fs::symlink_metadata(&entry_path) else {
+            continue;
+        };
+        if exclusions.keeps_owner_of(&meta) {
+            continue;
+        }
+
+        if meta.is_dir() {
+            stats.add(clean_dir(&entry_path, min_age, exclusions));
+            // Only succeeds once the subtree is empty
+            let _ = fs::remove_dir(&entry_path);
+        } else if meta.is_symlink() {
+            // Remove the link itself, never its target
+            if fs::remove_file(&entry_path).is_err() {
+                let _ = fs::remove_dir(&entry_path);
+            }
+        } else if meta.is_file() {
+            remove_file(&entry_path, &meta, min_age, exclusions, &mut stats);
+        }
+    }
+    stats
+}
+
+pub fn remove_file(
+    path: &Path,
+    meta: &fs::Metadata,
+    min_age: Option<Duration>,
+    exclusions: &Exclusions,
+    stats: &mut CleanStats,
+) {
+    if is_too_recent(meta, min_age) || exclusions.is_too_recent(last_used(meta)) {
+        stats.files_skipped += 1;
+        stats.skipped_bytes += meta.len();
+        return;
+    }
+    if delete_file(path) {
+        stats.freed_bytes += meta.len();
+        stats.files_deleted += 1;
+        record_deleted(meta.len());
+    } else {
+        stats.files_failed += 1;
+        stats.failed_bytes += meta.len();
+    }
+}
+
+// Empty `path` by moving it aside and recreating it, so a program using the
+// directory finds it either untouched or empty, never half deleted. When it
+// cannot be moved (files in it are open on Windows) nothing is deleted and
+// its files count as failed. Files that cannot be deleted once it is moved
+// stay in the moved folder, which the next reset of `path` empties first.
+pub fn reset_dir(path: &Path) -> CleanStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    let mut stats = CleanStats::default();
+    let Some(name) = path.file_name() else {
+        return stats;
+    };
+    if is_protected(path) || !path.is_dir() {
+        return stats;
+    }
+    record_dir(path);
+    let swept = sweep_aside(path);
+
+    let mut aside_name = name.to_os_string();
+    aside_name.push(format!(
+        ".cleaning-{}-{}",
+        std::process::id(),
+        RESETS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let aside = path.with_file_name(aside_name);
+    let mut fail_all = |dir: &Path| {
+        let kept = scan_path(dir);
+        stats.files_failed = kept.file_count;
+        stats.failed_bytes = kept.size_bytes;
+        stats.add(swept);
+        stats
+    };
+    if fs::rename(path, &aside).is_err() {
+        return fail_all(path);
+    }
+    if fs::create_dir(path).is_err() {
+        // Put it back rather than leave the program without its directory
+        let kept = if fs::rename(&aside, path).is_ok() { path } else { aside.as_path() };
+        return fail_all(kept);
+    }
+    stats = clean_dir(&aside, None, &Exclusions::default());
+    let _ = fs::remove_dir(&aside);
+    stats.add(swept);
+    stats
+}
+
+// Numbers the folders reset_dir moves aside, so one left over from an earlier
+// reset in this process is never in the way
+static RESETS: AtomicUsize = AtomicUsize::new(0);
+
+// Empty and remove what earlier resets of `path` left beside it: files that
+// were open at the time, or the whole folder when the app stopped midway
+fn sweep_aside(path: &Path) -> CleanStats {
+    let mut stats = CleanStats::default();
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return stats;
+    };
+    let prefix = format!("{}.cleaning-", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(parent) else {
+        return stats;
+    };
+    for entry in entries.flatten() {
+        let is_aside = entry.file_name().to_string_lossy().starts_with(&prefix);
+        if is_aside && entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            stats.add(clean_dir(&entry.path(), None, &Exclusions::default()));
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+    stats
+}
+
+// One tree scan: its limits, the threads it may still add beside the one
+// that started it, and whether it may reuse DIR_CACHE
+struct TreeScan {
+    limits: ScanLimits,
+    spare: AtomicUsize,
+    cached: bool,
+}
+
+impl TreeScan {
+    fn new(limits: ScanLimits, cached: bool) -> Self {
+        Self {
+            limits,
+            spare: AtomicUsize::new(std::thread::available_parallelism().map_or(4, |n| n.get()) - 1),
+            cached,
+        }
+    }
+
+    // Take one of the spare threads, if any is left
+    fn claim_thread(&self) -> bool {
+        self.spare
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+// What a directory held directly when it was last listed in full: the
+// totals of its files and its subdirectories
+#[derive(Clone)]
+struct DirListing {
+    files: DirStats,
+    subdirs: Vec<PathBuf>,
+}
+
+// Listings reused while the directory's modification time is unchanged.
+// Adding, removing or renaming an entry updates that time, so repeated scans
+// only stat the files of directories that changed; a file rewritten in place
+// does not, which is what forget_dir_sizes is for.
+static DIR_CACHE: Mutex<BTreeMap<PathBuf, (SystemTime, DirListing)>> = Mutex::new(BTreeMap::new());
+
+// Subtree totals for scans with skip_unchanged, with the modification time
+// and entry count of the subtree's top directory when they were taken
+static SUBTREE_CACHE: Mutex<BTreeMap<PathBuf, (SystemTime, usize, DirStats)>> = Mutex::new(BTreeMap::new());
+
+// Drop every cached listing, so the next scan stats every file again
+pub fn forget_dir_sizes() {
+    DIR_CACHE.lock().unwrap().clear();
+    SUBTREE_CACHE.lock().unwrap().clear();
+}
+
+// The modification time and entry count of `path`; counting only reads the
+// directory, without a stat of each entry
+fn subtree_key(path: &Path) -> Option<(SystemTime, usize)> {
+    let modified = fs::symlink_metadata(path).and_then(|meta| meta.modified()).ok()?;
+    Some((modified, fs::read_dir(path).ok()?.count()))
+}
+
+// List `path`, sampling large directories in estimate mode. Returns the
+// listing and the factor it is to be scaled by (1 when nothing was skipped).
+fn list_tree_dir(path: &Path, limits: ScanLimits, stats: &mut DirStats) -> Option<(DirListing, f64)> {
+    let entries: Vec<_> = list_dir(path, stats)?.flatten().collect();
+    // Every step-th entry stands in for the ones skipped around it
+    let step = if limits.estimate && entries.len() > SAMPLE_SIZE {
+        entries.len() / SAMPLE_SIZE
+    } else {
+        1
+    };
+    let mut listing = DirListing {
+        files: DirStats::default(),
+        subdirs: Vec::new(),
+    };
+    for entry in entries.iter().step_by(step) {
+        let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if meta.is_dir() {
+            if !is_skipped_in_scans(&entry.path()) {
+                listing.subdirs.push(entry.path());
+            }
+        } else if meta.is_file() {
+            listing.files.add_file(&entry.path(), meta.len());
+        }
+    }
+    let sampled = entries.len().div_ceil(step);
+    let scale = if step > 1 { entries.len() as f64 / sampled as f64 } else { 1.0 };
+    Some((listing, scale))
+}
+
+// The cached listing of `path` if it is still current, else a fresh one,
+// cached when it is complete
+fn tree_dir(path: &Path, scan: &TreeScan, stats: &mut DirStats) -> Option<(DirListing, f64)> {
+    if !scan.cached {
+        return list_tree_dir(path, scan.limits, stats);
+    }
+    let modified = fs::symlink_metadata(path).and_then(|meta| meta.modified()).ok();
+    if let Some(modified) = modified {
+        if let Some((_, listing)) = DIR_CACHE.lock().unwrap().get(path).filter(|(at, _)| *at == modified) {
+            return Some((listing.clone(), 1.0));
+        }
+    }
+    let (listing, scale) = list_tree_dir(path, scan.limits, stats)?;
+    // A sampled listing would hide the entries it skipped from later scans
+    if let Some(modified) = modified.filter(|_| scale == 1.0) {
+        DIR_CACHE.lock().unwrap().insert(path.to_path_buf(), (modified, listing.clone()));
+    }
+    Some((listing, scale))
+}
+
+// Size a directory within the scan's limits. While spare threads are left,
+// each subdirectory gets one of its own, so a deep tree keeps every thread
+// busy rather than only its top level; otherwise it is walked on this
+// thread. Each thread returns its subtree's totals, added to its parent's.
+fn scan_tree(path: &Path, depth: usize, scan: &TreeScan) -> DirStats {
+    let key = if scan.cached && scan.limits.skip_unchanged {
+        subtree_key(path)
+    } else {
+        None
+    };
+    if let Some(key) = key {
+        let cache = SUBTREE_CACHE.lock().unwrap();
+        if let Some((_, _, stats)) = cache.get(path).filter(|(at, count, _)| (*at, *count) == key) {
+            return stats.clone();
+        }
+    }
+
+    let mut stats = DirStats::default();
+    let Some((listing, scale)) = tree_dir(path, scan, &mut stats) else {
+        return stats;
+    };
+    stats.add(listing.files);
+
+    let limits = scan.limits;
+    if limits.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+        stats.approximate |= !listing.subdirs.is_empty();
+    } else {
+        std::thread::scope(|scope| {
+            let mut workers = Vec::new();
+            for subdir in &listing.subdirs {
+                if scan.claim_thread() {
+                    workers.push(scope.spawn(move || {
+                        let subdir = scan_tree(subdir, depth + 1, scan);
+                        scan.spare.fetch_add(1, Ordering::Relaxed);
+                        subdir
+                    }));
+                } else {
+                    stats.add(scan_tree(subdir, depth + 1, scan));
+                }
+            }
+            for worker in workers {
+                if let Ok(subdir) = worker.join() {
+                    stats.add(subdir);
+                }
+            }
+        });
+    }
+
+    if scale > 1.0 {
+        stats.scale(scale);
+    }
+    // Approximate totals depend on the depth they were taken at
+    if let Some((modified, count)) = key.filter(|_| !stats.approximate) {
+        SUBTREE_CACHE.lock().unwrap().insert(path.to_path_buf(), (modified, count, stats.clone()));
+    }
+    stats
+}
+
+// Size a directory in streaming mode: entries are added up as they are read
+// and subdirectories walked as they are met, so memory grows with the depth
+// of the tree, not its width. Spare threads take subdirectories as in
+// scan_tree.
+fn scan_stream(path: &Path, depth: usize, scan: &TreeScan) -> DirStats {
+    let mut stats = DirStats::default();
+    let Some(entries) = list_dir(path, &mut stats) else {
+        return stats;
+    };
+    let descend = scan.limits.max_depth.is_none_or(|max_depth| depth < max_depth);
+    std::thread::scope(|scope| {
+        let mut workers = Vec::new();
+        for entry in entries.flatten() {
+            // Neither follows symlinks, and both usually come with the listing
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if is_skipped_in_scans(&entry.path()) {
+                    continue;
+                }
+                if !descend {
+                    stats.approximate = true;
+                } else if scan.claim_thread() {
+                    let subdir = entry.path();
+                    workers.push(scope.spawn(move || {
+                        let subdir = scan_stream(&subdir, depth + 1, scan);
+                        scan.spare.fetch_add(1, Ordering::Relaxed);
+                        subdir
+                    }));
+                } else {
+                    stats.add(scan_stream(&entry.path(), depth + 1, scan));
+                }
+            } else if file_type.is_file() {
+                if let Ok(meta) = entry.metadata() {
+                    stats.add_file(&entry.path(), meta.len());
+                }
+            }
+        }
+        for worker in workers {
+            if let Ok(subdir) = worker.join() {
+                stats.add(subdir);
+            }
+        }
+    });
+    stats
+}
+
+// Like scan_path, but bounded by `limits`; the result is flagged approximate
+// whenever the limits left part of the tree unvisited. Directories that did
+// not change since the last call are not listed again, except in streaming
+// mode.
+pub fn scan_path_limited(path: &Path, limits: ScanLimits) -> DirStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() && limits.streaming => scan_stream(path, 0, &TreeScan::new(limits, false)),
+        Ok(meta) if meta.is_dir() => scan_tree(path, 0, &TreeScan::new(limits, true)),
+        _ => scan_path(path),
+    }
+}
+
+// Targets may list individual files as well as directories
+pub fn scan_path(path: &Path) -> DirStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    let mut stats = DirStats::default();
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_file() => stats.add_file(path, meta.len()),
+        Ok(meta) if meta.is_dir() => stats = scan_dir_parallel(path),
+        _ => {}
+    }
+    stats
+}
+
+fn clean_path(path: &Path, min_age: Option<Duration>, exclusions: &Exclusions) -> CleanStats {
+    let path = verbatim(path);
+    let path = path.as_ref();
+    let mut stats = CleanStats::default();
+    if is_protected(path) {
+        return stats;
+    }
+    match fs::symlink_metadata(path) {
+        Ok(meta) if exclusions.keeps_owner_of(&meta) => {}
+        Ok(meta) if meta.is_file() => remove_file(path, &meta, min_age, exclusions, &mut stats),
+        Ok(meta) if meta.is_dir() => stats = clean_dir(path, min_age, exclusions),
+        _ => {}
+    }
+    stats
+}
+
+struct CandidateFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    last_used: SystemTime,
+}
+
+// Every regular file under `path` (or `path` itself when it is a file),
+// except excluded ones below it
+fn collect_files(path: &Path, exclusions: &Exclusions, files: &mut Vec<CandidateFile>) {
+    if is_protected(path) {
+        return;
+    }
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if exclusions.keeps_owner_of(&meta) {
+        return;
+    }
+    if meta.is_file() {
+        files.push(CandidateFile {
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            last_used: last_used(&meta),
+        });
+    } else if meta.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if !exclusions.excludes(&entry.path()) {
+                collect_files(&entry.path(), exclusions, files);
+            }
+        }
+    }
+}
+
+// Remove directories left empty below `path`, keeping `path` itself and
+// excluded directories
+fn remove_empty_dirs(path: &Path, exclusions: &Exclusions) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if exclusions.excludes(&entry_path) {
+            continue;
+        }
+        if fs::symlink_metadata(&entry_path).is_ok_and(|meta| meta.is_dir()) {
+            remove_empty_dirs(&entry_path, exclusions);
+            let _ = fs::remove_dir(&entry_path);
+        }
+    }
+}
+
+// Of the files under a target, those its strategy deletes. Strategies that
+// keep some files rank them across every path of the target together.
+fn files_to_delete(mut files: Vec<CandidateFile>, strategy: Strategy) -> Vec<CandidateFile> {
+    let (keep_count, keep_bytes) = match strategy {
+        Strategy::DeleteAll => return files,
+        Strategy::DeleteOlderThan(min_age) => {
+            files.retain(|file| !used_within(file.modified, min_age));
+            return files;
+        }
+        Strategy::KeepNewestN(count) => (count, u64::MAX),
+        Strategy::TrimToSize(bytes) => (usize::MAX, bytes),
+    };
+
+    // Most recently used (or modified, when counting) first; once one file
+    // does not fit, every file ranked after it goes too
+    if matches!(strategy, Strategy::TrimToSize(_)) {
+        files.sort_by_key(|file| Reverse(file.last_used));
+    } else {
+        files.sort_by_key(|file| Reverse(file.modified));
+    }
+    let mut kept_bytes = 0u64;
+    let mut kept_count = 0usize;
+    files.retain(|file| {
+        if kept_count < keep_count && kept_bytes + file.size <= keep_bytes {
+            kept_count += 1;
+            kept_bytes += file.size;
+            return false;
+        }
+        kept_count = keep_count;
+        true
+    });
+    files
+}
+
+// A file that cleaning a target would delete
+#[derive(Serialize)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub size: u64,
+    // Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+// The files clean_target would delete for these paths, without deleting any
+pub fn preview_target(paths: &[PathBuf], strategy: Strategy, exclusions: &Exclusions) -> Vec<Candidate> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_files(&verbatim(path), exclusions, &mut files);
+    }
+    files_to_delete(files, strategy)
+        .into_iter()
+        .filter(|file| !exclusions.is_too_recent(file.last_used))
+        .map(|file| Candidate {
+            path: file.path,
+            size: file.size,
+            modified: file
+                .modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        })
+        .collect()
+}
+
+// Whether a folder between `root` and `file` is a link or junction, or
+// gone, so that `file` may really be somewhere outside `root`
+fn linked_below(root: &Path, file: &Path) -> bool {
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| comparable(dir) != root)
+        .any(|dir| fs::symlink_metadata(verbatim(dir)).map_or(true, |meta| meta.is_symlink()))
+}
+
+// Delete exactly the files a user reviewed from preview_target. Files that
+// are not under one of `roots` (by their path, and with no link or junction
+// on the way down to them), are protected, or are excluded (themselves or
+// through a folder between them and the root) are left alone.
+pub fn clean_files(roots: &[PathBuf], files: &[PathBuf], exclusions: &Exclusions) -> CleanStats {
+    let allowed: Vec<_> = roots.iter().map(|root| comparable(root)).collect();
+    let mut stats = CleanStats::default();
+    for file in files {
+        if is_cancelled() {
+            break;
+        }
+        let inside = check_path(file).is_ok()
+            && allowed
+                .iter()
+                .any(|root| comparable(file).starts_with(root) && !linked_below(root, file));
+        let excluded = file
+            .ancestors()
+            .take_while(|dir| !allowed.contains(&comparable(dir)))
+            .any(|dir| exclusions.excludes(dir));
+        if !inside || excluded || is_protected(file) {
+            continue;
+        }
+
+        let path = verbatim(file);
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_file() {
+            if let Some(dir) = path.parent() {
+                record_dir(dir);
+            }
+            remove_file(&path, &meta, None, exclusions, &mut stats);
+        }
+    }
+
+    for root in roots {
+        remove_empty_dirs(&verbatim(root), exclusions);
+    }
+    stats
+}
+
+// Clean all of a target's paths according to its strategy
+pub fn clean_target(paths: &[PathBuf], strategy: Strategy, exclusions: &Exclusions) -> CleanStats {
+    let mut stats = CleanStats::default();
+    match strategy {
+        Strategy::DeleteAll => {
+            for path in paths {
+                stats.add(clean_path(path, None, exclusions));
+            }
+            return stats;
+        }
+        Strategy::DeleteOlderThan(min_age) => {
+            for path in paths {
+                stats.add(clean_path(path, Some(min_age), exclusions));
+            }
+            return stats;
+        }
+        Strategy::KeepNewestN(_) | Strategy::TrimToSize(_) => {}
+    }
+
+    let roots: Vec<_> = paths.iter().map(|path| verbatim(path).into_owned()).collect();
+    let mut files = Vec::new();
+    for root in &roots {
+        collect_files(root, exclusions, &mut files);
+    }
+
+    for file in files_to_delete(files, strategy) {
+        if is_cancelled() {
+            break;
+        }
+        if exclusions.is_too_recent(file.last_used) {
+            stats.files_skipped += 1;
+            stats.skipped_bytes += file.size;
+            continue;
+        }
+        if let Some(dir) = file.path.parent() {
+            record_dir(dir);
+        }
+        if delete_file(&file.path) {
+            stats.freed_bytes += file.size;
+            stats.files_deleted += 1;
+            record_deleted(file.size);
+        } else {
+            stats.files_failed += 1;
+            stats.failed_bytes += file.size;
+        }
+    }
+
+    for root in &roots {
+        remove_empty_dirs(root, exclusions);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60;
+
+    fn file(name: &str, size: u64, modified_secs_ago: u64, used_secs_ago: u64) -> CandidateFile {
+        let now = SystemTime::now();
+        CandidateFile {
+            path: PathBuf::from(name),
+            size,
+            modified: now - Duration::from_secs(modified_secs_ago),
+            last_used: now - Duration::from_secs(used_secs_ago),
+        }
+    }
+
+    fn names(files: Vec<CandidateFile>) -> Vec<String> {
+        let mut names: Vec<_> = files.into_iter().map(|file| file.path.display().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    // An empty folder of the test's own below the temp folder
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cache-manager-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn delete_all_takes_every_file() {
+        let files = vec![file("a", 1, 0, 0), file("b", 1, HOUR, HOUR)];
+        assert_eq!(names(files_to_delete(files, Strategy::DeleteAll)), ["a", "b"]);
+    }
+
+    #[test]
+    fn delete_older_than_keeps_recently_modified_files() {
+        let files = vec![file("old", 1, 48 * HOUR, 0), file("new", 1, HOUR, HOUR)];
+        let deleted = files_to_delete(files, Strategy::DeleteOlderThan(Duration::from_secs(24 * HOUR)));
+        assert_eq!(names(deleted), ["old"]);
+    }
+
+    #[test]
+    fn keep_newest_n_keeps_the_most_recently_modified() {
+        let files = vec![
+            file("c", 1, 3 * HOUR, 0),
+            file("a", 1, HOUR, 0),
+            file("d", 1, 4 * HOUR, 0),
+            file("b", 1, 2 * HOUR, 0),
+        ];
+        assert_eq!(names(files_to_delete(files, Strategy::KeepNewestN(2))), ["c", "d"]);
+    }
+
+    #[test]
+    fn keep_newest_n_keeps_exactly_n_of_equally_old_files() {
+        let mut files: Vec<_> = (0..5).map(|i| file(&format!("f{}", i), 1, HOUR, HOUR)).collect();
+        let modified = files[0].modified;
+        for file in &mut files {
+            file.modified = modified;
+        }
+        assert_eq!(files_to_delete(files, Strategy::KeepNewestN(2)).len(), 3);
+    }
+
+    #[test]
+    fn keep_newest_zero_deletes_every_file() {
+        let files = vec![file("a", 1, 0, 0), file("b", 1, HOUR, HOUR)];
+        assert_eq!(files_to_delete(files, Strategy::KeepNewestN(0)).len(), 2);
+    }
+
+    #[test]
+    fn trim_to_size_deletes_least_recently_used_first() {
+        // Use counts, not modification: "a" was written long ago but read just now
+        let files = vec![
+            file("a", 40, 100 * HOUR, 0),
+            file("b", 40, HOUR, 2 * HOUR),
+            file("c", 40, HOUR, 3 * HOUR),
+        ];
+        assert_eq!(names(files_to_delete(files, Strategy::TrimToSize(100))), ["c"]);
+    }
+
+    #[test]
+    fn trim_to_size_deletes_everything_ranked_after_a_file_that_does_not_fit() {
+        let files = vec![file("a", 60, 0, HOUR), file("b", 50, 0, 2 * HOUR), file("c", 10, 0, 3 * HOUR)];
+        assert_eq!(names(files_to_delete(files, Strategy::TrimToSize(100))), ["b", "c"]);
+    }
+
+    #[test]
+    fn trim_to_zero_bytes_deletes_every_file() {
+        let files = vec![file("a", 1, 0, 0), file("b", 10, HOUR, HOUR)];
+        assert_eq!(files_to_delete(files, Strategy::TrimToSize(0)).len(), 2);
+    }
+
+    #[test]
+    fn strategies_rank_the_files_of_every_path_together() {
+        let dir = scratch("rank");
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let now = SystemTime::now();
+        let files = [(first.join("old"), 3 * HOUR), (second.join("new"), HOUR), (second.join("older"), 5 * HOUR)];
+        for (path, secs_ago) in files {
+            fs::File::create(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(secs_ago))
+                .unwrap();
+        }
+
+        let deleted = preview_target(&[first, second], Strategy::KeepNewestN(1), &Exclusions::default());
+        let mut names: Vec<_> = deleted.iter().map(|file| file.path.file_name().unwrap().to_owned()).collect();
+        names.sort();
+        assert_eq!(names, ["old", "older"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn clean_dir_keeps_recent_excluded_and_protected_files() {
+        let dir = scratch("clean-dir");
+        fs::create_dir_all(dir.join("keep")).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(48 * HOUR);
+        for name in ["gone.tmp", "kept.log", "keep/inside.tmp"] {
+            fs::File::create(dir.join(name)).unwrap().set_modified(old).unwrap();
+        }
+        fs::write(dir.join("recent.tmp"), "x").unwrap();
+        protect(&dir.join("keep"));
+
+        let exclusions = Exclusions::new(&["*.log".to_string()]);
+        let stats = clean_dir(&dir, Some(Duration::from_secs(24 * HOUR)), &exclusions);
+        assert_eq!(stats.files_deleted, 1);
+        assert_eq!(stats.files_skipped, 1);
+        assert!(!dir.join("gone.tmp").exists());
+        assert!(dir.join("kept.log").exists());
+        assert!(dir.join("recent.tmp").exists());
+        assert!(dir.join("keep/inside.tmp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reviewed_files_behind_a_link_are_left_alone() {
+        let dir = scratch("reviewed-link");
+        let (root, outside) = (dir.join("root"), dir.join("outside"));
+        fs::create_dir_all(root.join("real")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("real/cached"), "x").unwrap();
+        fs::write(outside.join("precious"), "x").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let files = [root.join("link/precious"), root.join("real/cached")];
+        let stats = clean_files(std::slice::from_ref(&root), &files, &Exclusions::default());
+        assert_eq!(stats.files_deleted, 1);
+        assert!(outside.join("precious").exists());
+        assert!(!root.join("real/cached").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn name_patterns_match_anywhere_below_a_target() {
+        let exclusions = Exclusions::new(&["*.log".to_string(), "GPUCache".to_string()]);
+        assert!(exclusions.excludes(Path::new("/cache/a/b/debug.log")));
+        assert!(exclusions.excludes(Path::new("/cache/profile/GPUCache")));
+        assert!(!exclusions.excludes(Path::new("/cache/a/debug.txt")));
+        assert!(!exclusions.excludes(Path::new("/cache/GPUCache/data_0")));
+    }
+
+    #[test]
+    fn path_patterns_match_trailing_components() {
+        let exclusions = Exclusions::new(&["Cache/index".to_string()]);
+        assert!(exclusions.excludes(Path::new("/profile/Cache/index")));
+        assert!(!exclusions.excludes(Path::new("/profile/Cache/index2")));
+        assert!(!exclusions.excludes(Path::new("/profile/Other/index")));
+        assert!(!exclusions.excludes(Path::new("/profile/MyCache/index")));
+    }
+
+    #[test]
+    fn path_patterns_take_backslashes_and_verbatim_paths() {
+        let exclusions = Exclusions::new(&[r"Cache\index".to_string()]);
+        assert!(exclusions.excludes(Path::new(r"\\?\C:\Users\me\Cache\index")));
+        assert!(exclusions.excludes(Path::new(r"C:\Users\me\Cache\index")));
+        assert!(!exclusions.excludes(Path::new(r"\\?\C:\Users\me\Cache\data_0")));
+    }
+
+    #[test]
+    fn patterns_ignore_case_only_on_windows() {
+        let exclusions = Exclusions::new(&["GPUCache".to_string(), "Cache/Index".to_string()]);
+        let folded = exclusions.excludes(Path::new("/profile/gpucache"));
+        let folded_path = exclusions.excludes(Path::new("/profile/cache/index"));
+        assert_eq!(folded, cfg!(target_os = "windows"));
+        assert_eq!(folded_path, cfg!(target_os = "windows"));
+    }
+
+    #[test]
+    fn empty_and_invalid_patterns() {
+        assert!(Exclusions::new(&["  ".to_string()]).is_empty());
+        assert!(Exclusions::validate(&["*.log".to_string()]).is_ok());
+        assert!(Exclusions::validate(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn check_path_refuses_parent_components() {
+        assert!(check_path(Path::new("/var/cache/../../etc")).is_err());
+        assert!(check_path(Path::new("../cache")).is_err());
+        assert!(check_path(Path::new("/var/cache/app..old")).is_ok());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn check_path_refuses_devices_and_streams() {
+        assert!(check_path(Path::new(r"C:\Temp\nul")).is_err());
+        assert!(check_path(Path::new(r"C:\Temp\CON.txt")).is_err());
+        assert!(check_path(Path::new(r"C:\Temp\nul .txt")).is_err());
+        assert!(check_path(Path::new(r"C:\Temp\file.txt:hidden")).is_err());
+        assert!(check_path(Path::new(r"\\.\PhysicalDrive0")).is_err());
+        assert!(check_path(Path::new(r"C:\Temp\console")).is_ok());
+        assert!(check_path(Path::new(r"\\?\C:\Temp\cache")).is_ok());
+        assert!(check_path(Path::new(r"\\server\share\cache")).is_ok());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn verbatim_adds_the_long_path_prefix() {
+        assert_eq!(verbatim(Path::new(r"C:\Temp\cache")), Path::new(r"\\?\C:\Temp\cache"));
+        assert_eq!(verbatim(Path::new(r"\\server\share\cache")), Path::new(r"\\?\UNC\server\share\cache"));
+        assert_eq!(verbatim(Path::new(r"\\?\C:\Temp")), Path::new(r"\\?\C:\Temp"));
+        assert_eq!(verbatim(Path::new(r"C:\Temp\..\cache")), Path::new(r"C:\Temp\..\cache"));
+    }
+
+    #[test]
+    fn protection_covers_whole_components_below_a_path() {
+        let dir = std::env::temp_dir().join(format!("cache-manager-test-{}-protected", std::process::id()));
+        protect(&dir.join("keep"));
+        assert!(is_protected(&dir.join("keep")));
+        assert!(is_protected(&dir.join("keep").join("file")));
+        assert!(!is_protected(&dir.join("keeper")));
+        assert!(!is_protected(&dir));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn protection_ignores_case_and_the_long_path_prefix() {
+        protect(Path::new(r"C:\CacheManagerTest\Keep"));
+        assert!(is_protected(Path::new(r"c:\cachemanagertest\keep\file")));
+        assert!(is_protected(Path::new(r"\\?\C:\CacheManagerTest\Keep")));
+        assert!(!is_protected(Path::new(r"C:\CacheManagerTest\Other")));
+    }
+}
@@ -0,0 +1,567 @@
+use crate::chromium_cache;
+use crate::cleaner::{self, Candidate, CleanStats};
+use crate::targets::{self, CacheFormat, CacheTarget};
+use crate::Config;
+use serde::Serialize;
+use std::path::PathBuf;
+
+// Each family of cache targets comes from a cleaner, which finds its targets
+// on this machine and cleans them. To add targets, add a cleaner to CLEANERS;
+// find_targets and the clean loop go through that list and never name one.
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    Any,
+    Windows,
+    Macos,
+    Linux,
+    // macOS and Linux
+    Unix,
+}
+
+impl Platform {
+    pub fn is_current(self) -> bool {
+        match self {
+            Platform::Any => true,
+            Platform::Windows => cfg!(target_os = "windows"),
+            Platform::Macos => cfg!(target_os = "macos"),
+            Platform::Linux => cfg!(target_os = "linux"),
+            Platform::Unix => cfg!(unix),
+        }
+    }
+}
+
+pub trait Cleaner: Sync {
+    fn name(&self) -> &'static str;
+
+    fn platform(&self) -> Platform;
+
+    // Whether its targets are outside what a standard user may delete
+    fn requires_admin(&self) -> bool {
+        false
+    }
+
+    // Add its targets to those the cleaners before it found; catch-alls look
+    // at those to leave out what they cover
+    fn scan(&self, config: &Config, found: &mut Vec<CacheTarget>);
+
+    // Clean one target according to its strategy, file by file
+    fn clean(&self, target: &CacheTarget) -> CleanStats {
+        cleaner::clean_target(&target.paths, target.strategy, &target.exclusions)
+    }
+
+    // What `clean` would delete, for previews and reclaimable sizes
+    fn candidates(&self, target: &CacheTarget) -> Vec<Candidate> {
+        cleaner::preview_target(&target.paths, target.strategy, &target.exclusions)
+    }
+
+    // Delete exactly the files a user reviewed from the target's preview
+    fn clean_files(&self, target: &CacheTarget, files: &[PathBuf]) -> CleanStats {
+        cleaner::clean_files(&target.paths, files, &target.exclusions)
+    }
+}
+
+// For cleaners that find Chromium disk caches, whose index must keep
+// matching their entries, next to plain folders such as crash reports
+fn clean_chromium(target: &CacheTarget) -> CleanStats {
+    match target.format {
+        CacheFormat::Files => cleaner::clean_target(&target.paths, target.strategy, &target.exclusions),
+        CacheFormat::Chromium => chromium_cache::clean(&target.paths, target.strategy, &target.exclusions),
+    }
+}
+
+fn chromium_candidates(target: &CacheTarget) -> Vec<Candidate> {
+    match target.format {
+        CacheFormat::Files => cleaner::preview_target(&target.paths, target.strategy, &target.exclusions),
+        CacheFormat::Chromium => chromium_cache::preview(&target.paths, target.strategy, &target.exclusions),
+    }
+}
+
+fn clean_chromium_files(target: &CacheTarget, files: &[PathBuf]) -> CleanStats {
+    match target.format {
+        CacheFormat::Files => cleaner::clean_files(&target.paths, files, &target.exclusions),
+        CacheFormat::Chromium => chromium_cache::clean_files(&target.paths, files, &target.exclusions),
+    }
+}
+
+pub struct BrowserCacheCleaner;
+
+impl Cleaner for BrowserCacheCleaner {
+    fn name(&self) -> &'static str {
+        "Browsers"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::chromium_targets(found);
+        targets::firefox_targets(found);
+    }
+
+    fn clean(&self, target: &CacheTarget) -> CleanStats {
+        clean_chromium(target)
+    }
+
+    fn candidates(&self, target: &CacheTarget) -> Vec<Candidate> {
+        chromium_candidates(target)
+    }
+
+    fn clean_files(&self, target: &CacheTarget, files: &[PathBuf]) -> CleanStats {
+        clean_chromium_files(target, files)
+    }
+}
+
+pub struct MacosCleaner;
+
+impl Cleaner for MacosCleaner {
+    fn name(&self) -> &'static str {
+        "macOS"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Macos
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::macos_targets(found);
+    }
+}
+
+pub struct LinuxAppCleaner;
+
+impl Cleaner for LinuxAppCleaner {
+    fn name(&self) -> &'static str {
+        "Linux apps"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Linux
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::linux_app_targets(found);
+    }
+}
+
+pub struct TrashCleaner;
+
+impl Cleaner for TrashCleaner {
+    fn name(&self) -> &'static str {
+        "Trash"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Linux
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::trash_targets(found);
+    }
+}
+
+pub struct GamesCleaner;
+
+impl Cleaner for GamesCleaner {
+    fn name(&self) -> &'static str {
+        "Game launchers"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Windows
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::games_targets(found);
+    }
+}
+
+pub struct PrefetchCleaner;
+
+impl Cleaner for PrefetchCleaner {
+    fn name(&self) -> &'static str {
+        "Windows Prefetch"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Windows
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::prefetch_targets(found);
+    }
+}
+
+pub struct WindowsUpdateCleaner;
+
+impl Cleaner for WindowsUpdateCleaner {
+    fn name(&self) -> &'static str {
+        "Windows Update"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Windows
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::windows_update_targets(found);
+    }
+}
+
+pub struct WslCleaner;
+
+impl Cleaner for WslCleaner {
+    fn name(&self) -> &'static str {
+        "WSL"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Windows
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::wsl_targets(found);
+    }
+
+    // Space freed inside a distribution stays taken on the Windows drive
+    // until its disk is compacted, a maintenance action; the disks
+    // themselves are only sized
+    fn clean(&self, _target: &CacheTarget) -> CleanStats {
+        CleanStats::default()
+    }
+
+    fn candidates(&self, _target: &CacheTarget) -> Vec<Candidate> {
+        Vec::new()
+    }
+
+    fn clean_files(&self, _target: &CacheTarget, _files: &[PathBuf]) -> CleanStats {
+        CleanStats::default()
+    }
+}
+
+pub struct GameEngineCleaner;
+
+impl Cleaner for GameEngineCleaner {
+    fn name(&self) -> &'static str {
+        "Game engines"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::game_engine_targets(found, config);
+    }
+}
+
+pub struct AndroidCleaner;
+
+impl Cleaner for AndroidCleaner {
+    fn name(&self) -> &'static str {
+        "Android"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::android_targets(found);
+    }
+}
+
+pub struct FlutterCleaner;
+
+impl Cleaner for FlutterCleaner {
+    fn name(&self) -> &'static str {
+        "Flutter"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::flutter_targets(found);
+    }
+}
+
+pub struct GoCleaner;
+
+impl Cleaner for GoCleaner {
+    fn name(&self) -> &'static str {
+        "Go"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::go_targets(found);
+    }
+}
+
+pub struct PackageManagerCleaner;
+
+impl Cleaner for PackageManagerCleaner {
+    fn name(&self) -> &'static str {
+        "Package managers"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::package_manager_targets(found);
+    }
+}
+
+pub struct JvmCleaner;
+
+impl Cleaner for JvmCleaner {
+    fn name(&self) -> &'static str {
+        "JVM builds"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::jvm_targets(found);
+    }
+}
+
+pub struct MediaCleaner;
+
+impl Cleaner for MediaCleaner {
+    fn name(&self) -> &'static str {
+        "Media servers"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::media_targets(found);
+    }
+}
+
+pub struct AppCleaner;
+
+impl Cleaner for AppCleaner {
+    fn name(&self) -> &'static str {
+        "Chat and music apps"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::app_targets(found);
+    }
+
+    // Discord and Teams keep Chromium disk caches
+    fn clean(&self, target: &CacheTarget) -> CleanStats {
+        clean_chromium(target)
+    }
+
+    fn candidates(&self, target: &CacheTarget) -> Vec<Candidate> {
+        chromium_candidates(target)
+    }
+
+    fn clean_files(&self, target: &CacheTarget, files: &[PathBuf]) -> CleanStats {
+        clean_chromium_files(target, files)
+    }
+}
+
+pub struct StoreAppCleaner;
+
+impl Cleaner for StoreAppCleaner {
+    fn name(&self) -> &'static str {
+        "Store apps"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Windows
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::store_app_targets(found);
+    }
+}
+
+pub struct TempDirCleaner;
+
+impl Cleaner for TempDirCleaner {
+    fn name(&self) -> &'static str {
+        "Temporary files"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::temp_targets(found);
+    }
+}
+
+pub struct UserCacheCleaner;
+
+impl Cleaner for UserCacheCleaner {
+    fn name(&self) -> &'static str {
+        "Other app caches"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Unix
+    }
+
+    fn scan(&self, _config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::user_cache_targets(found);
+    }
+}
+
+pub struct CustomFolderCleaner;
+
+impl Cleaner for CustomFolderCleaner {
+    fn name(&self) -> &'static str {
+        "Custom folders"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::custom_targets(found, config);
+    }
+}
+
+pub struct RemoteRuleCleaner;
+
+impl Cleaner for RemoteRuleCleaner {
+    fn name(&self) -> &'static str {
+        "Remote rule file"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::remote_targets(found, config);
+    }
+}
+
+pub struct NetworkShareCleaner;
+
+impl Cleaner for NetworkShareCleaner {
+    fn name(&self) -> &'static str {
+        "Network shares"
+    }
+
+    fn platform(&self) -> Platform {
+        Platform::Any
+    }
+
+    fn scan(&self, config: &Config, found: &mut Vec<CacheTarget>) {
+        targets::share_targets(found, config);
+    }
+}
+
+// In the order they scan; catch-alls come after the targets they leave out
+pub const CLEANERS: &[&dyn Cleaner] = &[
+    &BrowserCacheCleaner,
+    &MacosCleaner,
+    &LinuxAppCleaner,
+    &TrashCleaner,
+    &GamesCleaner,
+    &PrefetchCleaner,
+    &WindowsUpdateCleaner,
+    &WslCleaner,
+    &GameEngineCleaner,
+    &AndroidCleaner,
+    &FlutterCleaner,
+    &GoCleaner,
+    &PackageManagerCleaner,
+    &JvmCleaner,
+    &MediaCleaner,
+    &AppCleaner,
+    &StoreAppCleaner,
+    &TempDirCleaner,
+    &UserCacheCleaner,
+    &CustomFolderCleaner,
+    &RemoteRuleCleaner,
+    &NetworkShareCleaner,
+];
+
+// The cleaners of this platform
+pub fn available() -> impl Iterator<Item = &'static dyn Cleaner> {
+    CLEANERS.iter().copied().filter(|cleaner| cleaner.platform().is_current())
+}
+
+// The cleaner that found `target`; targets made outside a scan are cleaned
+// as custom folders
+pub fn of(target: &CacheTarget) -> &'static dyn Cleaner {
+    CLEANERS
+        .iter()
+        .copied()
+        .find(|cleaner| cleaner.name() == target.cleaner)
+        .unwrap_or(&CustomFolderCleaner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn cleaner_names_are_unique() {
+        let names: BTreeSet<_> = CLEANERS.iter().map(|cleaner| cleaner.name()).collect();
+        assert_eq!(names.len(), CLEANERS.len());
+    }
+
+    #[test]
+    fn targets_carry_the_name_of_the_cleaner_that_found_them() {
+        let config = Config::default();
+        let mut found = Vec::new();
+        let mut finders = BTreeMap::new();
+        for cleaner in available() {
+            let before = found.len();
+            cleaner.scan(&config, &mut found);
+            for target in &found[before..] {
+                finders.insert(target.id.clone(), cleaner.name());
+            }
+        }
+        let targets = targets::builtin_targets(&config);
+        // The temp folder is everywhere
+        assert!(!targets.is_empty());
+        for target in &targets {
+            assert_eq!(target.cleaner, finders[&target.id], "{}", target.id);
+            assert_eq!(of(target).name(), target.cleaner);
+        }
+    }
+
+    #[test]
+    fn targets_made_outside_a_scan_are_custom_folders() {
+        let target = targets::trial_target("/cache", PathBuf::from("/cache"), Default::default());
+        assert_eq!(of(&target).name(), CustomFolderCleaner.name());
+    }
+}
@@ -0,0 +1,530 @@
+use crate::clean_budget;
+use crate::clean_history;
+use crate::cleaner::{self, CleanStats, DirStats};
+use crate::config_file;
+use crate::confirmation;
+use crate::io_priority;
+use crate::locale;
+use crate::powershell;
+use crate::profiles;
+use crate::rules_export;
+use crate::targets;
+use crate::tui;
+use crate::undo;
+use std::cell::OnceCell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Handle command-line subcommands. Returns the process exit code when one was
+// run, or None to start the GUI as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("bench-scan") => Some(bench_scan()),
+        Some("clean") => Some(clean(&args[1..])),
+        // Flag spelling for scheduled tasks and CI scripts
+        Some("--clean") => Some(clean(&args[1..])),
+        Some("tui") => Some(tui::run()),
+        Some("clean-memory") => Some(clean_memory()),
+        Some("rules") => Some(export_rules(&args[1..])),
+        Some("status") => Some(status(&args[1..])),
+        Some("powershell-module") => Some(install_powershell_module(&args[1..])),
+        _ => None,
+    }
+}
+
+// GUI builds use the Windows subsystem, so reuse the launching console for output
+#[cfg(target_os = "windows")]
+pub fn attach_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_console() {}
+
+type ScanFn = fn(&Path) -> DirStats;
+
+fn time_scan(paths: &[std::path::PathBuf], scan: ScanFn) -> (DirStats, Duration) {
+    let start = Instant::now();
+    let mut stats = DirStats::default();
+    for path in paths {
+        stats.add(if path.is_dir() {
+            scan(path)
+        } else {
+            cleaner::scan_path(path)
+        });
+    }
+    (stats, start.elapsed())
+}
+
+// Time a full scan of every target with each traversal backend, twice. Only
+// the backend that goes first on a target meets a cold OS file cache; the
+// other's first pass is already warmed by it, so which backend goes first
+// alternates between targets and is marked with *.
+fn bench_scan() -> i32 {
+    attach_console();
+
+    const BACKENDS: [(&str, ScanFn); 2] = [
+        ("read_dir", cleaner::scan_dir),
+        ("parallel", cleaner::scan_dir_parallel),
+    ];
+
+    let config = config_file::load();
+    let locale = locale::resolve(&config.locale);
+    println!(
+        "{:<32} {:<9} {:>10} {:>12} {:>11} {:>11}",
+        "TARGET",
+        "BACKEND",
+        "FILES",
+        "SIZE",
+        "FIRST (ms)",
+        "AGAIN (ms)"
+    );
+
+    let mut totals = [(Duration::ZERO, Duration::ZERO); BACKENDS.len()];
+    for (n, target) in targets::builtin_targets(&config).into_iter().enumerate() {
+        let mut order: Vec<_> = (0..BACKENDS.len()).collect();
+        order.rotate_left(n % BACKENDS.len());
+        let mut rows = Vec::new();
+        for (position, i) in order.into_iter().enumerate() {
+            let (backend, scan) = BACKENDS[i];
+            let (stats, first) = time_scan(&target.paths, scan);
+            let (_, again) = time_scan(&target.paths, scan);
+            totals[i].0 += first;
+            totals[i].1 += again;
+            let marker = if position == 0 { "*" } else { "" };
+            rows.push((i, format!("{}{}", backend, marker), stats, first, again));
+        }
+        rows.sort_by_key(|row| row.0);
+
+        for (_, backend, stats, first, again) in rows {
+            println!(
+                "{:<32} {:<9} {:>10} {:>12} {:>11} {:>11}",
+                target.id,
+                backend,
+                locale::format_decimal(stats.file_count as f64, 0, &locale),
+                locale::format_size(stats.size_bytes, &config),
+                locale::format_decimal(first.as_millis() as f64, 0, &locale),
+                locale::format_decimal(again.as_millis() as f64, 0, &locale)
+            );
+        }
+    }
+
+    println!();
+    for ((backend, _), (first, again)) in BACKENDS.iter().zip(totals) {
+        println!(
+            "{:<32} {:<9} {:>10} {:>12} {:>11} {:>11}",
+            "TOTAL",
+            backend,
+            "",
+            "",
+            locale::format_decimal(first.as_millis() as f64, 0, &locale),
+            locale::format_decimal(again.as_millis() as f64, 0, &locale)
+        );
+    }
+    println!();
+    println!("* ran first on the target, against a cold file cache unless something else read it lately");
+    0
+}
+
+// Free memory cache as auto-clean does; what the Task Scheduler task runs
+fn clean_memory() -> i32 {
+    attach_console();
+    let config = config_file::load();
+    let target_mb = config.start_threshold_mb.saturating_sub(config.stop_threshold_mb);
+    match crate::clean_memory_cache(target_mb) {
+        Ok(cleaned_mb) => {
+            crate::memory_schedule::record_run();
+            println!("Freed {} of memory cache", locale::format_size(cleaned_mb * 1024 * 1024, &config));
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// Memory use and every target with its size and what a clean would free, as
+// a table or, with --format json, as the window gets them
+fn status(args: &[String]) -> i32 {
+    attach_console();
+    let json = match args {
+        [] => false,
+        [format, value] if format == "--format" && (value == "text" || value == "json") => value == "json",
+        [flag] if flag == "--json" => true,
+        _ => {
+            eprintln!("usage: memory-cache-manager status [--format text|json | --json]");
+            return 2;
+        }
+    };
+    let config = config_file::load();
+    let memory = crate::get_memory_info().ok();
+    let mut targets = crate::scan_targets(&config);
+    crate::add_reclaimable(&config, &mut targets);
+    if json {
+        let status = serde_json::json!({ "memory": memory, "targets": targets });
+        println!("{}", serde_json::to_string_pretty(&status).unwrap_or_default());
+        return 0;
+    }
+
+    if let Some(memory) = &memory {
+        println!(
+            "Memory: {} used of {}, {} cache",
+            locale::format_size(memory.used_mb * 1024 * 1024, &config),
+            locale::format_size(memory.total_mb * 1024 * 1024, &config),
+            locale::format_size(memory.cache_mb * 1024 * 1024, &config)
+        );
+    }
+    println!("{:<32} {:>12} {:>12}", "TARGET", "SIZE", "RECLAIMABLE");
+    for target in &targets {
+        let reclaimable = target
+            .reclaimable_bytes
+            .map_or_else(|| "?".to_string(), |bytes| locale::format_size(bytes, &config));
+        println!(
+            "{:<32} {:>12} {:>12}",
+            target.id,
+            locale::format_size(target.stats.size_bytes, &config),
+            reclaimable
+        );
+    }
+    0
+}
+
+// Install the PowerShell module in the user's module folders, or in the
+// given folder
+fn install_powershell_module(args: &[String]) -> i32 {
+    attach_console();
+    let dir = match args {
+        [] => None,
+        [dir] => Some(std::path::PathBuf::from(dir)),
+        _ => {
+            eprintln!("usage: memory-cache-manager powershell-module [<folder>]");
+            return 2;
+        }
+    };
+    match powershell::install(dir) {
+        Ok(paths) => {
+            for path in paths {
+                println!("Installed {}", path.display());
+            }
+            println!("Load it with: Import-Module MemoryCacheManager");
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// Print the resolved targets and their rules as JSON, or write them to the
+// given file
+fn export_rules(args: &[String]) -> i32 {
+    attach_console();
+    let config = config_file::load();
+    let result = match args {
+        [] => rules_export::to_json(&config).map(|json| println!("{}", json)),
+        [path] => rules_export::export(&config, Path::new(path)),
+        _ => {
+            eprintln!("usage: memory-cache-manager rules [<file>]");
+            return 2;
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+const CLEAN_USAGE: &str = "usage: memory-cache-manager clean [--target <id>... | --profile <name>] [--threshold <GB>] [--concurrency <N>] [--io-priority low|normal] [--max-duration <time>] [--dry-run] [--verify] [--format text|json | --json]";
+
+// Clean the given targets (every cleanable one that is not off by default
+// when none is given, or the VM profile's inside a virtual machine), or with
+// --dry-run list exactly which files would go. With --threshold, nothing
+// happens unless cleaning the targets would free at least that many
+// gigabytes (in the configured size units). --profile takes the
+// targets, age policy and threshold from a profile of the config. --verify
+// (or verify_cleans in the config) checks the targets again afterwards.
+// --concurrency cleans at most that many drives at once, --io-priority low
+// has other programs' disk use go first, and --max-duration (90s, 5m, 1h)
+// stops the clean between files once the time is up, reporting what it did
+// so far. Exits with 2 on bad arguments, 3 when stopped at --max-duration and
+// 1 when some files could not be deleted or the check found files that came
+// back or were not deleted.
+fn clean(args: &[String]) -> i32 {
+    attach_console();
+
+    let mut ids = Vec::new();
+    let mut profile_name = None;
+    let mut threshold_gb = None;
+    let mut dry_run = false;
+    let mut verify = false;
+    let mut json = false;
+    let mut concurrency = None;
+    let mut low_priority = false;
+    let mut max_duration = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.as_slice().first().map(String::as_str)) {
+            ("--target", Some(id)) => {
+                ids.push(id.to_string());
+                args.next();
+            }
+            ("--profile", Some(name)) => {
+                profile_name = Some(name.to_string());
+                args.next();
+            }
+            ("--format", Some(format @ ("text" | "json"))) => {
+                json = format == "json";
+                args.next();
+            }
+            ("--threshold", Some(value)) => {
+                match value.parse::<f64>() {
+                    Ok(gb) if gb >= 0.0 => threshold_gb = Some(gb),
+                    _ => {
+                        eprintln!("invalid threshold: {}", value);
+                        return 2;
+                    }
+                }
+                args.next();
+            }
+            ("--concurrency", Some(value)) => {
+                match value.parse::<usize>() {
+                    Ok(count) if count > 0 => concurrency = Some(count),
+                    _ => {
+                        eprintln!("invalid concurrency: {}", value);
+                        return 2;
+                    }
+                }
+                args.next();
+            }
+            ("--io-priority", Some(priority @ ("low" | "normal"))) => {
+                low_priority = priority == "low";
+                args.next();
+            }
+            ("--max-duration", Some(value)) => {
+                match parse_duration(value) {
+                    Some(duration) if !duration.is_zero() => max_duration = Some(duration),
+                    _ => {
+                        eprintln!("invalid duration: {}", value);
+                        return 2;
+                    }
+                }
+                args.next();
+            }
+            ("--json", _) => json = true,
+            ("--dry-run", _) => dry_run = true,
+            ("--verify", _) => verify = true,
+            _ => {
+                eprintln!("{}", CLEAN_USAGE);
+                return 2;
+            }
+        }
+    }
+
+    if profile_name.is_some() && !ids.is_empty() {
+        eprintln!("--profile and --target cannot be combined");
+        return 2;
+    }
+
+    // Naming a target is the confirmation those that ask before cleaning need
+    let named = !ids.is_empty();
+    let mut config = config_file::load();
+    let available = targets::builtin_targets(&config);
+    let mut selected = Vec::new();
+    let profile = match &profile_name {
+        Some(name) => match profiles::find(&config.profiles, name) {
+            Ok(profile) => Some(profile.clone()),
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        },
+        // Virtual machines get the lighter VM profile instead of every target
+        None if ids.is_empty() => profiles::for_virtual_machine(&config.profiles).map(|(machine, profile)| {
+            eprintln!("Running in {}: cleaning with the {} profile", machine, profile.name);
+            profile
+        }),
+        None => None,
+    };
+    if let Some(profile) = &profile {
+        selected = profiles::select(profile, &available);
+        if profile.threshold_gb > 0.0 {
+            threshold_gb = threshold_gb.or(Some(profile.threshold_gb));
+        }
+        config.deletion = profile.deletion(config.deletion);
+    } else if ids.is_empty() {
+        ids = available
+            .iter()
+            .filter(|target| !target.report_only && target.warning.is_none())
+            .map(|target| target.id.clone())
+            .collect();
+    }
+    for id in &ids {
+        match available.iter().find(|target| &target.id == id) {
+            Some(target) if target.report_only => {
+                eprintln!("target {} is report-only and is never cleaned", id);
+                return 2;
+            }
+            Some(target) => selected.push(target.clone()),
+            None => {
+                eprintln!("unknown or undetected target: {}", id);
+                return 2;
+            }
+        }
+    }
+    if !named {
+        let held = if dry_run {
+            let (held, allowed) = selected
+                .into_iter()
+                .partition(|target| confirmation::asks_first(&config, &target.id));
+            selected = allowed;
+            held
+        } else {
+            let (allowed, held) = confirmation::hold_back(&config, selected);
+            selected = allowed;
+            held
+        };
+        for target in &held {
+            eprintln!("{} asks before cleaning — skipped; clean it with --target {}", target.name, target.id);
+        }
+    }
+    let processes = OnceCell::new();
+    for target in &selected {
+        if let Some(app) = target.running_owner(&processes) {
+            eprintln!("{} is running — {} will be skipped", app, target.name);
+        }
+    }
+
+    let locale = locale::resolve(&config.locale);
+    if let Some(gb) = threshold_gb {
+        let threshold = config.size_units.giga_to_bytes(gb);
+        let total: u64 = selected.iter().map(targets::reclaimable).sum();
+        if total < threshold {
+            if json {
+                // Same shape as a run that found nothing to delete
+                let nothing = if dry_run {
+                    "[]".to_string()
+                } else {
+                    serde_json::to_string_pretty(&CleanStats::default()).unwrap_or_default()
+                };
+                println!("{}", nothing);
+            } else {
+                println!(
+                    "A clean would free {}, below the {} threshold; nothing to clean",
+                    locale::format_size(total, &config),
+                    locale::format_size(threshold, &config)
+                );
+            }
+            return 0;
+        }
+    }
+
+    if dry_run {
+        let previews: Vec<_> = selected.iter().map(targets::preview).collect();
+        if json {
+            println!("{}", serde_json::to_string_pretty(&previews).unwrap_or_default());
+        } else {
+            for preview in &previews {
+                println!(
+                    "{}: {} files, {}",
+                    preview.id,
+                    locale::format_decimal(preview.files.len() as f64, 0, &locale),
+                    locale::format_size(preview.total_bytes, &config)
+                );
+                for file in &preview.files {
+                    println!("  {}", file.path.display());
+                }
+            }
+        }
+        return 0;
+    }
+
+    if let Some(count) = concurrency {
+        targets::limit_parallel_volumes(count);
+    }
+    if low_priority {
+        if let Err(e) = io_priority::lower() {
+            eprintln!("{}", e);
+        }
+    }
+    let batch = undo::begin(config.deletion);
+    // Cancelling stops the clean between files, as from the window; the
+    // targets that freed space fastest before go first
+    if let Some(duration) = max_duration {
+        clean_budget::order(&mut selected);
+        let run = batch.run();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            run.cancel();
+        });
+    }
+    let history = clean_history::Recorder::start("cli").verified(verify || config.verify_cleans);
+    let stats: CleanStats = targets::clean_targets(&selected, &history, |_| {});
+    let run = history.finish();
+    let cancelled = batch.is_cancelled();
+    undo::finish(batch);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap_or_default());
+    } else {
+        println!(
+            "Deleted {} files ({}), {} failed ({}), {} kept as recently used ({})",
+            locale::format_decimal(stats.files_deleted as f64, 0, &locale),
+            locale::format_size(stats.freed_bytes, &config),
+            locale::format_decimal(stats.files_failed as f64, 0, &locale),
+            locale::format_size(stats.failed_bytes, &config),
+            locale::format_decimal(stats.files_skipped as f64, 0, &locale),
+            locale::format_size(stats.skipped_bytes, &config)
+        );
+        for volume in &run.volumes {
+            println!(
+                "  {}: freed {}, {} free",
+                volume.root,
+                locale::format_size(volume.freed_bytes, &config),
+                locale::format_size(volume.free_bytes, &config)
+            );
+        }
+    }
+    let mut discrepancies = false;
+    for target in &run.targets {
+        let Some(found) = &target.discrepancy else {
+            continue;
+        };
+        discrepancies = true;
+        eprintln!(
+            "{}: {} files reappeared ({}), {} reported deleted are still there",
+            target.id,
+            locale::format_decimal(found.reappeared_files as f64, 0, &locale),
+            locale::format_size(found.reappeared_bytes, &config),
+            locale::format_decimal(found.undeleted_files as f64, 0, &locale)
+        );
+        for path in &found.undeleted {
+            eprintln!("  {}", path.display());
+        }
+    }
+    if cancelled {
+        eprintln!("Stopped when the time allowed by --max-duration was up; the rest was not cleaned");
+        3
+    } else if stats.files_failed > 0 || discrepancies {
+        1
+    } else {
+        0
+    }
+}
+
+// A number of seconds, or of minutes or hours with an m or h suffix
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, unit_secs) = match text.as_bytes().last()? {
+        b's' => (&text[..text.len() - 1], 1),
+        b'm' => (&text[..text.len() - 1], 60),
+        b'h' => (&text[..text.len() - 1], 60 * 60),
+        _ => (text, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(unit_secs).map(Duration::from_secs)
+}
@@ -0,0 +1,115 @@
+use crate::targets;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Folders a sync client mirrors to the cloud. Deleting in one deletes on
+// every other device too, so they are never cleaned as caches.
+
+// The provider's name and the synced folder
+pub struct SyncedFolder {
+    pub provider: &'static str,
+    pub path: PathBuf,
+}
+
+// Dropbox lists its personal and business folders in info.json
+fn dropbox_folders() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let info_files = [targets::env_path("APPDATA"), targets::env_path("LOCALAPPDATA")]
+        .into_iter()
+        .flatten()
+        .map(|p| p.join("Dropbox").join("info.json"))
+        .collect::<Vec<_>>();
+    #[cfg(not(target_os = "windows"))]
+    let info_files = targets::home_dir()
+        .map(|p| p.join(".dropbox").join("info.json"))
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut folders = Vec::new();
+    for info in info_files {
+        let Some(accounts) = fs::read_to_string(info)
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        else {
+            continue;
+        };
+        for account in ["personal", "business"] {
+            if let Some(path) = accounts[account]["path"].as_str() {
+                folders.push(PathBuf::from(path));
+            }
+        }
+    }
+    if folders.is_empty() {
+        folders.extend(targets::home_dir().map(|p| p.join("Dropbox")));
+    }
+    folders
+}
+
+pub fn synced_folders() -> Vec<SyncedFolder> {
+    let mut folders = Vec::new();
+    let mut add = |provider, path: PathBuf| folders.push(SyncedFolder { provider, path });
+
+    for path in dropbox_folders() {
+        add("Dropbox", path);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        for var in ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"] {
+            if let Some(path) = targets::env_path(var) {
+                add("OneDrive", path);
+            }
+        }
+        // Drive for desktop mounts a drive holding "My Drive"; the older
+        // Backup and Sync used a folder in the profile
+        for root in targets::drive_roots() {
+            if root.join("My Drive").is_dir() {
+                add("Google Drive", root);
+            }
+        }
+        if let Some(home) = targets::home_dir() {
+            add("Google Drive", home.join("Google Drive"));
+            add("iCloud Drive", home.join("iCloudDrive"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(home) = targets::home_dir() {
+        // File Provider clients all live here, named after the provider
+        for dir in fs::read_dir(home.join("Library/CloudStorage")).into_iter().flatten().flatten() {
+            let name = dir.file_name().to_string_lossy().into_owned();
+            let provider = if name.starts_with("OneDrive") {
+                "OneDrive"
+            } else if name.starts_with("GoogleDrive") {
+                "Google Drive"
+            } else if name.starts_with("Dropbox") {
+                "Dropbox"
+            } else {
+                "a cloud storage provider"
+            };
+            add(provider, dir.path());
+        }
+        add("iCloud Drive", home.join("Library/Mobile Documents"));
+        add("Google Drive", home.join("Google Drive"));
+    }
+
+    folders.retain(|folder| folder.path.is_dir());
+    folders
+}
+
+// Compares case-insensitively on Windows, whose file names are
+fn is_within(path: &Path, root: &Path) -> bool {
+    if cfg!(target_os = "windows") {
+        let lower = |p: &Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+        lower(path).starts_with(lower(root))
+    } else {
+        path.starts_with(root)
+    }
+}
+
+// The synced folder `path` is inside of, or holds
+pub fn overlap(path: &Path) -> Option<SyncedFolder> {
+    synced_folders()
+        .into_iter()
+        .find(|folder| is_within(path, &folder.path) || is_within(&folder.path, path))
+}
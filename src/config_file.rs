@@ -0,0 +1,204 @@
+use crate::cleaner;
+use crate::system_lock;
+use crate::targets;
+use crate::Config;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Earlier versions of the user config kept beside it, newest first
+const MAX_BACKUPS: usize = 5;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Default,
+    Machine,
+    User,
+}
+
+// Machine-wide defaults, written by administrators (e.g. via deployment tools)
+fn machine_config_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return std::env::var_os("ProgramData")
+        .map(|p| PathBuf::from(p).join("MemoryCacheManager").join("config.json"));
+
+    #[cfg(target_os = "macos")]
+    return Some(PathBuf::from(
+        "/Library/Application Support/MemoryCacheManager/config.json",
+    ));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return Some(PathBuf::from("/etc/memory-cache-manager/config.json"));
+}
+
+// Per-user directory for the config file and other app state
+pub fn app_data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let dir_name = "MemoryCacheManager";
+    #[cfg(not(target_os = "windows"))]
+    let dir_name = "memory-cache-manager";
+
+    targets::app_config_dir().map(|p| p.join(dir_name))
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    app_data_dir().map(|p| p.join("config.json"))
+}
+
+// Whether settings were ever saved, i.e. the app ran here before
+pub fn has_user_config() -> bool {
+    user_config_path().is_some_and(|path| path.exists())
+}
+
+// config.1.json is the version saved before the current one
+fn backup_path(config_path: &Path, generation: usize) -> PathBuf {
+    config_path.with_file_name(format!("config.{}.json", generation))
+}
+
+// Move every backup one generation older, dropping the oldest, and make the
+// current file the newest backup
+fn rotate_backups(config_path: &Path) {
+    if !config_path.exists() {
+        return;
+    }
+    for generation in (1..MAX_BACKUPS).rev() {
+        let from = backup_path(config_path, generation);
+        if from.exists() {
+            let _ = fs::rename(&from, backup_path(config_path, generation + 1));
+        }
+    }
+    let _ = fs::copy(config_path, backup_path(config_path, 1));
+}
+
+// Register the config files and app state with the cleaner so no target
+// ever deletes them
+pub fn protect_app_files() {
+    if let Some(path) = machine_config_path() {
+        cleaner::protect(&path);
+    }
+    if let Some(dir) = app_data_dir() {
+        cleaner::protect(&dir);
+    }
+    if let Ok(exe) = std::env::current_exe() {
+        cleaner::protect(&exe);
+    }
+    // Deleting it while held would let a second instance lock a new file
+    if let Some(path) = system_lock::lock_path() {
+        cleaner::protect(&path);
+    }
+}
+
+// A missing or unreadable file contributes no settings
+fn read_settings(path: Option<PathBuf>) -> Map<String, Value> {
+    let Some(path) = path else {
+        return Map::new();
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Map::new();
+    };
+    match serde_json::from_str(&text) {
+        Ok(Value::Object(settings)) => settings,
+        _ => {
+            eprintln!("Ignoring malformed config file {}", path.display());
+            Map::new()
+        }
+    }
+}
+
+fn to_settings(config: &Config) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(settings)) => settings,
+        _ => Map::new(),
+    }
+}
+
+// Built-in defaults overlaid with the machine-wide file
+fn machine_baseline() -> Map<String, Value> {
+    let mut settings = to_settings(&Config::default());
+    settings.extend(read_settings(machine_config_path()));
+    settings
+}
+
+pub fn load() -> Config {
+    let mut settings = machine_baseline();
+    settings.extend(read_settings(user_config_path()));
+    serde_json::from_value(Value::Object(settings)).unwrap_or_else(|e| {
+        eprintln!("Invalid configuration, using defaults: {}", e);
+        Config::default()
+    })
+}
+
+// Persist only the settings that differ from the machine baseline, so values
+// left untouched keep following the machine-wide file
+pub fn save(config: &Config) -> Result<(), String> {
+    let path = user_config_path().ok_or("No user config directory available")?;
+    let baseline = machine_baseline();
+    let overrides: Map<String, Value> = to_settings(config)
+        .into_iter()
+        .filter(|(key, value)| baseline.get(key) != Some(value))
+        .collect();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let text = serde_json::to_string_pretty(&Value::Object(overrides))
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    // Saving unchanged settings would push real earlier versions out
+    if fs::read_to_string(&path).is_ok_and(|current| current == text) {
+        return Ok(());
+    }
+    rotate_backups(&path);
+    fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// How many earlier versions restore_previous can go back through
+pub fn backup_count() -> usize {
+    let Some(path) = user_config_path() else {
+        return 0;
+    };
+    (1..=MAX_BACKUPS)
+        .take_while(|generation| backup_path(&path, *generation).exists())
+        .count()
+}
+
+// Replace the user config with the version saved before it and return the
+// settings that result. Each call goes one version further back; the
+// replaced settings are discarded.
+pub fn restore_previous() -> Result<Config, String> {
+    let path = user_config_path().ok_or("No user config directory available")?;
+    let newest = backup_path(&path, 1);
+    if !newest.exists() {
+        return Err("There are no earlier settings to restore".to_string());
+    }
+    fs::rename(&newest, &path).map_err(|e| format!("Failed to restore {}: {}", newest.display(), e))?;
+    for generation in 2..=MAX_BACKUPS {
+        let from = backup_path(&path, generation);
+        if from.exists() {
+            let _ = fs::rename(&from, backup_path(&path, generation - 1));
+        }
+    }
+    Ok(load())
+}
+
+// Which scope currently supplies each setting
+pub fn scopes() -> BTreeMap<String, Scope> {
+    let machine = read_settings(machine_config_path());
+    let user = read_settings(user_config_path());
+
+    to_settings(&Config::default())
+        .keys()
+        .map(|key| {
+            let scope = if user.contains_key(key) {
+                Scope::User
+            } else if machine.contains_key(key) {
+                Scope::Machine
+            } else {
+                Scope::Default
+            };
+            (key.clone(), scope)
+        })
+        .collect()
+}
@@ -0,0 +1,105 @@
+use crate::config_file;
+use crate::i18n;
+use crate::notifications::{self, Level};
+use crate::targets::{self, CacheTarget};
+use crate::Config;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Targets marked to ask before cleaning, such as Downloads or Windows.old.
+// Automatic cleans (schedules, low disk space, emergency mode and profile
+// runs from the command line) leave them alone and queue them, with a
+// notification, to be cleaned from the window, which asks before cleaning
+// them too. A target stays queued, notified once, until a clean of it.
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("pending-confirmations.json"))
+}
+
+fn read_all() -> BTreeMap<String, u64> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(pending: &BTreeMap<String, u64>) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(pending) {
+        let _ = fs::write(path, text);
+    }
+}
+
+pub fn asks_first(config: &Config, id: &str) -> bool {
+    config.confirm_targets.iter().any(|marked| marked == id)
+}
+
+// When each marked target was first held back, keyed by target id
+pub fn pending(config: &Config) -> BTreeMap<String, u64> {
+    let mut pending = read_all();
+    pending.retain(|id, _| asks_first(config, id));
+    pending
+}
+
+// Split `targets` into those an automatic clean may clean and those that
+// wait for confirmation. Held back targets a clean would free something
+// from are queued, with a notification the first time.
+pub fn hold_back(config: &Config, targets: Vec<CacheTarget>) -> (Vec<CacheTarget>, Vec<CacheTarget>) {
+    let (held, allowed): (Vec<_>, Vec<_>) = targets.into_iter().partition(|target| asks_first(config, &target.id));
+    if held.is_empty() {
+        return (allowed, held);
+    }
+
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut pending = read_all();
+    let now = now();
+    let mut queued = false;
+    for target in &held {
+        if pending.contains_key(&target.id) || targets::reclaimable(target) == 0 {
+            continue;
+        }
+        pending.insert(target.id.clone(), now);
+        queued = true;
+        notifications::push(
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "{target} asks before cleaning and was left out of an automatic clean; clean it from the window",
+                &[("target", &target.name)],
+            ),
+        );
+    }
+    if queued {
+        write_all(&pending);
+    }
+    (allowed, held)
+}
+
+// Take cleaned targets off the queue
+pub fn clear<'a>(ids: impl IntoIterator<Item = &'a str>) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut pending = read_all();
+    let before = pending.len();
+    for id in ids {
+        pending.remove(id);
+    }
+    if pending.len() != before {
+        write_all(&pending);
+    }
+}
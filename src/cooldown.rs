@@ -0,0 +1,81 @@
+use crate::config_file;
+use crate::targets::{CacheTarget, ALL_TARGETS};
+use crate::Config;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Per-target cooldown of the low-disk auto-clean: a target cleaned within
+// its cooldown is left alone even while free space stays low, so caches
+// that grow back within minutes are not cleaned over and over. Any clean
+// starts the cooldown, as does the trigger finding nothing to free.
+
+// For targets without a cooldown of their own when none is set for all
+const DEFAULT_HOURS: u64 = 1;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("cooldowns.json"))
+}
+
+// When each target's cooldown started, keyed by target id
+pub fn started() -> BTreeMap<String, u64> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// Start the cooldown of the targets `ids`
+pub fn start<'a>(ids: impl IntoIterator<Item = &'a str>) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut started = started();
+    let now = now();
+    for id in ids {
+        started.insert(id.to_string(), now);
+    }
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(&started) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// A target's own cooldown, else the one for every target
+pub fn hours(config: &Config, id: &str) -> u64 {
+    config
+        .clean_cooldown_hours
+        .get(id)
+        .or_else(|| config.clean_cooldown_hours.get(ALL_TARGETS))
+        .copied()
+        .unwrap_or(DEFAULT_HOURS)
+}
+
+// Seconds since the Unix epoch until which the low-disk auto-clean leaves
+// the target alone; None once its cooldown is over
+pub fn until(config: &Config, id: &str, started: &BTreeMap<String, u64>) -> Option<u64> {
+    let until = started.get(id)?.saturating_add(hours(config, id).saturating_mul(60 * 60));
+    (until > now()).then_some(until)
+}
+
+// `targets` less those still cooling down
+pub fn ready(config: &Config, targets: Vec<CacheTarget>) -> Vec<CacheTarget> {
+    let started = started();
+    targets
+        .into_iter()
+        .filter(|target| until(config, &target.id, &started).is_none())
+        .collect()
+}
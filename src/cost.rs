@@ -0,0 +1,86 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// What the last run of an operation cost, so users can judge whether full
+// scans are worth it or scan limits should be set
+#[derive(Serialize, Clone)]
+pub struct OperationCost {
+    pub files: u64,
+    pub elapsed_ms: u64,
+    // Disk I/O of the whole process while it ran; None where the OS does
+    // not report it
+    pub bytes_read: Option<u64>,
+    pub bytes_written: Option<u64>,
+    pub finished_at: u64,
+}
+
+static LAST: Mutex<BTreeMap<String, OperationCost>> = Mutex::new(BTreeMap::new());
+
+struct IoCounters {
+    read: u64,
+    written: u64,
+}
+
+// Directory enumeration shows up as "other" transfers rather than reads
+#[cfg(target_os = "windows")]
+fn io_counters() -> Option<IoCounters> {
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessIoCounters, IO_COUNTERS};
+
+    let mut counters = IO_COUNTERS::default();
+    unsafe { GetProcessIoCounters(GetCurrentProcess(), &mut counters) }.ok()?;
+    Some(IoCounters {
+        read: counters.ReadTransferCount + counters.OtherTransferCount,
+        written: counters.WriteTransferCount,
+    })
+}
+
+// Bytes actually fetched from or sent to storage, so cached metadata is free
+#[cfg(target_os = "linux")]
+fn io_counters() -> Option<IoCounters> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    let field = |name: &str| {
+        io.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    Some(IoCounters {
+        read: field("read_bytes")?,
+        written: field("write_bytes")?,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn io_counters() -> Option<IoCounters> {
+    None
+}
+
+// Run `work` and record its cost as the last run of `operation`; `files`
+// counts the files it went through
+pub fn measure<T>(operation: &str, work: impl FnOnce() -> T, files: impl Fn(&T) -> u64) -> T {
+    let before = io_counters();
+    let start = Instant::now();
+    let result = work();
+    let elapsed = start.elapsed();
+    let after = io_counters();
+
+    let delta = before.zip(after);
+    let cost = OperationCost {
+        files: files(&result),
+        elapsed_ms: elapsed.as_millis() as u64,
+        bytes_read: delta.as_ref().map(|(before, after)| after.read.saturating_sub(before.read)),
+        bytes_written: delta
+            .as_ref()
+            .map(|(before, after)| after.written.saturating_sub(before.written)),
+        finished_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+    };
+    LAST.lock().unwrap().insert(operation.to_string(), cost);
+    result
+}
+
+pub fn last() -> BTreeMap<String, OperationCost> {
+    LAST.lock().unwrap().clone()
+}
@@ -0,0 +1,113 @@
+use crate::clean_history;
+use crate::cleaner;
+use crate::config_file;
+use crate::notifications;
+use crate::size_stats;
+use crate::Config;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Limits on the app's own history, so the tool that frees space does not
+// slowly fill it: clean history, size snapshots and notifications older than
+// the retention period are dropped, and each of those files is cut down to
+// its size cap from the oldest entries.
+
+// Background compaction runs at most this often
+const COMPACT_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+static LAST_COMPACTED: AtomicU64 = AtomicU64::new(0);
+
+pub struct Policy {
+    // Entries from before this time are dropped, in seconds since the epoch
+    pub cutoff: u64,
+    pub max_bytes: Option<u64>,
+}
+
+// A file or folder in the app's data folder
+#[derive(Serialize)]
+pub struct DataFile {
+    pub name: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct DataUsage {
+    pub total_bytes: u64,
+    // Largest first
+    pub files: Vec<DataFile>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn policy(config: &Config) -> Policy {
+    let max_age = config.history_retention_days.saturating_mul(24 * 60 * 60);
+    Policy {
+        cutoff: if config.history_retention_days == 0 { 0 } else { now().saturating_sub(max_age) },
+        max_bytes: (config.history_max_mb > 0).then(|| config.history_max_mb * 1024 * 1024),
+    }
+}
+
+// Drop the entries older than the cutoff, then the oldest until the rest fit
+// the size cap as JSON. Entries are kept oldest first. True when any were
+// dropped.
+pub fn fit<T: Serialize>(entries: &mut Vec<T>, policy: &Policy, at: impl Fn(&T) -> u64) -> bool {
+    let before = entries.len();
+    entries.retain(|entry| at(entry) >= policy.cutoff);
+    if let Some(max_bytes) = policy.max_bytes {
+        while !entries.is_empty() && serde_json::to_vec(&*entries).map_or(0, |json| json.len() as u64) > max_bytes {
+            let excess = (entries.len() / 10).max(1);
+            entries.drain(..excess);
+        }
+    }
+    entries.len() != before
+}
+
+// Apply the retention settings to every history file now
+pub fn compact(config: &Config) {
+    let policy = policy(config);
+    clean_history::compact(&policy);
+    size_stats::compact(&policy);
+    notifications::compact(&policy);
+    LAST_COMPACTED.store(now(), Ordering::Relaxed);
+}
+
+// Compact once a day, the first time soon after the app starts
+pub fn compact_if_due(config: &Config) {
+    if now().saturating_sub(LAST_COMPACTED.load(Ordering::Relaxed)) >= COMPACT_INTERVAL_SECS {
+        compact(config);
+    }
+}
+
+// What the app keeps on disk, including the quarantine folder
+pub fn usage() -> DataUsage {
+    let mut files: Vec<DataFile> = config_file::app_data_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            let size_bytes = match entry.metadata() {
+                Ok(meta) if meta.is_dir() => cleaner::scan_path(&path).size_bytes,
+                Ok(meta) => meta.len(),
+                Err(_) => 0,
+            };
+            DataFile {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size_bytes,
+            }
+        })
+        .collect();
+    files.sort_by_key(|file| Reverse(file.size_bytes));
+    DataUsage {
+        total_bytes: files.iter().map(|file| file.size_bytes).sum(),
+        files,
+    }
+}
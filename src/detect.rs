@@ -0,0 +1,222 @@
+use crate::targets;
+use std::cell::OnceCell;
+
+// Precondition for a target to be offered, in the spirit of winapp2 and
+// CleanerML detection rules
+#[derive(Clone, Copy)]
+pub enum Condition {
+    // e.g. "HKCU\\Software\\Unity Technologies"; HKCU and HKLM are supported
+    #[cfg(target_os = "windows")]
+    RegistryKey(&'static str),
+    // Found on PATH
+    Executable(&'static str),
+    // Any of these found on PATH
+    AnyExecutable(&'static [&'static str]),
+    // No process with this name (without ".exe") is running
+    ProcessNotRunning(&'static str),
+}
+
+impl Condition {
+    // `processes` is filled on first use, so the process list is read at
+    // most once per target scan
+    pub fn holds(&self, processes: &OnceCell<Vec<String>>) -> bool {
+        match *self {
+            #[cfg(target_os = "windows")]
+            Condition::RegistryKey(key) => registry_key_exists(key),
+            Condition::Executable(name) => targets::find_in_path(name).is_some(),
+            Condition::AnyExecutable(names) => names.iter().any(|name| targets::find_in_path(name).is_some()),
+            Condition::ProcessNotRunning(name) => !is_running(name, processes),
+        }
+    }
+}
+
+// `name` is a process name without ".exe"; `processes` as for Condition::holds
+pub fn is_running(name: &str, processes: &OnceCell<Vec<String>>) -> bool {
+    processes.get_or_init(running_processes).contains(&name.to_lowercase())
+}
+
+// Ask every process named `name` to quit, as closing its windows would, and
+// wait a few seconds for them to exit
+pub fn close_process(name: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let closed = targets::tool_output("taskkill", &["/im", &format!("{}.exe", name)]);
+    #[cfg(not(target_os = "windows"))]
+    let closed = targets::tool_output("pkill", &["-x", name]);
+    if closed.is_none() {
+        return Err(format!("Could not close {}", name));
+    }
+    for _ in 0..10 {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        if !is_running(name, &OnceCell::new()) {
+            return Ok(());
+        }
+    }
+    Err(format!("{} is still running", name))
+}
+
+#[cfg(target_os = "windows")]
+fn registry_key_exists(key: &str) -> bool {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::*;
+
+    let (root, subkey) = match key.split_once('\\') {
+        Some(("HKCU", subkey)) => (HKEY_CURRENT_USER, subkey),
+        Some(("HKLM", subkey)) => (HKEY_LOCAL_MACHINE, subkey),
+        _ => return false,
+    };
+
+    let mut handle = HKEY::default();
+    unsafe {
+        if RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut handle).is_err() {
+            return false;
+        }
+        let _ = RegCloseKey(handle);
+    }
+    true
+}
+
+// Lowercase names of running processes, without any ".exe" suffix
+#[cfg(target_os = "windows")]
+pub fn running_processes() -> Vec<String> {
+    // CSV rows like "chrome.exe","1234","Console","1","120,000 K"
+    let output = targets::tool_output("tasklist", &["/fo", "csv", "/nh"]).unwrap_or_default();
+    output
+        .lines()
+        .filter_map(|line| line.split('"').nth(1))
+        .map(|name| name.to_lowercase().trim_end_matches(".exe").to_string())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn running_processes() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()))
+        .filter_map(|e| std::fs::read_to_string(e.path().join("comm")).ok())
+        .map(|comm| comm.trim().to_lowercase())
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+pub fn running_processes() -> Vec<String> {
+    // `comm` is the executable path; keep just its file name
+    let output = targets::tool_output("ps", &["-Axo", "comm="]).unwrap_or_default();
+    output
+        .lines()
+        .filter_map(|line| line.trim().rsplit('/').next())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn running_processes() -> Vec<String> {
+    Vec::new()
+}
+
+// Hardware names of virtual machines, matched in lowercase against the
+// firmware's manufacturer and product, and what to call them
+const VIRTUAL_HARDWARE: &[(&str, &str)] = &[
+    ("vmware", "VMware"),
+    ("virtualbox", "VirtualBox"),
+    ("innotek", "VirtualBox"),
+    ("qemu", "QEMU"),
+    ("kvm", "KVM"),
+    ("xen", "Xen"),
+    ("parallels", "Parallels"),
+    ("bochs", "Bochs"),
+    // Hyper-V reports "Microsoft Corporation" and "Virtual Machine"
+    ("virtual machine", "Hyper-V"),
+    ("virtualmac", "Apple Virtualization"),
+    ("amazon ec2", "Amazon EC2"),
+    ("google compute engine", "Google Compute Engine"),
+];
+
+fn match_hardware(description: &str) -> Option<&'static str> {
+    let description = description.to_lowercase();
+    VIRTUAL_HARDWARE
+        .iter()
+        .find(|(needle, _)| description.contains(needle))
+        .map(|(_, name)| *name)
+}
+
+// Firmware manufacturer and product, e.g. "VMware, Inc. VMware7,1"
+#[cfg(target_os = "windows")]
+fn hardware_description() -> String {
+    const BIOS_KEY: &str = "HKLM\\HARDWARE\\DESCRIPTION\\System\\BIOS";
+    ["SystemManufacturer", "SystemProductName"]
+        .iter()
+        .filter_map(|value| targets::tool_output("reg", &["query", BIOS_KEY, "/v", value]))
+        // Output like "    SystemProductName    REG_SZ    Virtual Machine"
+        .filter_map(|output| Some(output.split("REG_SZ").nth(1)?.trim().to_string()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "linux")]
+fn hardware_description() -> String {
+    ["sys_vendor", "product_name"]
+        .iter()
+        .filter_map(|file| std::fs::read_to_string(format!("/sys/class/dmi/id/{}", file)).ok())
+        .map(|text| text.trim().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "macos")]
+fn hardware_description() -> String {
+    targets::tool_output("sysctl", &["-n", "hw.model"]).unwrap_or_default()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn hardware_description() -> String {
+    String::new()
+}
+
+// Windows Sandbox runs everything as this account
+#[cfg(target_os = "windows")]
+fn is_windows_sandbox() -> bool {
+    std::env::var("USERNAME").is_ok_and(|user| user.eq_ignore_ascii_case("WDAGUtilityAccount"))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_windows_sandbox() -> bool {
+    false
+}
+
+// Whether the CPU reports a hypervisor, for virtual machines whose firmware
+// names nothing known
+#[cfg(target_os = "macos")]
+fn has_hypervisor() -> bool {
+    targets::tool_output("sysctl", &["-n", "kern.hv_vmm_present"]).is_some_and(|out| out.trim() == "1")
+}
+
+#[cfg(target_os = "linux")]
+fn has_hypervisor() -> bool {
+    let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") else {
+        return false;
+    };
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn has_hypervisor() -> bool {
+    false
+}
+
+// What the app runs inside, e.g. "Windows Sandbox" or "VMware", or None on
+// real hardware. Looked up once; it does not change while the app runs.
+pub fn virtual_machine() -> Option<&'static str> {
+    static DETECTED: std::sync::OnceLock<Option<&'static str>> = std::sync::OnceLock::new();
+    *DETECTED.get_or_init(|| {
+        if is_windows_sandbox() {
+            return Some("Windows Sandbox");
+        }
+        match_hardware(&hardware_description()).or_else(|| has_hypervisor().then_some("a virtual machine"))
+    })
+}
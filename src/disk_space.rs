@@ -0,0 +1,125 @@
+use crate::cleaner;
+use crate::targets;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// Free and total space of the mounted drives, for the low-disk trigger of
+// auto-clean and for advice on where caches could go
+
+#[derive(Serialize)]
+pub struct DriveSpace {
+    pub root: String,
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+    // The drive the system boots from
+    pub system: bool,
+}
+
+#[cfg(target_os = "windows")]
+pub fn system_root() -> PathBuf {
+    PathBuf::from(format!("{}\\", std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string())))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn system_root() -> PathBuf {
+    PathBuf::from("/")
+}
+
+pub fn on_same_volume(a: &Path, b: &Path) -> bool {
+    match (cleaner::volume_id(a), cleaner::volume_id(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Free and total bytes of the drive holding `path`
+#[cfg(target_os = "windows")]
+pub fn space(path: &Path) -> Option<(u64, u64)> {
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let mut free = 0u64;
+    let mut total = 0u64;
+    unsafe { GetDiskFreeSpaceExW(&HSTRING::from(path.as_os_str()), Some(&mut free), Some(&mut total), None).ok()? };
+    Some((free, total))
+}
+
+// `df -Pk` rows: filesystem, 1K blocks, used, available, capacity, mount
+// point, the last split further when it contains spaces
+#[cfg(not(target_os = "windows"))]
+pub fn df_rows(args: &[&str]) -> Vec<Vec<String>> {
+    let output = targets::tool_output("df", &[&["-Pk"], args].concat()).unwrap_or_default();
+    output
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|columns| columns.len() >= 6)
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn row_space(row: &[String]) -> Option<(u64, u64)> {
+    let free = row[3].parse::<u64>().ok()?;
+    let total = row[1].parse::<u64>().ok()?;
+    Some((free * 1024, total * 1024))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn space(path: &Path) -> Option<(u64, u64)> {
+    row_space(df_rows(&[&path.to_string_lossy()]).first()?)
+}
+
+pub fn free_bytes(path: &Path) -> Option<u64> {
+    space(path).map(|(free, _)| free)
+}
+
+// The root of the drive holding `path`, as shown to the user ("D:\",
+// "/home"), with its free bytes
+#[cfg(target_os = "windows")]
+pub fn drive_of(path: &Path) -> Option<(String, u64)> {
+    let root = cleaner::volume_id(path)?.to_uppercase();
+    let free = free_bytes(Path::new(&root))?;
+    Some((root, free))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn drive_of(path: &Path) -> Option<(String, u64)> {
+    let row = df_rows(&[&path.to_string_lossy()]).into_iter().next()?;
+    let (free, _) = row_space(&row)?;
+    Some((row[5..].join(" "), free))
+}
+
+#[cfg(target_os = "windows")]
+pub fn volume_roots() -> Vec<PathBuf> {
+    targets::drive_roots()
+}
+
+// Mounted disks, leaving out pseudo filesystems and boot partitions
+#[cfg(not(target_os = "windows"))]
+pub fn volume_roots() -> Vec<PathBuf> {
+    df_rows(&[])
+        .into_iter()
+        .filter(|row| row[0].starts_with("/dev/"))
+        .map(|row| PathBuf::from(row[5..].join(" ")))
+        .filter(|root| !root.starts_with("/boot") && !root.starts_with("/snap") && !root.starts_with("/System"))
+        .collect()
+}
+
+// Every drive, the system drive first
+pub fn drives() -> Vec<DriveSpace> {
+    let system = system_root();
+    let mut drives: Vec<_> = volume_roots()
+        .into_iter()
+        .filter_map(|root| {
+            let (free_bytes, total_bytes) = space(&root)?;
+            Some(DriveSpace {
+                system: root == system || on_same_volume(&root, &system),
+                root: root.display().to_string(),
+                free_bytes,
+                total_bytes,
+            })
+        })
+        .collect();
+    drives.sort_by_key(|drive| !drive.system);
+    drives
+}
@@ -0,0 +1,112 @@
+use crate::cleaner::DirStats;
+use serde::Serialize;
+
+// How much of a target the current process can see, and therefore clean
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Full,
+    // Some subdirectories could not be listed; their files are neither
+    // counted nor cleaned
+    Partial,
+    Denied,
+    // Its files can be listed, but only an administrator may delete them
+    AdminOnly,
+}
+
+// `requires_admin` for targets of a cleaner that says only an administrator
+// can clean them
+pub fn access(stats: &DirStats, requires_admin: bool) -> Access {
+    if requires_admin && !is_elevated() {
+        Access::AdminOnly
+    } else if stats.denied_dirs == 0 {
+        Access::Full
+    } else if stats.file_count == 0 {
+        Access::Denied
+    } else {
+        Access::Partial
+    }
+}
+
+// Running as administrator (an elevated token under UAC)
+#[cfg(target_os = "windows")]
+pub fn is_elevated() -> bool {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    unsafe {
+        let mut token = HANDLE::default();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).is_err() {
+            return false;
+        }
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut returned = 0u32;
+        let queried = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut _),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        );
+        let _ = CloseHandle(token);
+        queried.is_ok() && elevation.TokenIsElevated != 0
+    }
+}
+
+// Effective uid 0, read from procfs to avoid a libc dependency
+#[cfg(target_os = "linux")]
+pub fn is_elevated() -> bool {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().nth(1))
+        .is_some_and(|euid| euid == "0")
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_elevated() -> bool {
+    crate::targets::tool_output("id", &["-u"]).is_some_and(|uid| uid.trim() == "0")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn is_elevated() -> bool {
+    false
+}
+
+// Start another instance of the app as administrator, which asks for
+// consent through UAC. `args` are passed to it as given.
+#[cfg(target_os = "windows")]
+pub fn relaunch_elevated(args: &[String]) -> Result<(), String> {
+    use windows::core::{w, HSTRING};
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let params: Vec<String> = args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+        .collect();
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("runas"),
+            &HSTRING::from(exe.as_os_str()),
+            &HSTRING::from(params.join(" ")),
+            None,
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values up to 32 are errors, including the user declining the prompt
+    if result.0 <= 32 {
+        return Err("Restart as administrator was cancelled or failed".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn relaunch_elevated(_args: &[String]) -> Result<(), String> {
+    Err("Restarting as administrator is only supported on Windows; start the app with sudo instead".to_string())
+}
@@ -0,0 +1,121 @@
+use crate::cleaner;
+use crate::disk_space;
+use crate::i18n;
+use crate::locale;
+use crate::profiles;
+use crate::targets::{self, CacheTarget};
+use crate::wsl;
+use crate::Config;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Emergency mode, for a system drive so full that updates fail and apps
+// cannot save: the safest targets are cleaned at once, background scans wait
+// so they do not add to the load, and the window says what to try next. It
+// ends once free space is back above twice the critical level, so a clean
+// that frees a little does not start it over and over.
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Next steps found when emergency mode started
+static SUGGESTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Targets suggested by how much cleaning them would free
+const SUGGESTED_TARGETS: usize = 3;
+
+#[derive(Serialize)]
+pub struct EmergencyStatus {
+    pub active: bool,
+    pub free_bytes: Option<u64>,
+    pub threshold_bytes: u64,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(PartialEq)]
+pub enum Change {
+    Entered,
+    Left,
+    Unchanged,
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+// Enter or leave emergency mode given the system drive's free space
+pub fn update(config: &Config, free_bytes: Option<u64>) -> Change {
+    let threshold = config.emergency_free_mb * 1024 * 1024;
+    let active = is_active();
+    let now_active = match free_bytes {
+        _ if config.emergency_free_mb == 0 => false,
+        None => active,
+        Some(free) if active => free < threshold.saturating_mul(2),
+        Some(free) => free < threshold,
+    };
+    ACTIVE.store(now_active, Ordering::Relaxed);
+    match (active, now_active) {
+        (false, true) => Change::Entered,
+        (true, false) => {
+            SUGGESTIONS.lock().unwrap().clear();
+            Change::Left
+        }
+        _ => Change::Unchanged,
+    }
+}
+
+// The Quick profile's targets on the system drive, leaving out any that are
+// off by default
+pub fn quick_targets(config: &Config) -> Vec<CacheTarget> {
+    let Some(profile) = profiles::quick(&config.profiles) else {
+        return Vec::new();
+    };
+    let system = cleaner::volume_id(&disk_space::system_root());
+    profiles::select(&profile, &targets::builtin_targets(config))
+        .into_iter()
+        .filter(|target| target.warning.is_none())
+        .filter(|target| system.is_some() && target.volume == system)
+        .collect()
+}
+
+// What to try next once the quick clean is done: the system drive's targets
+// that would free the most, then steps outside the app
+pub fn find_suggestions(config: &Config, cleaned: &[CacheTarget]) {
+    let tr = |text: &str, values: &[(&str, &str)]| i18n::tr(&config.language, text, values);
+    let system = cleaner::volume_id(&disk_space::system_root());
+    let mut reclaimable: Vec<_> = targets::cleanable(&targets::builtin_targets(config))
+        .into_iter()
+        .filter(|target| system.is_some() && target.volume == system)
+        .filter(|target| !cleaned.iter().any(|done| done.id == target.id))
+        .map(|target| (targets::reclaimable(target), target.name.clone()))
+        .filter(|(bytes, _)| *bytes > 0)
+        .collect();
+    reclaimable.sort_by_key(|(bytes, _)| Reverse(*bytes));
+
+    let mut suggestions: Vec<String> = reclaimable
+        .iter()
+        .take(SUGGESTED_TARGETS)
+        .map(|(bytes, name)| {
+            tr(
+                "Clean {target} to free {size}",
+                &[("target", name), ("size", &locale::format_size(*bytes, config))],
+            )
+        })
+        .collect();
+    if !wsl::distros().is_empty() {
+        suggestions.push(tr("Compact the WSL disks to give back space freed inside Linux", &[]));
+    }
+    suggestions.push(tr("Look for large files nobody uses with Find Large & Stale Files", &[]));
+    suggestions.push(tr("Move downloads, videos and photos to another drive", &[]));
+    *SUGGESTIONS.lock().unwrap() = suggestions;
+}
+
+pub fn status(config: &Config) -> EmergencyStatus {
+    EmergencyStatus {
+        active: is_active(),
+        free_bytes: disk_space::free_bytes(&disk_space::system_root()),
+        threshold_bytes: config.emergency_free_mb * 1024 * 1024,
+        suggestions: SUGGESTIONS.lock().unwrap().clone(),
+    }
+}
@@ -0,0 +1,160 @@
+use crate::cleaner::Exclusions;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Exclusion rules shared between machines as a named pack, like
+//   {"name": "Build machines", "exclusions": {"*": ["*.lock"], "custom:D:\\Builds": ["keep/"]}}
+// keyed by target id as in the settings, "*" for every target. Importing a
+// pack merges it into the exclusions here; where both have different
+// patterns for a target, the choice made for that target decides.
+
+#[derive(Serialize, Deserialize)]
+pub struct ExclusionPack {
+    pub name: String,
+    pub exclusions: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    // This machine's patterns, then the pack's it lacks
+    #[default]
+    Combine,
+    KeepMine,
+    TakePack,
+}
+
+#[derive(Serialize)]
+pub struct PackTarget {
+    pub id: String,
+    pub pack: Vec<String>,
+    pub mine: Vec<String>,
+    // Both have patterns for the target and they differ
+    pub conflict: bool,
+}
+
+#[derive(Serialize)]
+pub struct PackPreview {
+    pub name: String,
+    pub targets: Vec<PackTarget>,
+}
+
+fn clean_patterns(patterns: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if !cleaned.iter().any(|p| p == pattern) {
+            cleaned.push(pattern.to_string());
+        }
+    }
+    cleaned
+}
+
+pub fn export(config: &Config, name: &str, path: &Path) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Give the pack a name".to_string());
+    }
+    let pack = ExclusionPack {
+        name: name.to_string(),
+        exclusions: config.exclusions.clone(),
+    };
+    let text = serde_json::to_string_pretty(&pack).map_err(|e| format!("Failed to write the pack: {}", e))?;
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn load(path: &Path) -> Result<ExclusionPack, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut pack: ExclusionPack = serde_json::from_str(&text)
+        .map_err(|e| format!("{} is not a valid exclusion pack: {}", path.display(), e))?;
+    for (id, patterns) in &mut pack.exclusions {
+        Exclusions::validate(patterns.iter()).map_err(|e| format!("{}: {}", id, e))?;
+        *patterns = clean_patterns(patterns);
+    }
+    pack.exclusions.retain(|_, patterns| !patterns.is_empty());
+    Ok(pack)
+}
+
+pub fn preview(config: &Config, pack: &ExclusionPack) -> PackPreview {
+    let targets = pack
+        .exclusions
+        .iter()
+        .map(|(id, patterns)| {
+            let mine = config.exclusions.get(id).cloned().unwrap_or_default();
+            PackTarget {
+                id: id.clone(),
+                conflict: !mine.is_empty() && mine != *patterns,
+                pack: patterns.clone(),
+                mine,
+            }
+        })
+        .collect();
+    PackPreview {
+        name: pack.name.clone(),
+        targets,
+    }
+}
+
+// Targets without a choice are combined
+pub fn merge(config: &mut Config, pack: &ExclusionPack, choices: &BTreeMap<String, Resolution>) {
+    for (id, patterns) in &pack.exclusions {
+        let mine = config.exclusions.entry(id.clone()).or_default();
+        match choices.get(id).copied().unwrap_or_default() {
+            Resolution::Combine => {
+                let combined: Vec<String> = mine.iter().chain(patterns).cloned().collect();
+                *mine = clean_patterns(&combined);
+            }
+            Resolution::KeepMine if !mine.is_empty() => {}
+            Resolution::KeepMine | Resolution::TakePack => *mine = patterns.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(exclusions: &[(&str, &[&str])]) -> ExclusionPack {
+        ExclusionPack {
+            name: "Team".to_string(),
+            exclusions: exclusions
+                .iter()
+                .map(|(id, patterns)| (id.to_string(), patterns.iter().map(|p| p.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    fn config(exclusions: &[(&str, &[&str])]) -> Config {
+        Config {
+            exclusions: pack(exclusions).exclusions,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn preview_flags_differing_patterns_only() {
+        let config = config(&[("*", &["*.lock"]), ("npm", &["keep"])]);
+        let preview = preview(&config, &pack(&[("*", &["*.lock"]), ("npm", &["other"]), ("pip", &["x"])]));
+        let conflicts: Vec<_> = preview.targets.iter().map(|t| (t.id.as_str(), t.conflict)).collect();
+        assert_eq!(conflicts, [("*", false), ("npm", true), ("pip", false)]);
+    }
+
+    #[test]
+    fn merge_follows_each_choice() {
+        let mut config = config(&[("a", &["mine"]), ("b", &["mine"]), ("c", &["mine", "both"])]);
+        let pack = pack(&[("a", &["pack"]), ("b", &["pack"]), ("c", &["both", "pack"]), ("d", &["pack"])]);
+        let choices = BTreeMap::from([
+            ("a".to_string(), Resolution::KeepMine),
+            ("b".to_string(), Resolution::TakePack),
+            ("d".to_string(), Resolution::KeepMine),
+        ]);
+        merge(&mut config, &pack, &choices);
+        assert_eq!(config.exclusions["a"], ["mine"]);
+        assert_eq!(config.exclusions["b"], ["pack"]);
+        assert_eq!(config.exclusions["c"], ["mine", "both", "pack"]);
+        // Nothing of this machine's to keep
+        assert_eq!(config.exclusions["d"], ["pack"]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::cleaner;
+use crate::targets::CacheTarget;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Single large or long-unused files across every target, for when one crash
+// dump is the whole problem and cleaning entire targets is more than needed
+
+// Most files listed in each list
+pub const MAX_LISTED: usize = 1000;
+
+#[derive(Serialize, Clone)]
+pub struct FoundFile {
+    pub target_id: String,
+    pub target_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    // Seconds since the Unix epoch
+    pub last_used: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct FoundFiles {
+    // The largest files, largest first
+    pub largest: Vec<FoundFile>,
+    // The largest files unused for at least the requested days
+    pub stale: Vec<FoundFile>,
+    // All stale files, including those not listed
+    pub stale_count: u64,
+    pub stale_bytes: u64,
+}
+
+// Files collected for one list; trimmed to `limit` when it has grown to
+// twice that
+struct Largest {
+    files: Vec<FoundFile>,
+    limit: usize,
+}
+
+impl Largest {
+    fn new(limit: usize) -> Self {
+        Self { files: Vec::new(), limit }
+    }
+
+    fn push(&mut self, file: FoundFile) {
+        self.files.push(file);
+        if self.files.len() >= 2 * self.limit {
+            self.trim();
+        }
+    }
+
+    fn trim(&mut self) {
+        self.files.sort_by_key(|file| Reverse(file.size_bytes));
+        self.files.truncate(self.limit);
+    }
+
+    fn into_sorted(mut self) -> Vec<FoundFile> {
+        self.trim();
+        self.files
+    }
+}
+
+struct Search<'a> {
+    target: &'a CacheTarget,
+    stale_before: SystemTime,
+    largest: Largest,
+    stale: Largest,
+    stale_count: u64,
+    stale_bytes: u64,
+}
+
+impl Search<'_> {
+    fn add_file(&mut self, path: &Path, meta: &fs::Metadata) {
+        let last_used = cleaner::last_used(meta);
+        let file = FoundFile {
+            target_id: self.target.id.clone(),
+            target_name: self.target.name.clone(),
+            path: path.display().to_string().trim_start_matches(r"\\?\").to_string(),
+            size_bytes: meta.len(),
+            last_used: last_used.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        };
+        if last_used < self.stale_before {
+            self.stale_count += 1;
+            self.stale_bytes += file.size_bytes;
+            self.stale.push(file.clone());
+        }
+        self.largest.push(file);
+    }
+
+    // Symlinks and junctions are not followed
+    fn walk(&mut self, path: &Path) {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return;
+        };
+        if meta.is_file() {
+            self.add_file(path, &meta);
+            return;
+        }
+        if !meta.is_dir() {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            self.walk(&entry.path());
+        }
+    }
+}
+
+// The `limit` largest files of `targets`, and the largest of those unused
+// for `stale_days` or more, walking the targets across threads
+pub fn find(targets: &[CacheTarget], limit: usize, stale_days: u64) -> FoundFiles {
+    let limit = limit.clamp(1, MAX_LISTED);
+    let stale_before = SystemTime::now() - Duration::from_secs(stale_days.saturating_mul(24 * 60 * 60));
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk_size = targets.len().div_ceil(threads).max(1);
+    let mut found = FoundFiles::default();
+    let mut largest = Largest::new(limit);
+    let mut stale = Largest::new(limit);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = targets
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut searches = Vec::new();
+                    for target in chunk {
+                        let mut search = Search {
+                            target,
+                            stale_before,
+                            largest: Largest::new(limit),
+                            stale: Largest::new(limit),
+                            stale_count: 0,
+                            stale_bytes: 0,
+                        };
+                        for path in &target.paths {
+                            search.walk(&cleaner::verbatim(path));
+                        }
+                        searches.push(search);
+                    }
+                    searches
+                })
+            })
+            .collect();
+        for worker in workers {
+            for search in worker.join().unwrap_or_default() {
+                search.largest.files.into_iter().for_each(|file| largest.push(file));
+                search.stale.files.into_iter().for_each(|file| stale.push(file));
+                found.stale_count += search.stale_count;
+                found.stale_bytes += search.stale_bytes;
+            }
+        }
+    });
+    found.largest = largest.into_sorted();
+    found.stale = stale.into_sorted();
+    found
+}
+
+// Show the file selected in the system file manager, or its folder where
+// the file manager cannot select it
+pub fn reveal(path: &Path) -> Result<(), String> {
+    cleaner::check_path(path)?;
+    if !path.exists() {
+        return Err(format!("{} no longer exists", path.display()));
+    }
+    #[cfg(target_os = "windows")]
+    let spawned = std::process::Command::new("explorer.exe").arg(format!("/select,{}", path.display())).spawn();
+    #[cfg(target_os = "macos")]
+    let spawned = std::process::Command::new("open").arg("-R").arg(path).spawn();
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let spawned = std::process::Command::new("xdg-open").arg(path.parent().unwrap_or(path)).spawn();
+    spawned.map(|_| ()).map_err(|e| format!("Failed to open the file manager: {}", e))
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+use std::path::Path;
+
+// Which running programs keep files of a clean open. Only Windows refuses
+// to delete open files, so elsewhere nothing is ever locked.
+
+// Files asked about one at a time; the rest are not attributed to a program
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const MAX_CHECKED: usize = 500;
+
+// A program holding files a clean would delete
+#[derive(Serialize)]
+pub struct LockingApp {
+    pub pid: u32,
+    pub name: String,
+    pub file_count: u64,
+    pub size_bytes: u64,
+}
+
+// Whether another program has `path` open without letting it be deleted
+#[cfg(target_os = "windows")]
+pub fn is_locked(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const DELETE: u32 = 0x0001_0000;
+    // FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE: only fails
+    // on handles of others that do not share deletion
+    const SHARE_ALL: u32 = 0x7;
+    match std::fs::OpenOptions::new().access_mode(DELETE).share_mode(SHARE_ALL).open(path) {
+        Ok(_) => false,
+        // ERROR_SHARING_VIOLATION
+        Err(e) => e.raw_os_error() == Some(32),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_locked(_path: &Path) -> bool {
+    false
+}
+
+// The programs Restart Manager finds using `path`, as (pid, name), leaving
+// out services, Explorer, critical processes and this app, which are not
+// closed for a clean
+#[cfg(target_os = "windows")]
+fn users_of(path: &Path) -> Vec<(u32, String)> {
+    use windows::core::{HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::Foundation::ERROR_MORE_DATA;
+    use windows::Win32::System::RestartManager::*;
+
+    let mut session = 0u32;
+    let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+    if unsafe { RmStartSession(&mut session, 0, PWSTR(key.as_mut_ptr())) }.is_err() {
+        return Vec::new();
+    }
+    let name = HSTRING::from(path.as_os_str());
+    let mut users = Vec::new();
+    if unsafe { RmRegisterResources(session, Some(&[PCWSTR(name.as_ptr())]), None, None) }.is_ok() {
+        let mut apps = vec![RM_PROCESS_INFO::default(); 16];
+        let mut listed = None;
+        // With more users than fit, ERROR_MORE_DATA says how many there are;
+        // that may grow again before the next call
+        for _ in 0..3 {
+            let (mut needed, mut count, mut reasons) = (0u32, apps.len() as u32, 0u32);
+            match unsafe { RmGetList(session, &mut needed, &mut count, Some(apps.as_mut_ptr()), &mut reasons) } {
+                Ok(()) => listed = Some(count as usize),
+                Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => {
+                    apps.resize(needed as usize, RM_PROCESS_INFO::default());
+                    continue;
+                }
+                Err(_) => {}
+            }
+            break;
+        }
+        if let Some(count) = listed {
+            let closable = |app: &&RM_PROCESS_INFO| {
+                ![RmService, RmExplorer, RmCritical].contains(&app.ApplicationType)
+                    && app.Process.dwProcessId != std::process::id()
+            };
+            for app in apps[..count].iter().filter(closable) {
+                let len = app.strAppName.iter().position(|&c| c == 0).unwrap_or(app.strAppName.len());
+                users.push((app.Process.dwProcessId, String::from_utf16_lossy(&app.strAppName[..len])));
+            }
+        }
+    }
+    let _ = unsafe { RmEndSession(session) };
+    users
+}
+
+// The programs keeping any of `files`, given with their sizes, from being
+// deleted, the most bytes first
+#[cfg(target_os = "windows")]
+pub fn locking_apps<'a>(files: impl Iterator<Item = (&'a Path, u64)>) -> Vec<LockingApp> {
+    use std::collections::BTreeMap;
+
+    let mut apps: BTreeMap<u32, LockingApp> = BTreeMap::new();
+    let locked = files.filter(|(path, _)| is_locked(path)).take(MAX_CHECKED);
+    for (path, size) in locked {
+        for (pid, name) in users_of(path) {
+            let app = apps.entry(pid).or_insert(LockingApp {
+                pid,
+                name,
+                file_count: 0,
+                size_bytes: 0,
+            });
+            app.file_count += 1;
+            app.size_bytes += size;
+        }
+    }
+    let mut apps: Vec<_> = apps.into_values().collect();
+    apps.sort_by_key(|app| std::cmp::Reverse(app.size_bytes));
+    apps
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn locking_apps<'a>(_files: impl Iterator<Item = (&'a Path, u64)>) -> Vec<LockingApp> {
+    Vec::new()
+}
+
+// Ask the program to quit as closing its windows would, never forcing it,
+// and wait a few seconds for it to exit
+#[cfg(target_os = "windows")]
+pub fn close_app(pid: u32) -> Result<(), String> {
+    let pid = pid.to_string();
+    crate::targets::tool_output("taskkill", &["/pid", &pid]).ok_or_else(|| format!("Could not close process {}", pid))?;
+    for _ in 0..10 {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let listed = crate::targets::tool_output("tasklist", &["/fi", &format!("PID eq {}", pid), "/fo", "csv", "/nh"]);
+        if !listed.is_some_and(|list| list.contains(&format!("\"{}\"", pid))) {
+            return Ok(());
+        }
+    }
+    Err("The program is still running; it may be waiting for you to save your work".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn close_app(_pid: u32) -> Result<(), String> {
+    Err("Programs only keep files from being deleted on Windows".to_string())
+}
@@ -0,0 +1,68 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// Text of the window, the tray and notifications in the chosen language.
+// English is the text itself; each other language maps it to a translation,
+// and text a language does not cover yet stays English.
+
+pub const ENGLISH: &str = "en";
+
+// (code, name in the language itself, English text → translation)
+const TRANSLATIONS: &[(&str, &str, &str)] = &[("vi", "Tiếng Việt", include_str!("../locales/vi.json"))];
+
+#[derive(Serialize)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+}
+
+fn tables() -> &'static BTreeMap<&'static str, BTreeMap<String, String>> {
+    static TABLES: OnceLock<BTreeMap<&str, BTreeMap<String, String>>> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        TRANSLATIONS
+            .iter()
+            .map(|(code, _, json)| (*code, serde_json::from_str(json).unwrap_or_default()))
+            .collect()
+    })
+}
+
+// Every language the app can be shown in, English first
+pub fn languages() -> Vec<Language> {
+    let english = Language {
+        code: ENGLISH.to_string(),
+        name: "English".to_string(),
+    };
+    let others = TRANSLATIONS.iter().map(|(code, name, _)| Language {
+        code: code.to_string(),
+        name: name.to_string(),
+    });
+    std::iter::once(english).chain(others).collect()
+}
+
+pub fn validate(language: &str) -> Result<(), String> {
+    if language == ENGLISH || tables().contains_key(language) {
+        Ok(())
+    } else {
+        Err(format!("Unknown language {}", language))
+    }
+}
+
+// The translations of `language`, keyed by their English text; empty for
+// English
+pub fn strings(language: &str) -> BTreeMap<String, String> {
+    tables().get(language).cloned().unwrap_or_default()
+}
+
+// `text` in `language`, with each `{name}` in it replaced by its value
+pub fn tr(language: &str, text: &str, values: &[(&str, &str)]) -> String {
+    let mut translated = tables()
+        .get(language)
+        .and_then(|table| table.get(text))
+        .map_or(text, String::as_str)
+        .to_string();
+    for (name, value) in values {
+        translated = translated.replace(&format!("{{{}}}", name), value);
+    }
+    translated
+}
@@ -0,0 +1,132 @@
+use crate::clean_history;
+use crate::cleaner::{self, CleanStats};
+use crate::config_file;
+use crate::targets::{self, CacheTarget};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Clone)]
+struct TargetSnapshot {
+    id: String,
+    name: String,
+    size_before: u64,
+    size_after: u64,
+}
+
+// A clean whose aftermath is being watched
+#[derive(Serialize, Deserialize)]
+pub struct ImpactSession {
+    started_at: u64,
+    period_days: u64,
+    targets: Vec<TargetSnapshot>,
+}
+
+#[derive(Serialize)]
+pub struct TargetRegrowth {
+    id: String,
+    name: String,
+    size_before: u64,
+    size_after: u64,
+    size_now: u64,
+    // Average regrowth since the clean
+    bytes_per_day: u64,
+}
+
+#[derive(Serialize)]
+pub struct ImpactReport {
+    started_at: u64,
+    period_days: u64,
+    elapsed_days: f64,
+    complete: bool,
+    // Fastest-regrowing targets first
+    targets: Vec<TargetRegrowth>,
+}
+
+fn session_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("impact.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn target_size(target: &CacheTarget) -> u64 {
+    target
+        .paths
+        .iter()
+        .map(|path| cleaner::scan_path(path).size_bytes)
+        .sum()
+}
+
+// Snapshot the targets, clean them, and remember the result for the report
+pub fn start(targets: &[CacheTarget], period_days: u64) -> Result<CleanStats, String> {
+    let history = clean_history::Recorder::start("impact");
+    let mut total = CleanStats::default();
+    let mut snapshots = Vec::new();
+    for target in targets::cleanable(targets) {
+        let size_before = target_size(target);
+        total.add(history.track(target, targets::clean_target(target)));
+        snapshots.push(TargetSnapshot {
+            id: target.id.clone(),
+            name: target.name.clone(),
+            size_before,
+            size_after: target_size(target),
+        });
+    }
+    history.finish();
+
+    let session = ImpactSession {
+        started_at: now_secs(),
+        period_days,
+        targets: snapshots,
+    };
+    let path = session_path().ok_or("No app data directory available")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let text = serde_json::to_string_pretty(&session)
+        .map_err(|e| format!("Failed to serialize impact session: {}", e))?;
+    fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(total)
+}
+
+// Current regrowth of the last analysed clean, if one was started
+pub fn report(targets: &[CacheTarget]) -> Option<ImpactReport> {
+    let text = fs::read_to_string(session_path()?).ok()?;
+    let session: ImpactSession = serde_json::from_str(&text).ok()?;
+
+    let elapsed_days = now_secs().saturating_sub(session.started_at) as f64 / 86_400.0;
+    let mut regrowth: Vec<TargetRegrowth> = session
+        .targets
+        .into_iter()
+        .map(|snapshot| {
+            // Targets that disappeared since count as not regrown
+            let size_now = targets
+                .iter()
+                .find(|t| t.id == snapshot.id)
+                .map_or(snapshot.size_after, target_size);
+            let regrown = size_now.saturating_sub(snapshot.size_after);
+            TargetRegrowth {
+                bytes_per_day: (regrown as f64 / elapsed_days.max(1.0 / 24.0)) as u64,
+                id: snapshot.id,
+                name: snapshot.name,
+                size_before: snapshot.size_before,
+                size_after: snapshot.size_after,
+                size_now,
+            }
+        })
+        .collect();
+    regrowth.sort_by_key(|t| std::cmp::Reverse(t.bytes_per_day));
+
+    Some(ImpactReport {
+        started_at: session.started_at,
+        period_days: session.period_days,
+        elapsed_days,
+        complete: elapsed_days >= session.period_days as f64,
+        targets: regrowth,
+    })
+}
@@ -0,0 +1,25 @@
+// Running cleans at background priority, so a clean in a maintenance window
+// does not slow down what else the machine is doing. Disk reads and writes of
+// the whole process yield to other programs' from then on.
+
+#[cfg(target_os = "windows")]
+pub fn lower() -> Result<(), String> {
+    use windows::Win32::System::Threading::{GetCurrentProcess, SetPriorityClass, PROCESS_MODE_BACKGROUND_BEGIN};
+    unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) }
+        .map_err(|e| format!("Failed to lower the I/O priority: {}", e))
+}
+
+// The idle class of ionice; threads started afterwards inherit it
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn lower() -> Result<(), String> {
+    crate::targets::tool_output("ionice", &["-c", "3", "-p", &std::process::id().to_string()])
+        .map(|_| ())
+        .ok_or_else(|| "Failed to lower the I/O priority with ionice".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn lower() -> Result<(), String> {
+    crate::targets::tool_output("taskpolicy", &["-b", "-p", &std::process::id().to_string()])
+        .map(|_| ())
+        .ok_or_else(|| "Failed to lower the I/O priority with taskpolicy".to_string())
+}
@@ -0,0 +1,90 @@
+use crate::cleaner::CleanProgress;
+use crate::config_file;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// On-disk record of the clean in flight. It is removed when the clean ends,
+// so finding one at startup means the app crashed or lost power mid-clean.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CleanJournal {
+    pub started_at: u64,
+    pub target_ids: Vec<String>,
+    pub completed_ids: Vec<String>,
+    pub files_deleted: u64,
+    pub freed_bytes: u64,
+}
+
+static CURRENT: Mutex<Option<CleanJournal>> = Mutex::new(None);
+
+fn journal_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("clean-journal.json"))
+}
+
+// Write to a temporary file and rename it over the journal, so a power loss
+// leaves either the old or the new journal and never a truncated one
+fn write(journal: &CleanJournal) {
+    let Some(path) = journal_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let temp = path.with_extension("json.tmp");
+    let Ok(text) = serde_json::to_string(journal) else {
+        return;
+    };
+    if fs::write(&temp, text).is_ok() {
+        let _ = fs::rename(&temp, &path);
+    }
+}
+
+pub fn begin(target_ids: &[String]) {
+    let journal = CleanJournal {
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        target_ids: target_ids.to_vec(),
+        completed_ids: Vec::new(),
+        files_deleted: 0,
+        freed_bytes: 0,
+    };
+    write(&journal);
+    *CURRENT.lock().unwrap() = Some(journal);
+}
+
+pub fn target_done(id: &str) {
+    if let Some(journal) = CURRENT.lock().unwrap().as_mut() {
+        journal.completed_ids.push(id.to_string());
+        write(journal);
+    }
+}
+
+pub fn record_progress(progress: &CleanProgress) {
+    if let Some(journal) = CURRENT.lock().unwrap().as_mut() {
+        if journal.files_deleted != progress.files_deleted {
+            journal.files_deleted = progress.files_deleted;
+            journal.freed_bytes = progress.freed_bytes;
+            write(journal);
+        }
+    }
+}
+
+pub fn finish() {
+    *CURRENT.lock().unwrap() = None;
+    dismiss();
+}
+
+// The journal of a clean that never finished, if any
+pub fn interrupted() -> Option<CleanJournal> {
+    let text = fs::read_to_string(journal_path()?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn dismiss() {
+    if let Some(path) = journal_path() {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::Config;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum ClockFormat {
+    #[default]
+    #[serde(rename = "system")]
+    System,
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+}
+
+// Binary sizes are labelled MiB/GiB (powers of 1024), decimal ones MB/GB
+// (powers of 1000)
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Decimal,
+}
+
+impl SizeUnits {
+    fn base(self) -> f64 {
+        match self {
+            SizeUnits::Binary => 1024.0,
+            SizeUnits::Decimal => 1000.0,
+        }
+    }
+
+    // Labels of bytes and each power of the base after it
+    fn labels(self) -> [&'static str; 5] {
+        match self {
+            SizeUnits::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+            SizeUnits::Decimal => ["B", "KB", "MB", "GB", "TB"],
+        }
+    }
+
+    // Byte count of an amount in giga units (GiB or GB)
+    pub fn giga_to_bytes(self, giga: f64) -> u64 {
+        (giga * self.base().powi(3)) as u64
+    }
+}
+
+// BCP 47 tag of the user's locale, e.g. "de-DE"
+#[cfg(target_os = "windows")]
+pub fn system_locale() -> String {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    let mut name = [0u16; 85]; // LOCALE_NAME_MAX_LENGTH
+    let len = unsafe { GetUserDefaultLocaleName(&mut name) };
+    if len <= 1 {
+        return "en-US".to_string();
+    }
+    String::from_utf16_lossy(&name[..len as usize - 1])
+}
+
+// POSIX locales look like "de_DE.UTF-8"; LC_ALL overrides LC_NUMERIC overrides LANG
+#[cfg(not(target_os = "windows"))]
+pub fn system_locale() -> String {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| value.split(['.', '@']).next().map(|tag| tag.replace('_', "-")))
+        .filter(|tag| tag != "C" && tag != "POSIX")
+        .unwrap_or_else(|| "en-US".to_string())
+}
+
+// The configured locale, falling back to the system one when unset
+pub fn resolve(configured: &str) -> String {
+    if configured.is_empty() {
+        system_locale()
+    } else {
+        configured.to_string()
+    }
+}
+
+// Whether a locale writes times on a 12-hour clock, as browsers do for it
+fn uses_twelve_hours(locale: &str) -> bool {
+    let region = locale.split('-').nth(1).unwrap_or("").to_uppercase();
+    matches!(region.as_str(), "US" | "CA" | "AU" | "NZ" | "IN" | "PH")
+}
+
+// e.g. "14:30" or "2:30 PM" for a moment today, with the date before it
+// otherwise: "2024-05-01 14:30"
+pub fn format_time(secs: i64, config: &Config) -> String {
+    use chrono::{Local, TimeZone};
+
+    let Some(at) = Local.timestamp_opt(secs, 0).single() else {
+        return String::new();
+    };
+    let twelve_hours = match config.clock {
+        ClockFormat::System => uses_twelve_hours(&resolve(&config.locale)),
+        ClockFormat::TwelveHour => true,
+        ClockFormat::TwentyFourHour => false,
+    };
+    let time = at.format(if twelve_hours { "%-I:%M %p" } else { "%H:%M" });
+    if at.date_naive() == Local::now().date_naive() {
+        time.to_string()
+    } else {
+        format!("{} {}", at.format("%Y-%m-%d"), time)
+    }
+}
+
+// (decimal separator, digit group separator) for a locale's language
+fn separators(locale: &str) -> (char, char) {
+    let language = locale.split('-').next().unwrap_or("").to_lowercase();
+    match language.as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => (',', '.'),
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg" => {
+            (',', '\u{a0}')
+        }
+        _ => ('.', ','),
+    }
+}
+
+// `value` with `decimals` fraction digits and grouped thousands, e.g.
+// 12345.6 -> "12,345.6" (en) or "12.345,6" (de)
+pub fn format_decimal(value: f64, decimals: usize, locale: &str) -> String {
+    let (decimal_sep, group_sep) = separators(locale);
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+
+    let mut out = String::new();
+    // Not for values that round to zero, such as -0.04 to one decimal
+    if value < 0.0 && formatted.bytes().any(|digit| matches!(digit, b'1'..=b'9')) {
+        out.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            out.push(group_sep);
+        }
+        out.push(digit);
+    }
+    if !fraction.is_empty() {
+        out.push(decimal_sep);
+        out.push_str(fraction);
+    }
+    out
+}
+
+// e.g. "512 B", "12.4 KiB", "310.5 MiB" or "1,60 GB": in the largest unit
+// the size reaches, with two decimals from gigabytes up, matching the UI's
+// formatSize
+pub fn format_size(bytes: u64, config: &Config) -> String {
+    const DECIMALS: [usize; 5] = [0, 1, 1, 2, 2];
+    let locale = resolve(&config.locale);
+    let units = config.size_units;
+    let labels = units.labels();
+    let mut value = bytes as f64;
+    let mut power = 0;
+    // As rounded for display, so 1023.96 KiB shows as 1.0 MiB and not 1024.0 KiB
+    let reaches_next = |value: f64, power: usize| {
+        format!("{:.*}", DECIMALS[power], value).parse::<f64>().is_ok_and(|shown| shown >= units.base())
+    };
+    while reaches_next(value, power) && power < labels.len() - 1 {
+        value /= units.base();
+        power += 1;
+    }
+    format!("{} {}", format_decimal(value, DECIMALS[power], &locale), labels[power])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(locale: &str, size_units: SizeUnits) -> Config {
+        Config {
+            locale: locale.to_string(),
+            size_units,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn decimals_use_the_separators_of_the_locale() {
+        let cases = [
+            (0.0, 1, "en-US", "0.0"),
+            (999.0, 0, "en-US", "999"),
+            (1000.0, 0, "en-US", "1,000"),
+            (1234567.891, 2, "en-US", "1,234,567.89"),
+            (1234567.891, 2, "de-DE", "1.234.567,89"),
+            (1234.5, 1, "fr-FR", "1\u{a0}234,5"),
+            (1234.5, 1, "pt-BR", "1.234,5"),
+            (1234.5, 1, "ja-JP", "1,234.5"),
+            (-1234.56, 1, "en-US", "-1,234.6"),
+            (-1234.56, 1, "de-DE", "-1.234,6"),
+            (-0.04, 1, "en-US", "0.0"),
+            (-0.4, 0, "de-DE", "0"),
+        ];
+        for (value, decimals, locale, expected) in cases {
+            assert_eq!(format_decimal(value, decimals, locale), expected, "{} in {}", value, locale);
+        }
+    }
+
+    #[test]
+    fn sizes_move_to_the_next_unit_once_they_round_to_it() {
+        let cases = [
+            (0, "en-US", SizeUnits::Binary, "0 B"),
+            (1023, "en-US", SizeUnits::Binary, "1,023 B"),
+            (1024, "en-US", SizeUnits::Binary, "1.0 KiB"),
+            // 1023.96 KiB
+            (1_048_535, "en-US", SizeUnits::Binary, "1.0 MiB"),
+            (1_048_576, "en-US", SizeUnits::Binary, "1.0 MiB"),
+            (1_610_612_736, "en-US", SizeUnits::Binary, "1.50 GiB"),
+            (1_610_612_736, "de-DE", SizeUnits::Binary, "1,50 GiB"),
+            (999, "en-US", SizeUnits::Decimal, "999 B"),
+            (999_960, "en-US", SizeUnits::Decimal, "1.0 MB"),
+            (1_600_000_000, "fr-FR", SizeUnits::Decimal, "1,60 GB"),
+            (5_000_000_000_000_000, "en-US", SizeUnits::Decimal, "5,000.00 TB"),
+        ];
+        for (bytes, locale, units, expected) in cases {
+            assert_eq!(format_size(bytes, &config(locale, units)), expected, "{} bytes", bytes);
+        }
+    }
+
+    #[test]
+    fn giga_amounts_become_bytes_in_the_chosen_units() {
+        assert_eq!(SizeUnits::Binary.giga_to_bytes(1.0), 1_073_741_824);
+        assert_eq!(SizeUnits::Decimal.giga_to_bytes(1.5), 1_500_000_000);
+        assert_eq!(SizeUnits::Binary.giga_to_bytes(0.0), 0);
+        assert_eq!(SizeUnits::Decimal.giga_to_bytes(-1.0), 0);
+    }
+}
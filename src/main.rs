@@ -1,9 +1,111 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod analyzer;
+mod autostart;
+mod backups;
+mod changelog;
+mod chromium_cache;
+mod clean_budget;
+mod clean_events;
+mod clean_history;
+mod clean_verification;
+mod cleaner;
+mod cleaners;
+mod cli;
+mod cloud_sync;
+mod config_file;
+mod confirmation;
+mod cooldown;
+mod cost;
+mod data_retention;
+mod detect;
+mod disk_space;
+mod elevation;
+mod emergency;
+mod exclusion_packs;
+mod file_finder;
+mod file_locks;
+mod i18n;
+mod impact;
+mod io_priority;
+mod journal;
+mod locale;
+mod maintenance;
+mod memory_schedule;
+mod network_shares;
+mod notifications;
+mod powershell;
+mod profiles;
+mod quarantine;
+mod reboot_queue;
+mod regrowth;
+mod remote_rules;
+mod restore_check;
+mod rule_signatures;
+mod rule_simulation;
+mod rules_export;
+mod scan_history;
+mod schedule;
+mod size_report;
+mod size_stats;
+mod staging;
+mod suggestions;
+mod system_lock;
+mod targets;
+mod temp_advisor;
+mod temp_attribution;
+mod toast;
+mod tui;
+mod undo;
+mod wsl;
+
+use backups::DeviceBackup;
+use clean_budget::Priority;
+use changelog::WhatsNew;
+use cleaner::{CleanProgress, CleanStats, DeletionMode, DirStats, Exclusions, ScanLimits, Strategy};
+use cleaners::Platform;
+use config_file::Scope;
+use cost::OperationCost;
+use data_retention::DataUsage;
+use disk_space::DriveSpace;
+use elevation::Access;
+use emergency::EmergencyStatus;
+use file_finder::FoundFiles;
+use i18n::Language;
+use impact::ImpactReport;
+use journal::CleanJournal;
+use locale::{ClockFormat, SizeUnits};
+use maintenance::MaintenanceAction;
+use memory_schedule::MemorySchedule;
+use notifications::{Level, Notification};
+use profiles::Profile;
+use quarantine::FailedFile;
+use reboot_queue::PendingFile;
+use restore_check::RestoreCheck;
+use scan_history::{ScanChanges, TargetCounts};
+use schedule::Frequency;
 use serde::{Deserialize, Serialize};
+use size_report::SizeReport;
+use size_stats::SizeStats;
+use staging::StagedFile;
+use suggestions::Suggestion;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use tauri::State;
+use tauri::ipc::Channel;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_window_state::StateFlags;
+use targets::{CacheTarget, Category, TargetLabel, TargetPreview};
+use temp_advisor::TempAdvice;
+use undo::{UndoInfo, UndoStats};
 
 #[cfg(target_os = "windows")]
 use windows::Win32::Foundation::*;
@@ -16,9 +118,103 @@ use windows::Win32::System::SystemInformation::*;
 #[cfg(target_os = "windows")]
 use windows::Win32::System::Threading::*;
 
-#[derive(Default)]
 struct AppState {
     config: Mutex<Config>,
+    // Started with --safe-mode: config files are ignored and nothing is deleted
+    safe_mode: bool,
+    // Targets a clean was waiting on when the app restarted as administrator
+    resume_clean: Mutex<Vec<String>>,
+    // View report was clicked on a toast; the window shows the clean history
+    // when it asks, even if it was still loading
+    show_clean_report: AtomicBool,
+}
+
+// The tray menu's entries, kept in step with the auto-clean, schedule and
+// language settings
+struct TrayMenu {
+    show: MenuItem<tauri::Wry>,
+    clean: MenuItem<tauri::Wry>,
+    pause: CheckMenuItem<tauri::Wry>,
+    // The next scheduled clean and a countdown to it; not clickable
+    next_run: MenuItem<tauri::Wry>,
+    run_now: MenuItem<tauri::Wry>,
+    skip_next: MenuItem<tauri::Wry>,
+    quit: MenuItem<tauri::Wry>,
+}
+
+impl TrayMenu {
+    fn relabel(&self, language: &str) {
+        let _ = self.show.set_text(i18n::tr(language, "Show window", &[]));
+        let _ = self.clean.set_text(i18n::tr(language, "Clean now", &[]));
+        let _ = self.pause.set_text(i18n::tr(language, "Pause auto-clean", &[]));
+        let _ = self.run_now.set_text(i18n::tr(language, "Run scheduled clean now", &[]));
+        let _ = self.skip_next.set_text(i18n::tr(language, "Skip next scheduled clean", &[]));
+        let _ = self.quit.set_text(i18n::tr(language, "Quit", &[]));
+    }
+
+    fn show_schedule(&self, config: &Config) {
+        let language = &config.language;
+        let upcoming = schedule::upcoming(config);
+        let text = match &upcoming {
+            None => i18n::tr(language, "No scheduled cleans", &[]),
+            Some(upcoming) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                if upcoming.at <= now {
+                    i18n::tr(language, "Next scheduled clean: due now", &[])
+                } else {
+                    i18n::tr(
+                        language,
+                        "Next scheduled clean: {time} (in {countdown})",
+                        &[
+                            ("time", &locale::format_time(upcoming.at as i64, config)),
+                            ("countdown", &countdown(language, upcoming.at - now)),
+                        ],
+                    )
+                }
+            }
+        };
+        let _ = self.next_run.set_text(text);
+        let _ = self.run_now.set_enabled(upcoming.is_some());
+        let _ = self.skip_next.set_enabled(upcoming.is_some());
+    }
+}
+
+// e.g. "2 d 5 h", "3 h 20 min" or "4 min", rounded up to the minute
+fn countdown(language: &str, secs: u64) -> String {
+    let minutes = secs.div_ceil(60);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        i18n::tr(language, "{days} d {hours} h", &[("days", &days.to_string()), ("hours", &hours.to_string())])
+    } else if hours > 0 {
+        i18n::tr(
+            language,
+            "{hours} h {minutes} min",
+            &[("hours", &hours.to_string()), ("minutes", &minutes.to_string())],
+        )
+    } else {
+        i18n::tr(language, "{minutes} min", &[("minutes", &minutes.to_string())])
+    }
+}
+
+impl AppState {
+    fn check_not_safe_mode(&self) -> Result<(), String> {
+        if self.safe_mode {
+            return Err("Disabled in safe mode; restart without --safe-mode to make changes".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Colors of the window's status messages, alerts and warnings
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+enum StatusPalette {
+    #[default]
+    Standard,
+    // Blue, yellow and vermilion, told apart with any color vision
+    ColorBlind,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -26,6 +222,216 @@ struct Config {
     start_threshold_mb: u64,
     stop_threshold_mb: u64,
     auto_clean_enabled: bool,
+    // Closing the window hides it to the tray; the app keeps running
+    #[serde(default)]
+    close_to_tray: bool,
+    // The app is started when the user logs in
+    #[serde(default)]
+    start_at_login: bool,
+    // Starts at login stay in the tray without showing the window
+    #[serde(default)]
+    start_minimized: bool,
+    // System toasts for cleans that run by themselves (auto-clean, schedules,
+    // the tray) and for their failures
+    #[serde(default = "default_desktop_notifications")]
+    desktop_notifications: bool,
+    // System-wide shortcut for a quick memory clean, e.g. "Ctrl+Alt+Shift+C";
+    // empty turns it off
+    #[serde(default = "default_quick_clean_hotkey")]
+    quick_clean_hotkey: String,
+    // Below this much available memory the threshold alert turns critical
+    #[serde(default = "default_critical_free_mb")]
+    critical_free_mb: u64,
+    // Auto-clean also cleans the targets on the system drive once its free
+    // space falls below this; 0 turns the trigger off
+    #[serde(default)]
+    low_disk_free_mb: u64,
+    // Below this much free space on the system drive, emergency mode cleans
+    // the Quick profile's targets at once and pauses background scans; 0
+    // turns it off
+    #[serde(default = "default_emergency_free_mb")]
+    emergency_free_mb: u64,
+    // Scheduled and low-disk cleans stop after this many minutes, the
+    // targets they did not finish going first next time; 0 for no limit
+    #[serde(default)]
+    auto_clean_max_minutes: u64,
+    // Scheduled cleans missed while the computer was off wait this many
+    // minutes after startup; 0 runs them at once
+    #[serde(default = "default_missed_run_delay_minutes")]
+    missed_run_delay_minutes: u64,
+    // Unity/Unreal project folders whose per-project caches are offered as targets
+    #[serde(default)]
+    game_project_dirs: Vec<String>,
+    // Extra folders cleaned as caches; may use environment variables
+    #[serde(default)]
+    custom_cache_dirs: Vec<String>,
+    // Custom folders the startup check turned off, with why; cleans skip
+    // them until they are turned back on
+    #[serde(default)]
+    disabled_custom_dirs: BTreeMap<String, String>,
+    // Notes and owners of custom folders, keyed by the folder as entered
+    #[serde(default)]
+    custom_dir_labels: BTreeMap<String, TargetLabel>,
+    // Folders on network shares whose size is tracked but never cleaned
+    #[serde(default)]
+    network_share_dirs: Vec<String>,
+    // A rule file fetched every few hours, whose folders are offered as
+    // targets; empty fetches none
+    #[serde(default)]
+    remote_rules_url: String,
+    #[serde(default = "default_remote_rules_hours")]
+    remote_rules_hours: u64,
+    // Ed25519 public keys, in hex, that the remote rule file and rule files
+    // to activate must be signed with for their folders to be cleaned
+    #[serde(default)]
+    trusted_rule_keys: Vec<String>,
+    // Suggested cache folders the user chose never to be offered again
+    #[serde(default)]
+    ignored_suggestions: Vec<String>,
+    // Size journald is vacuumed down to by the Linux maintenance action
+    #[serde(default = "default_journal_max_mb")]
+    journal_max_mb: u64,
+    // How long impact analysis watches cleaned targets regrow
+    #[serde(default = "default_impact_period_days")]
+    impact_period_days: u64,
+    // Language of the window, the tray and notifications, as an i18n code
+    #[serde(default = "default_language")]
+    language: String,
+    // BCP 47 tag used to format numbers and dates; empty follows the system
+    #[serde(default)]
+    locale: String,
+    #[serde(default)]
+    clock: ClockFormat,
+    #[serde(default)]
+    size_units: SizeUnits,
+    #[serde(default)]
+    status_palette: StatusPalette,
+    // Size caps in MiB for targets trimmed to size, keyed by target id
+    #[serde(default)]
+    size_budgets_mb: BTreeMap<String, u64>,
+    // How many recent files log-style targets keep, keyed by target id
+    #[serde(default)]
+    keep_newest_counts: BTreeMap<String, usize>,
+    // Depth and sampling limits for sizing huge targets, keyed by target id
+    #[serde(default)]
+    scan_limits: BTreeMap<String, ScanLimits>,
+    // Size every target in streaming mode, with bounded memory on trees of
+    // millions of files
+    #[serde(default)]
+    streaming_scan: bool,
+    // Rescan targets after each clean and flag files that came back or were
+    // not really deleted
+    #[serde(default)]
+    verify_cleans: bool,
+    // A second launch with --clean-now cleans memory in this one; any local
+    // program can start the app that way
+    #[serde(default = "default_accept_forwarded_cleans")]
+    accept_forwarded_cleans: bool,
+    #[serde(default)]
+    deletion: DeletionMode,
+    // Days cleaned files stay in the quarantine folder before they are
+    // deleted for good
+    #[serde(default = "default_staging_retention_days")]
+    staging_retention_days: u64,
+    // Clean history, size snapshots and notifications older than this many
+    // days are dropped; 0 keeps them all
+    #[serde(default = "default_history_retention_days")]
+    history_retention_days: u64,
+    // Each of those files is kept under this size, dropping the oldest
+    // entries first; 0 for no cap
+    #[serde(default = "default_history_max_mb")]
+    history_max_mb: u64,
+    // Glob patterns of files and folders cleans keep, keyed by target id
+    // ("*" for every target)
+    #[serde(default)]
+    exclusions: BTreeMap<String, Vec<String>>,
+    // Files used within this many days are left alone, keyed by target id
+    // ("*" for every target a target's own entry overrides)
+    #[serde(default)]
+    min_age_days: BTreeMap<String, u64>,
+    // How often targets are cleaned in the background, keyed by target id
+    // ("*" for every target a target's own entry overrides)
+    #[serde(default)]
+    schedules: BTreeMap<String, Frequency>,
+    // Hours after a clean before the low-disk auto-clean cleans a target
+    // again, keyed by target id ("*" for every target)
+    #[serde(default)]
+    clean_cooldown_hours: BTreeMap<String, u64>,
+    // Ids of targets only cleaned once the user confirms; automatic cleans
+    // leave them out and ask in a notification instead
+    #[serde(default)]
+    confirm_targets: Vec<String>,
+    // Minutes between background scans that keep target sizes current; 0
+    // scans only when asked. Scans never clean.
+    #[serde(default = "default_scan_interval_minutes")]
+    scan_interval_minutes: u64,
+    // Hours between size snapshots for the growth chart, taken by a scan
+    // when no other scan recorded one in that time; 0 leaves snapshots to
+    // the other scans
+    #[serde(default)]
+    snapshot_interval_hours: u64,
+    // Memory cleans every few hours or at a set time, whatever the threshold
+    #[serde(default)]
+    memory_schedule: MemorySchedule,
+    // Named target selections, picked in the window or with --profile
+    #[serde(default = "profiles::defaults")]
+    profiles: Vec<Profile>,
+}
+
+fn default_quick_clean_hotkey() -> String {
+    "Ctrl+Alt+Shift+C".to_string()
+}
+
+fn default_desktop_notifications() -> bool {
+    true
+}
+
+fn default_accept_forwarded_cleans() -> bool {
+    true
+}
+
+fn default_critical_free_mb() -> u64 {
+    512
+}
+
+fn default_journal_max_mb() -> u64 {
+    500
+}
+
+fn default_impact_period_days() -> u64 {
+    7
+}
+
+fn default_language() -> String {
+    i18n::ENGLISH.to_string()
+}
+
+fn default_staging_retention_days() -> u64 {
+    7
+}
+
+fn default_history_retention_days() -> u64 {
+    365
+}
+
+fn default_history_max_mb() -> u64 {
+    10
+}
+
+fn default_emergency_free_mb() -> u64 {
+    1024
+}
+
+fn default_remote_rules_hours() -> u64 {
+    24
+}
+
+fn default_missed_run_delay_minutes() -> u64 {
+    5
+}
+
+fn default_scan_interval_minutes() -> u64 {
+    60
 }
 
 impl Default for Config {
@@ -34,6 +440,51 @@ impl Default for Config {
             start_threshold_mb: 2048,
             stop_threshold_mb: 1024,
             auto_clean_enabled: true,
+            close_to_tray: false,
+            start_at_login: false,
+            start_minimized: false,
+            desktop_notifications: default_desktop_notifications(),
+            quick_clean_hotkey: default_quick_clean_hotkey(),
+            critical_free_mb: default_critical_free_mb(),
+            low_disk_free_mb: 0,
+            emergency_free_mb: default_emergency_free_mb(),
+            auto_clean_max_minutes: 0,
+            missed_run_delay_minutes: default_missed_run_delay_minutes(),
+            game_project_dirs: Vec::new(),
+            custom_cache_dirs: Vec::new(),
+            disabled_custom_dirs: BTreeMap::new(),
+            custom_dir_labels: BTreeMap::new(),
+            network_share_dirs: Vec::new(),
+            remote_rules_url: String::new(),
+            remote_rules_hours: default_remote_rules_hours(),
+            trusted_rule_keys: Vec::new(),
+            ignored_suggestions: Vec::new(),
+            journal_max_mb: default_journal_max_mb(),
+            impact_period_days: default_impact_period_days(),
+            language: default_language(),
+            locale: String::new(),
+            clock: ClockFormat::System,
+            size_units: SizeUnits::Binary,
+            status_palette: StatusPalette::Standard,
+            size_budgets_mb: BTreeMap::new(),
+            keep_newest_counts: BTreeMap::new(),
+            scan_limits: BTreeMap::new(),
+            streaming_scan: false,
+            verify_cleans: false,
+            accept_forwarded_cleans: default_accept_forwarded_cleans(),
+            deletion: DeletionMode::Permanent,
+            staging_retention_days: default_staging_retention_days(),
+            history_retention_days: default_history_retention_days(),
+            history_max_mb: default_history_max_mb(),
+            exclusions: BTreeMap::new(),
+            min_age_days: BTreeMap::new(),
+            schedules: BTreeMap::new(),
+            clean_cooldown_hours: BTreeMap::new(),
+            confirm_targets: Vec::new(),
+            scan_interval_minutes: default_scan_interval_minutes(),
+            snapshot_interval_hours: 0,
+            memory_schedule: MemorySchedule::default(),
+            profiles: profiles::defaults(),
         }
     }
 }
@@ -47,6 +498,56 @@ struct MemoryInfo {
     usage_percent: f32,
 }
 
+#[derive(Serialize, Clone)]
+struct CacheTargetInfo {
+    id: String,
+    name: String,
+    category: Category,
+    paths: Vec<String>,
+    // e.g. "older than 30 days"; absent when everything is deleted
+    strategy: Option<String>,
+    // Set for targets trimmed to size, whose budget can be edited
+    size_budget_mb: Option<u64>,
+    // Set for targets that keep their newest files, whose count can be edited
+    keep_newest: Option<usize>,
+    report_only: bool,
+    // Off by default: left out of category selections
+    warning: Option<&'static str>,
+    schedule: Frequency,
+    // Seconds since the Unix epoch; absent for unscheduled targets
+    next_run: Option<u64>,
+    // Until when the low-disk auto-clean leaves the target alone after its
+    // last clean, in seconds since the Unix epoch
+    cooldown_until: Option<u64>,
+    // When an automatic clean first left the target out for asking first;
+    // absent unless it is waiting to be cleaned from the window
+    pending_since: Option<u64>,
+    // The program using the target's files, when it is running and the
+    // target is skipped by cleans for it
+    running_app: Option<&'static str>,
+    access: Access,
+    stats: DirStats,
+    // What a clean would free now, leaving out files too new for the age
+    // policy, excluded or held open by another program. Working it out walks
+    // the files again, so scans leave it absent (except for targets whose
+    // program is running) and the window asks for it per target.
+    reclaimable_bytes: Option<u64>,
+    // Days the target takes to fill up again after a clean; absent until
+    // scans have followed one for long enough
+    regrow_days: Option<f64>,
+    // The note and owner of a custom folder
+    label: Option<TargetLabel>,
+}
+
+#[derive(Serialize)]
+struct CleanerInfo {
+    name: &'static str,
+    platform: Platform,
+    requires_admin: bool,
+    // Targets it found on this machine
+    target_count: usize,
+}
+
 #[cfg(target_os = "windows")]
 #[tauri::command]
 fn get_memory_info() -> Result<MemoryInfo, String> {
@@ -128,28 +629,1903 @@ fn clean_memory_cache(_target_mb: u64) -> Result<u64, String> {
     Err("Only supported on Windows".to_string())
 }
 
+// Filesystem-heavy work runs on a blocking pool thread; synchronous commands
+// would run on the main thread and freeze the window until they finish
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+}
+
+// Free memory cache as auto-clean does and tell the window, which may be
+// hidden, to refresh. Returns the message recorded in the notifications.
+fn clean_memory_in_background(app: &AppHandle, source: &str) -> String {
+    let config = app.state::<AppState>().config.lock().unwrap().clone();
+    let target_mb = config.start_threshold_mb.saturating_sub(config.stop_threshold_mb);
+    let source = i18n::tr(&config.language, source, &[]);
+    match clean_memory_cache(target_mb) {
+        Ok(cleaned_mb) => {
+            let freed = locale::format_size(cleaned_mb * 1024 * 1024, &config);
+            let message = i18n::tr(
+                &config.language,
+                "{source}: freed {size} of memory cache",
+                &[("source", &source), ("size", &freed)],
+            );
+            notifications::push(Level::Success, message.clone());
+            let _ = app.emit("memory-cleaned", cleaned_mb);
+            message
+        }
+        Err(e) => {
+            let message = i18n::tr(&config.language, "{source} failed: {error}", &[("source", &source), ("error", &e)]);
+            notifications::push(Level::Warning, message.clone());
+            message
+        }
+    }
+}
+
+fn show_toast(app: &AppHandle, body: impl Into<String>) {
+    let _ = app
+        .notification()
+        .builder()
+        .title("Memory Cache Manager")
+        .body(body)
+        .show();
+}
+
+// A toast for a clean nobody watched start, unless they are turned off
+fn notify_desktop(app: &AppHandle, config: &Config, body: impl Into<String>) {
+    if config.desktop_notifications {
+        show_toast(app, body);
+    }
+}
+
+// The toast after a disk clean that ran by itself. View report opens the
+// clean history; Undo, offered when the clean quarantined or recycled
+// files, puts them back once.
+fn notify_clean(app: &AppHandle, config: &Config, body: String, undoable: undo::Undoable) {
+    if !config.desktop_notifications {
+        return;
+    }
+    let mut buttons = vec![(toast::Action::ViewReport, i18n::tr(&config.language, "View report", &[]))];
+    if !undoable.is_empty() {
+        buttons.push((toast::Action::Undo, i18n::tr(&config.language, "Undo", &[])));
+    }
+    let handle = app.clone();
+    let undoable = Mutex::new(Some(undoable));
+    let shown = toast::show(
+        &app.config().identifier,
+        "Memory Cache Manager",
+        &body,
+        &buttons,
+        move |action| match action {
+            toast::Action::ViewReport => {
+                handle.state::<AppState>().show_clean_report.store(true, Ordering::Relaxed);
+                show_main_window(&handle);
+                if let Some(window) = handle.get_webview_window("main") {
+                    let _ = window.eval("showCleanReportIfAsked()");
+                }
+            }
+            toast::Action::Undo => {
+                if let Some(undoable) = undoable.lock().unwrap().take() {
+                    let handle = handle.clone();
+                    std::thread::spawn(move || undo_from_toast(&handle, &undoable));
+                }
+            }
+        },
+    );
+    if shown.is_err() {
+        show_toast(app, body);
+    }
+}
+
+fn undo_from_toast(app: &AppHandle, undoable: &undo::Undoable) {
+    let state = app.state::<AppState>();
+    let config = state.config.lock().unwrap().clone();
+    let result = state.check_not_safe_mode().and_then(|()| undo::revert(undoable));
+    let (level, message) = match result {
+        Ok(stats) if stats.failed > 0 => (
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "Undo restored {restored} files; {failed} could not be restored",
+                &[("restored", &stats.restored.to_string()), ("failed", &stats.failed.to_string())],
+            ),
+        ),
+        Ok(stats) => (
+            Level::Success,
+            i18n::tr(&config.language, "Undo restored {restored} files", &[("restored", &stats.restored.to_string())]),
+        ),
+        Err(e) => (Level::Warning, i18n::tr(&config.language, "Undo failed: {error}", &[("error", &e)])),
+    };
+    notifications::push(level, message.clone());
+    show_toast(app, message);
+    let _ = app.emit("clean-undone", ());
+}
+
+// e.g. "; 12 files (310 MiB) could not be deleted; kept 40 recently used
+// files (1.20 GiB)", or nothing when every file went
+fn left_behind(stats: CleanStats, config: &Config) -> String {
+    let locale = locale::resolve(&config.locale);
+    let files = |count: u64, bytes: u64| {
+        i18n::tr(
+            &config.language,
+            "{count} files ({size})",
+            &[
+                ("count", &locale::format_decimal(count as f64, 0, &locale)),
+                ("size", &locale::format_size(bytes, config)),
+            ],
+        )
+    };
+    let mut text = String::new();
+    if stats.files_failed > 0 {
+        text.push_str(&i18n::tr(
+            &config.language,
+            "; {files} could not be deleted (in use or no permission)",
+            &[("files", &files(stats.files_failed, stats.failed_bytes))],
+        ));
+    }
+    if stats.files_skipped > 0 {
+        text.push_str(&i18n::tr(
+            &config.language,
+            "; kept {files} as recently used",
+            &[("files", &files(stats.files_skipped, stats.skipped_bytes))],
+        ));
+    }
+    text
+}
+
+// e.g. "Auto-clean: cleaned 1,243 files (3.20 GiB)", with the files left
+// behind
+fn disk_clean_summary(source: &str, stats: CleanStats, config: &Config) -> String {
+    let locale = locale::resolve(&config.locale);
+    let summary = i18n::tr(
+        &config.language,
+        "{source}: cleaned {count} files ({size})",
+        &[
+            ("source", &i18n::tr(&config.language, source, &[])),
+            ("count", &locale::format_decimal(stats.files_deleted as f64, 0, &locale)),
+            ("size", &locale::format_size(stats.freed_bytes, config)),
+        ],
+    );
+    summary + &left_behind(stats, config)
+}
+
+// The global hotkey's clean; the result is always shown as a system toast
+// since the window is often hidden
+fn quick_clean(app: &AppHandle) {
+    let message = clean_memory_in_background(app, "Quick clean");
+    show_toast(app, message);
+}
+
+// Replace the registered quick clean hotkey; an empty one just unregisters
+fn set_quick_clean_hotkey(app: &AppHandle, hotkey: &str) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister hotkey: {}", e))?;
+    if hotkey.is_empty() {
+        return Ok(());
+    }
+    let shortcut: Shortcut = hotkey
+        .parse()
+        .map_err(|e| format!("Invalid hotkey {}: {}", hotkey, e))?;
+    shortcuts
+        .register(shortcut)
+        .map_err(|e| format!("Failed to register hotkey {}: {}", hotkey, e))
+}
+
+fn disk_is_low(config: &Config, system_free_bytes: Option<u64>) -> bool {
+    config.low_disk_free_mb > 0 && system_free_bytes.is_some_and(|free| free < config.low_disk_free_mb * 1024 * 1024)
+}
+
+// What auto-clean should clean: memory once the cache reaches the start
+// threshold, the system drive's targets once its free space runs low,
+// given what cleaning those past their cooldown would free (None while it
+// is not low or none are)
+fn should_auto_clean(config: &Config, cache_mb: u64, system_reclaimable_bytes: Option<u64>) -> (bool, bool) {
+    if !config.auto_clean_enabled {
+        return (false, false);
+    }
+    let memory = cache_mb >= config.start_threshold_mb;
+    let disk = system_reclaimable_bytes.is_some_and(|bytes| bytes > 0);
+    (memory, disk)
+}
+
+// The targets on the system drive that a schedule for every target would
+// clean
+fn low_disk_targets(config: &Config) -> Vec<CacheTarget> {
+    let system = cleaner::volume_id(&disk_space::system_root());
+    targets::builtin_targets(config)
+        .into_iter()
+        .filter(|target| !target.report_only && target.warning.is_none())
+        .filter(|target| config.schedules.get(&target.id) != Some(&Frequency::Never))
+        .filter(|target| system.is_some() && target.volume == system)
+        .collect()
+}
+
+// How long an automatic clean may run
+fn auto_clean_budget(config: &Config) -> Option<std::time::Duration> {
+    (config.auto_clean_max_minutes > 0).then(|| std::time::Duration::from_secs(config.auto_clean_max_minutes.saturating_mul(60)))
+}
+
+// Tell the user an automatic clean ran out of time
+fn report_carried_over(config: &Config, count: usize) {
+    if count == 0 {
+        return;
+    }
+    notifications::push(
+        Level::Info,
+        i18n::tr(
+            &config.language,
+            "Auto-clean stopped after {minutes} min; {count} targets carry over to the next run",
+            &[("minutes", &config.auto_clean_max_minutes.to_string()), ("count", &count.to_string())],
+        ),
+    );
+}
+
+// Clean `ready`, the system drive's targets past their cooldown. Those the
+// time limit cuts off cool down too, and go first once they are ready again.
+fn clean_low_disk(config: &Config, ready: &[CacheTarget]) -> (CleanStats, undo::Undoable) {
+    cooldown::start(ready.iter().map(|target| target.id.as_str()));
+    let mut targets = ready.to_vec();
+    clean_budget::order(&mut targets);
+    if targets.is_empty() {
+        return Default::default();
+    }
+    let batch = undo::begin_automatic(config.deletion);
+    let history = clean_history::Recorder::start("low_disk").verified(config.verify_cleans);
+    let (stats, carried_over) = cleaner::track_progress(
+        |_| {},
+        || clean_budget::clean(&targets, auto_clean_budget(config), &history),
+    );
+    history.finish();
+    let undoable = undo::finish(batch);
+    report_carried_over(config, carried_over);
+    (stats, undoable)
+}
+
+// Clean the Quick profile's targets at once, unless in safe mode, and tell
+// the user what to try next
+fn enter_emergency(app: &AppHandle, config: &Config, free_bytes: u64) {
+    let free = locale::format_size(free_bytes, config);
+    let (targets, _) = confirmation::hold_back(config, emergency::quick_targets(config));
+    if app.state::<AppState>().safe_mode || targets.is_empty() {
+        notifications::push(
+            Level::Warning,
+            i18n::tr(&config.language, "The system drive is almost full ({free} free)", &[("free", &free)]),
+        );
+    } else {
+        let batch = undo::begin_automatic(config.deletion);
+        let history = clean_history::Recorder::start("emergency").verified(config.verify_cleans);
+        let stats = cleaner::track_progress(|_| {}, || targets::clean_targets(&targets, &history, |_| {}));
+        history.finish();
+        let undoable = undo::finish(batch);
+        notifications::push(
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "The system drive is almost full ({free} free): emergency mode freed {size}",
+                &[("free", &free), ("size", &locale::format_size(stats.freed_bytes, config))],
+            ),
+        );
+        notify_clean(app, config, disk_clean_summary("Emergency clean", stats, config), undoable);
+        let _ = app.emit("disk-cleaned", stats.freed_bytes);
+    }
+    emergency::find_suggestions(config, &targets);
+    let _ = app.emit("emergency-changed", true);
+}
+
+// Auto-clean runs here rather than in the window so it keeps working while
+// the window is hidden to the tray. Also keeps the tray tooltip and the
+// countdown to the next scheduled clean current.
+fn run_auto_clean(app: AppHandle) {
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(30));
+        let Ok(info) = get_memory_info() else {
+            continue;
+        };
+        let config = app.state::<AppState>().config.lock().unwrap().clone();
+        if let Some(tray) = app.tray_by_id("main") {
+            let cache = locale::format_size(info.cache_mb * 1024 * 1024, &config);
+            let _ = tray.set_tooltip(Some(format!("Memory Cache Manager · cache {}", cache)));
+        }
+        if let Some(tray) = app.try_state::<TrayMenu>() {
+            tray.show_schedule(&config);
+        }
+        let system_free = if config.low_disk_free_mb > 0 || config.emergency_free_mb > 0 {
+            disk_space::free_bytes(&disk_space::system_root())
+        } else {
+            None
+        };
+        match emergency::update(&config, system_free) {
+            emergency::Change::Entered => enter_emergency(&app, &config, system_free.unwrap_or(0)),
+            emergency::Change::Left => {
+                notifications::push(
+                    Level::Success,
+                    i18n::tr(
+                        &config.language,
+                        "The system drive has {free} free again; emergency mode is over",
+                        &[("free", &locale::format_size(system_free.unwrap_or(0), &config))],
+                    ),
+                );
+                let _ = app.emit("emergency-changed", false);
+            }
+            emergency::Change::Unchanged => {}
+        }
+        // While free space stays low, each target is measured, which walks
+        // it, and cleaned again only once its cooldown is over. Those that
+        // ask first cool down too, so they are not looked at every time.
+        let ready = if config.auto_clean_enabled && disk_is_low(&config, system_free) {
+            let (ready, held) = confirmation::hold_back(&config, cooldown::ready(&config, low_disk_targets(&config)));
+            cooldown::start(held.iter().map(|target| target.id.as_str()));
+            ready
+        } else {
+            Vec::new()
+        };
+        let reclaimable: Option<u64> = (!ready.is_empty()).then(|| ready.iter().map(targets::reclaimable).sum());
+        let (memory, disk) = should_auto_clean(&config, info.cache_mb, reclaimable);
+        if reclaimable.is_some() && !disk {
+            cooldown::start(ready.iter().map(|target| target.id.as_str()));
+        }
+        if memory {
+            let message = clean_memory_in_background(&app, "Auto-clean");
+            notify_desktop(&app, &config, message);
+        }
+        if disk {
+            let (stats, undoable) = clean_low_disk(&config, &ready);
+            notifications::push(
+                Level::Warning,
+                i18n::tr(
+                    &config.language,
+                    "Low disk space on the system drive ({free} free): auto-clean freed {size}",
+                    &[
+                        ("free", &locale::format_size(system_free.unwrap_or(0), &config)),
+                        ("size", &locale::format_size(stats.freed_bytes, &config)),
+                    ],
+                ),
+            );
+            notify_clean(&app, &config, disk_clean_summary("Low disk space auto-clean", stats, &config), undoable);
+            let _ = app.emit("disk-cleaned", stats.freed_bytes);
+        }
+    }
+}
+
+fn set_auto_clean(app: &AppHandle, enabled: bool) {
+    app.state::<AppState>().config.lock().unwrap().auto_clean_enabled = enabled;
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        let _ = tray.pause.set_checked(!enabled);
+    }
+    let _ = app.emit("auto-clean-changed", enabled);
+}
+
+// Clean memory in the background as the tray's Clean now does
+fn clean_now(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let message = clean_memory_in_background(&app, "Manual clean");
+        let config = app.state::<AppState>().config.lock().unwrap().clone();
+        notify_desktop(&app, &config, message);
+    });
+}
+
+// Move the next scheduled clean's targets on to the run after it
+fn skip_next_schedule(app: &AppHandle) {
+    let config = app.state::<AppState>().config.lock().unwrap().clone();
+    let Some(upcoming) = schedule::upcoming(&config) else {
+        return;
+    };
+    schedule::skip(&config, &upcoming.targets);
+    let names: Vec<_> = upcoming.targets.iter().map(|target| target.name.as_str()).collect();
+    notifications::push(
+        Level::Info,
+        i18n::tr(&config.language, "Skipped the next scheduled clean of {targets}", &[("targets", &names.join(", "))]),
+    );
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        tray.show_schedule(&config);
+    }
+}
+
+// Launching the app again hands its arguments to the running instance
+// instead of opening a second window over the same folders. A clean is
+// forwarded, unless in safe mode or turned off, and the window brought to
+// the front; starts at login, which find the app already running, change
+// nothing.
+fn handle_second_launch(app: &AppHandle, args: &[String]) {
+    if args.iter().any(|arg| arg == autostart::AT_LOGIN) {
+        return;
+    }
+    if args.iter().any(|arg| arg == CLEAN_NOW) {
+        let state = app.state::<AppState>();
+        let language = state.config.lock().unwrap().language.clone();
+        let refused = match state.check_not_safe_mode() {
+            Err(e) => Some(e),
+            Ok(()) if !state.config.lock().unwrap().accept_forwarded_cleans => Some(i18n::tr(
+                &language,
+                "cleans from other launches are turned off under Advanced",
+                &[],
+            )),
+            Ok(()) => None,
+        };
+        match refused {
+            Some(reason) => notifications::push(
+                Level::Info,
+                i18n::tr(&language, "Ignored --clean-now from another launch: {reason}", &[("reason", &reason)]),
+            ),
+            None => clean_now(app),
+        }
+    }
+    show_main_window(app);
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn build_tray(app: &tauri::App) -> tauri::Result<()> {
+    let config = app.state::<AppState>().config.lock().unwrap().clone();
+    let show = MenuItem::with_id(app, "show", "Show window", true, None::<&str>)?;
+    let clean = MenuItem::with_id(app, "clean", "Clean now", true, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(app, "pause", "Pause auto-clean", true, !config.auto_clean_enabled, None::<&str>)?;
+    let next_run = MenuItem::with_id(app, "next_run", "No scheduled cleans", false, None::<&str>)?;
+    let run_now = MenuItem::with_id(app, "run_now", "Run scheduled clean now", true, None::<&str>)?;
+    let skip_next = MenuItem::with_id(app, "skip_next", "Skip next scheduled clean", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &show,
+            &clean,
+            &pause,
+            &PredefinedMenuItem::separator(app)?,
+            &next_run,
+            &run_now,
+            &skip_next,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+    let items = TrayMenu {
+        show,
+        clean,
+        pause,
+        next_run,
+        run_now,
+        skip_next,
+        quit,
+    };
+    items.relabel(&config.language);
+    items.show_schedule(&config);
+    app.manage(items);
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("Memory Cache Manager")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "show" => show_main_window(app),
+            "clean" => clean_now(app),
+            "pause" => {
+                let enabled = app.state::<AppState>().config.lock().unwrap().auto_clean_enabled;
+                set_auto_clean(app, !enabled);
+            }
+            "run_now" => schedule::request_run_now(),
+            "skip_next" => skip_next_schedule(app),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+// Say which scheduled cleans came round while the computer was off, and
+// when they run
+fn report_missed_runs(app: &AppHandle, config: &Config, missed: &[CacheTarget]) {
+    let names: Vec<_> = missed.iter().map(|target| target.name.as_str()).collect();
+    let names = names.join(", ");
+    let message = if config.missed_run_delay_minutes == 0 {
+        i18n::tr(
+            &config.language,
+            "Scheduled cleans missed while the computer was off run now ({targets})",
+            &[("targets", &names)],
+        )
+    } else {
+        i18n::tr(
+            &config.language,
+            "Scheduled cleans missed while the computer was off run in {minutes} min ({targets})",
+            &[("minutes", &config.missed_run_delay_minutes.to_string()), ("targets", &names)],
+        )
+    };
+    notifications::push(Level::Info, message.clone());
+    notify_desktop(app, config, message);
+}
+
+// Clean memory and the targets whose schedule has come round and take size
+// snapshots, checking once a minute. Runs for as long as the app does.
+fn run_schedules(app: tauri::AppHandle) {
+    // Target runs that came round while the app was not running wait out the
+    // grace delay, so they do not slow down logging in
+    let config = app.state::<AppState>().config.lock().unwrap().clone();
+    let missed = schedule::missed(&config);
+    let started = std::time::Instant::now();
+    let grace = std::time::Duration::from_secs(config.missed_run_delay_minutes.saturating_mul(60));
+    if !missed.is_empty() {
+        report_missed_runs(&app, &config, &missed);
+    }
+    loop {
+        let run_now = schedule::wait(std::time::Duration::from_secs(60));
+        let config = app.state::<AppState>().config.lock().unwrap().clone();
+        if memory_schedule::is_due(&config.memory_schedule) {
+            memory_schedule::record_run();
+            let message = clean_memory_in_background(&app, "Scheduled clean");
+            notify_desktop(&app, &config, message);
+        }
+        // Snapshots scan every target, which a nearly full drive can do without
+        if !emergency::is_active() && size_stats::snapshot_due(config.snapshot_interval_hours) {
+            let _ = app.emit("targets-scanned", scan_and_record(&config));
+        }
+        staging::purge_expired(config.staging_retention_days);
+        data_retention::compact_if_due(&config);
+        remote_rules::fetch_if_due(&config);
+        if !run_now && !missed.is_empty() && started.elapsed() < grace {
+            continue;
+        }
+
+        // Run now from the tray cleans the next run's targets early
+        let due = if run_now {
+            schedule::upcoming(&config).map_or_else(Vec::new, |upcoming| upcoming.targets)
+        } else {
+            schedule::due(&config)
+        };
+        // Targets that ask first count as run, so they are asked for once
+        // per period
+        let (mut due, held) = confirmation::hold_back(&config, due);
+        schedule::record_run(&held);
+        clean_budget::order(&mut due);
+        if due.is_empty() {
+            continue;
+        }
+
+        let batch = undo::begin_automatic(config.deletion);
+        let history = clean_history::Recorder::start("schedule").verified(config.verify_cleans);
+        let (stats, carried_over) = cleaner::track_progress(
+            |_| {},
+            || clean_budget::clean(&due, auto_clean_budget(&config), &history),
+        );
+        history.finish();
+        let undoable = undo::finish(batch);
+        // Only the time limit stops a scheduled clean, and one cut short
+        // still counts as run; what it did not get to goes first next time
+        report_carried_over(&config, carried_over);
+        schedule::record_run(&due);
+        let names: Vec<_> = due.iter().map(|target| target.name.as_str()).collect();
+        notifications::push(
+            Level::Info,
+            i18n::tr(
+                &config.language,
+                "Scheduled clean freed {size} ({targets})",
+                &[("size", &locale::format_size(stats.freed_bytes, &config)), ("targets", &names.join(", "))],
+            ),
+        );
+        notify_clean(&app, &config, disk_clean_summary("Scheduled clean", stats, &config), undoable);
+        if let Some(tray) = app.try_state::<TrayMenu>() {
+            tray.show_schedule(&config);
+        }
+    }
+}
+
+fn scan_targets(config: &Config) -> Vec<CacheTargetInfo> {
+    targets::forget_found();
+    let last_runs = schedule::last_runs();
+    let cooldowns = cooldown::started();
+    let pending = confirmation::pending(config);
+    let processes = OnceCell::new();
+    targets::builtin_targets(config)
+        .into_iter()
+        .map(|target| {
+            let stats = target.scan();
+            let schedule = schedule::frequency(config, &target);
+            let next_run = schedule::next_run(config, &target, &last_runs);
+            let cooldown_until = (!target.report_only)
+                .then(|| cooldown::until(config, &target.id, &cooldowns))
+                .flatten();
+            let pending_since = pending.get(&target.id).copied();
+            let running_app = target.running_owner(&processes);
+            let requires_admin = cleaners::of(&target).requires_admin();
+            // Cleans skip targets whose program is running
+            let reclaimable_bytes = running_app.map(|_| 0);
+            CacheTargetInfo {
+                id: target.id,
+                name: target.name,
+                category: target.category,
+                paths: target.paths.iter().map(|p| p.display().to_string()).collect(),
+                strategy: target.strategy.describe(),
+                size_budget_mb: match target.strategy {
+                    Strategy::TrimToSize(bytes) => Some(bytes / (1024 * 1024)),
+                    _ => None,
+                },
+                keep_newest: match target.strategy {
+                    Strategy::KeepNewestN(count) => Some(count),
+                    _ => None,
+                },
+                report_only: target.report_only,
+                warning: target.warning,
+                schedule,
+                next_run,
+                cooldown_until,
+                pending_since,
+                running_app,
+                access: elevation::access(&stats, requires_admin),
+                stats,
+                reclaimable_bytes,
+                regrow_days: None,
+                label: target.label,
+            }
+        })
+        .collect()
+}
+
+// Fill in what a clean would free for scanned targets that were sized in
+// full, one thread per target
+fn add_reclaimable(config: &Config, scanned: &mut [CacheTargetInfo]) {
+    let targets = targets::builtin_targets(config);
+    std::thread::scope(|scope| {
+        for info in scanned.iter_mut() {
+            if info.reclaimable_bytes.is_some() || info.stats.approximate {
+                continue;
+            }
+            if let Some(target) = targets.iter().find(|target| target.id == info.id) {
+                scope.spawn(move || info.reclaimable_bytes = Some(targets::reclaimable(target)));
+            }
+        }
+    });
+}
+
+// Scan every target, recording the cost and the sizes for change and regrowth tracking
+fn scan_and_record(config: &Config) -> Vec<CacheTargetInfo> {
+    let mut scanned = cost::measure(
+        "scan",
+        || scan_targets(config),
+        |scanned| scanned.iter().map(|target| target.stats.file_count).sum(),
+    );
+    scan_history::record(scanned.iter().map(|target| {
+        let counts = TargetCounts {
+            size_bytes: target.stats.size_bytes,
+            file_count: target.stats.file_count,
+        };
+        (target.id.clone(), target.name.clone(), counts)
+    }));
+    size_stats::record(
+        scanned.iter().map(|target| (target.id.clone(), target.stats.size_bytes)),
+        disk_space::free_bytes(&disk_space::system_root()),
+    );
+    let regrow_days = regrowth::observe(scanned.iter().map(|target| (target.id.clone(), target.stats.size_bytes)));
+    for target in &mut scanned {
+        target.regrow_days = regrow_days.get(&target.id).copied();
+    }
+    scanned
+}
+
+// With `force`, every file is looked at again rather than only those in
+// folders that changed since the last scan
+#[tauri::command]
+async fn get_cache_targets(state: State<'_, AppState>, force: bool) -> Result<Vec<CacheTargetInfo>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        if force {
+            cleaner::forget_dir_sizes();
+        }
+        scan_and_record(&config)
+    })
+    .await
+}
+
+// What a clean would free of one target now, for when the window shows it
+#[tauri::command]
+async fn get_reclaimable(state: State<'_, AppState>, id: String) -> Result<u64, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let target = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .ok_or_else(|| format!("Unknown target: {}", id))?;
+        if target.running_owner(&OnceCell::new()).is_some() {
+            return Ok(0);
+        }
+        Ok(targets::reclaimable(&target))
+    })
+    .await?
+}
+
+// Rescan on the configured interval, independently of clean schedules, and
+// hand the window the result. The window scans on its own at startup.
+fn run_scans(app: AppHandle) {
+    let mut last_scan = std::time::Instant::now();
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(60));
+        let config = app.state::<AppState>().config.lock().unwrap().clone();
+        let interval = std::time::Duration::from_secs(config.scan_interval_minutes.saturating_mul(60));
+        // Held back in emergency mode, then run as soon as it ends
+        if config.scan_interval_minutes == 0 || last_scan.elapsed() < interval || emergency::is_active() {
+            continue;
+        }
+        last_scan = std::time::Instant::now();
+        let _ = app.emit("targets-scanned", scan_and_record(&config));
+    }
+}
+
+// Clean a made-up set of files into the quarantine folder and put them back,
+// comparing each with the original
+#[tauri::command]
+async fn check_quarantine_restore() -> Result<RestoreCheck, String> {
+    run_blocking(restore_check::run).await?
+}
+
+// Write the PowerShell module into the user's module folders, returning the
+// files written
+#[tauri::command]
+fn install_powershell_module() -> Result<Vec<PathBuf>, String> {
+    powershell::install(None)
+}
+
+// The release notes and targets new since the version last seen, once after
+// an update
+#[tauri::command]
+async fn get_whats_new(state: State<'_, AppState>) -> Result<Option<WhatsNew>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || changelog::pending(&config)).await
+}
+
+#[tauri::command]
+fn dismiss_whats_new() {
+    changelog::mark_seen();
+}
+
+// Size of each file and folder the app keeps
+#[tauri::command]
+async fn get_data_usage() -> Result<DataUsage, String> {
+    run_blocking(data_retention::usage).await
+}
+
+// Apply the history retention settings now rather than at the daily pass
+#[tauri::command]
+async fn compact_app_data(state: State<'_, AppState>) -> Result<DataUsage, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        data_retention::compact(&config);
+        data_retention::usage()
+    })
+    .await
+}
+
+// Whether the system drive is nearly full, with what to try next if so
+#[tauri::command]
+fn get_emergency_status(state: State<AppState>) -> EmergencyStatus {
+    emergency::status(&state.config.lock().unwrap())
+}
+
+// The order automatic cleans and cleans with a time limit take targets in
+#[tauri::command]
+async fn get_clean_priorities(state: State<'_, AppState>) -> Result<Vec<Priority>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || clean_budget::priorities(&config)).await
+}
+
+// Time, files, and disk I/O of the last scan, preview, and clean
+#[tauri::command]
+fn get_operation_costs() -> BTreeMap<String, OperationCost> {
+    cost::last()
+}
+
+// What changed between the last two target scans
+#[tauri::command]
+fn get_scan_changes() -> Option<ScanChanges> {
+    scan_history::changes()
+}
+
+// Cache sizes and cleans over the last months, for the growth chart
+#[tauri::command]
+fn get_size_stats() -> SizeStats {
+    size_stats::load()
+}
+
+#[tauri::command]
+async fn preview_clean(state: State<'_, AppState>, ids: Vec<String>) -> Result<Vec<TargetPreview>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        cost::measure(
+            "preview",
+            || {
+                targets::builtin_targets(&config)
+                    .iter()
+                    .filter(|target| ids.contains(&target.id) && !target.report_only)
+                    .map(targets::preview)
+                    .collect::<Vec<_>>()
+            },
+            |previews| previews.iter().map(|preview| preview.files.len() as u64).sum(),
+        )
+    })
+    .await
+}
+
+// A clean started from the window, journaled, undoable and logged. With
+// `reviewed`, only the files kept in the preview list of each target are
+// deleted; otherwise each target is cleaned by its strategy.
+fn clean_from_window(
+    config: &Config,
+    selected: Vec<CacheTarget>,
+    reviewed: Option<BTreeMap<String, Vec<PathBuf>>>,
+    on_progress: Channel<CleanProgress>,
+) -> CleanStats {
+    let history = clean_history::Recorder::start("window").verified(config.verify_cleans);
+    let clean = |target: &CacheTarget| {
+        let stats = match &reviewed {
+            Some(reviewed) => {
+                let files = reviewed.get(&target.id).map_or(&[][..], Vec::as_slice);
+                targets::clean_reviewed(target, files)
+            }
+            None => targets::clean_target(target),
+        };
+        history.track(target, stats)
+    };
+    let selected_ids: Vec<_> = selected.iter().map(|target| target.id.clone()).collect();
+
+    journal::begin(&selected_ids);
+    let batch = undo::begin(config.deletion);
+    let total = cost::measure(
+        "clean",
+        || {
+            cleaner::track_progress(
+                |progress| {
+                    journal::record_progress(&progress);
+                    let _ = on_progress.send(progress);
+                },
+                || targets::clean_targets_with(&selected, clean, |target| journal::target_done(&target.id)),
+            )
+        },
+        |stats| stats.files_deleted + stats.files_failed,
+    );
+    history.finish();
+    let cancelled = batch.is_cancelled();
+    undo::finish(batch);
+    journal::finish();
+    notify_window_clean(config, total, cancelled);
+    total
+}
+
+#[tauri::command]
+async fn clean_cache_targets(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    reviewed: Option<BTreeMap<String, Vec<PathBuf>>>,
+    on_progress: Channel<CleanProgress>,
+) -> Result<CleanStats, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let selected: Vec<_> = targets::builtin_targets(&config)
+            .into_iter()
+            .filter(|target| ids.contains(&target.id) && !target.report_only)
+            .collect();
+        clean_from_window(&config, selected, reviewed, on_progress)
+    })
+    .await
+}
+
+// Clean the targets of a profile with its age policy, unless a clean would
+// free less than its threshold; None when it would. `skipped` are targets
+// that ask before cleaning which the user chose to leave out.
+#[tauri::command]
+async fn clean_profile(
+    state: State<'_, AppState>,
+    name: String,
+    skipped: Vec<String>,
+    on_progress: Channel<CleanProgress>,
+) -> Result<Option<CleanStats>, String> {
+    state.check_not_safe_mode()?;
+    let mut config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let profile = profiles::find(&config.profiles, &name)?.clone();
+        config.deletion = profile.deletion(config.deletion);
+        let mut selected = profiles::select(&profile, &targets::builtin_targets(&config));
+        selected.retain(|target| !skipped.contains(&target.id));
+        let threshold = config.size_units.giga_to_bytes(profile.threshold_gb);
+        if threshold > 0 && selected.iter().map(targets::reclaimable).sum::<u64>() < threshold {
+            return Ok(None);
+        }
+        Ok(Some(clean_from_window(&config, selected, None, on_progress)))
+    })
+    .await?
+}
+
+// Record how a clean from the window went in the notifications
+fn notify_window_clean(config: &Config, total: CleanStats, cancelled: bool) {
+    let freed = locale::format_size(total.freed_bytes, config);
+    let kept = left_behind(total, config);
+    let values = [("freed", freed.as_str()), ("kept", kept.as_str())];
+    if cancelled {
+        notifications::push(
+            Level::Info,
+            i18n::tr(&config.language, "Clean cancelled after freeing {freed}", &values[..1]),
+        );
+    } else if total.files_failed > 0 {
+        notifications::push(Level::Warning, i18n::tr(&config.language, "Cleaned {freed}{kept}", &values));
+    } else {
+        let text = if config.deletion == DeletionMode::RecycleBin {
+            "Moved {freed} of disk cache to the recycle bin{kept}"
+        } else if config.deletion == DeletionMode::Staging {
+            "Moved {freed} of disk cache to the quarantine folder{kept}"
+        } else {
+            "Cleaned {freed} of disk cache{kept}"
+        };
+        notifications::push(Level::Success, i18n::tr(&config.language, text, &values));
+    }
+}
+
+// The folders and files directly inside `path` of a target, or the target's
+// own paths without one, for the treemap
+#[tauri::command]
+async fn get_treemap_children(
+    state: State<'_, AppState>,
+    id: String,
+    path: Option<PathBuf>,
+) -> Result<Vec<analyzer::Node>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let target = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .ok_or_else(|| format!("Unknown target {}", id))?;
+        analyzer::children(&target, path.as_deref())
+    })
+    .await?
+}
+
+// Clean one folder or file of a target, picked in the treemap
+#[tauri::command]
+async fn clean_target_folder(
+    state: State<'_, AppState>,
+    id: String,
+    path: PathBuf,
+    on_progress: Channel<CleanProgress>,
+) -> Result<CleanStats, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let target = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .ok_or_else(|| format!("Unknown target {}", id))?;
+        let narrowed = analyzer::narrow(&target, &path)?;
+        Ok(clean_from_window(&config, vec![narrowed], None, on_progress))
+    })
+    .await?
+}
+
+// The largest files of every target and the largest of those unused for
+// `stale_days`, `limit` of each
+#[tauri::command]
+async fn find_files(state: State<'_, AppState>, limit: usize, stale_days: u64) -> Result<FoundFiles, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || file_finder::find(&targets::builtin_targets(&config), limit, stale_days)).await
+}
+
+// Delete one file picked in the file finder, within its target's exclusions
+// and minimum age
+#[tauri::command]
+async fn delete_found_file(
+    state: State<'_, AppState>,
+    id: String,
+    path: PathBuf,
+    on_progress: Channel<CleanProgress>,
+) -> Result<CleanStats, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let target = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .ok_or_else(|| format!("Unknown target {}", id))?;
+        if target.report_only {
+            return Err(format!("{} is only reported, never cleaned", target.name));
+        }
+        let reviewed = BTreeMap::from([(id, vec![path])]);
+        Ok(clean_from_window(&config, vec![target], Some(reviewed), on_progress))
+    })
+    .await?
+}
+
+// Show a file in the system file manager
+#[tauri::command]
+fn reveal_file(path: PathBuf) -> Result<(), String> {
+    file_finder::reveal(&path)
+}
+
+// The last clean that sent files to the recycle bin, if it can still be undone
+#[tauri::command]
+fn get_last_recycled() -> Option<UndoInfo> {
+    undo::last()
+}
+
+// The most recent cleans, newest first
+#[tauri::command]
+fn get_clean_history(count: usize) -> Vec<clean_history::CleanRun> {
+    clean_history::recent(count)
+}
+
+// Why files of the last clean could not be deleted
+#[tauri::command]
+fn get_clean_failures() -> clean_history::FailureReport {
+    clean_history::last_failures()
+}
+
+// Save the clean history where the user picks, as CSV or JSON by extension;
+// false when the dialog is cancelled
+#[tauri::command]
+async fn export_clean_history(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let Some(file) = app
+            .dialog()
+            .file()
+            .set_file_name("clean-history.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .blocking_save_file()
+        else {
+            return Ok(false);
+        };
+        let path = file.into_path().map_err(|e| format!("Invalid export path: {}", e))?;
+        clean_history::export(&path, &config)?;
+        Ok(true)
+    })
+    .await?
+}
+
+// Save every target with its paths, strategy and filters as this config
+// resolves them, as JSON where the user picks; false when the dialog is
+// cancelled
+#[tauri::command]
+async fn export_effective_rules(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let Some(file) = app
+            .dialog()
+            .file()
+            .set_file_name("effective-rules.json")
+            .add_filter("JSON", &["json"])
+            .blocking_save_file()
+        else {
+            return Ok(false);
+        };
+        let path = file.into_path().map_err(|e| format!("Invalid export path: {}", e))?;
+        rules_export::export(&config, &path)?;
+        Ok(true)
+    })
+    .await?
+}
+
+#[tauri::command]
+async fn undo_last_clean(state: State<'_, AppState>) -> Result<UndoStats, String> {
+    state.check_not_safe_mode()?;
+    run_blocking(undo::undo).await?
+}
+
+// A clean that was cut short by a crash or power loss
+#[tauri::command]
+fn get_interrupted_clean() -> Option<CleanJournal> {
+    journal::interrupted()
+}
+
+#[tauri::command]
+fn dismiss_interrupted_clean() {
+    journal::dismiss();
+}
+
+// Try the quarantined files again and report how it went; `by_user` when
+// asked for in the window rather than done at startup
+fn retry_quarantine(config: &Config, by_user: bool) -> CleanStats {
+    let batch = if by_user {
+        undo::begin(config.deletion)
+    } else {
+        undo::begin_automatic(config.deletion)
+    };
+    let stats = cleaner::track_progress(|_| {}, quarantine::retry);
+    undo::finish(batch);
+    let freed = locale::format_size(stats.freed_bytes, config);
+    if stats.files_failed > 0 {
+        notifications::push(
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "Retried quarantined files: deleted {deleted} ({freed}), {failed} ({size}) still failing",
+                &[
+                    ("deleted", &stats.files_deleted.to_string()),
+                    ("freed", &freed),
+                    ("failed", &stats.files_failed.to_string()),
+                    ("size", &locale::format_size(stats.failed_bytes, config)),
+                ],
+            ),
+        );
+    } else {
+        notifications::push(
+            Level::Success,
+            i18n::tr(
+                &config.language,
+                "Retried quarantined files: deleted {deleted} ({freed})",
+                &[("deleted", &stats.files_deleted.to_string()), ("freed", &freed)],
+            ),
+        );
+    }
+    stats
+}
+
+// Files that repeatedly failed to delete, keyed by target id
+#[tauri::command]
+fn get_quarantined_files() -> BTreeMap<String, Vec<FailedFile>> {
+    quarantine::list()
+}
+
+#[tauri::command]
+async fn retry_quarantined_files(state: State<'_, AppState>) -> Result<CleanStats, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || retry_quarantine(&config, true)).await
+}
+
+// Custom cache folders that are skipped, with the reason
+#[tauri::command]
+fn get_custom_dir_problems(state: State<AppState>) -> BTreeMap<String, String> {
+    targets::custom_dir_problems(&state.config.lock().unwrap())
+}
+
+// App cache folders found outside every target, to be offered as custom folders
+#[tauri::command]
+async fn get_target_suggestions(state: State<'_, AppState>) -> Result<Vec<Suggestion>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || suggestions::find(&config, &targets::builtin_targets(&config))).await
+}
+
+// Turn a custom folder the startup check turned off back on, once it passes
+#[tauri::command]
+fn enable_custom_dir(state: State<AppState>, dir: String) -> Result<(), String> {
+    state.check_not_safe_mode()?;
+    targets::check_custom_dir(&dir)?;
+    let mut config = state.config.lock().unwrap();
+    config.disabled_custom_dirs.remove(&dir);
+    config_file::save(&config)
+}
+
+// Turn off custom folders that are gone, were replaced by a link or junction,
+// or fail checks added since they were, so a clean never empties whatever
+// their path leads to now
+fn check_custom_dirs(config: &mut Config) {
+    let failed: Vec<_> = config
+        .custom_cache_dirs
+        .iter()
+        .filter(|dir| !config.disabled_custom_dirs.contains_key(*dir))
+        .filter_map(|dir| targets::check_custom_dir(dir).err().map(|e| (dir.clone(), e)))
+        .collect();
+    if failed.is_empty() {
+        return;
+    }
+    for (dir, problem) in failed {
+        notifications::push(
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "{problem}; it is no longer cleaned until turned back on",
+                &[("problem", &problem)],
+            ),
+        );
+        config.disabled_custom_dirs.insert(dir, problem);
+    }
+    if let Err(e) = config_file::save(config) {
+        notifications::push(Level::Warning, e);
+    }
+}
+
+// Files queued for deletion at the next restart
+#[tauri::command]
+fn get_reboot_queue() -> Vec<PendingFile> {
+    reboot_queue::pending()
+}
+
+// Hand every quarantined file to Windows to delete at the next restart
+#[tauri::command]
+fn queue_quarantined_for_reboot(state: State<AppState>) -> Result<usize, String> {
+    state.check_not_safe_mode()?;
+    reboot_queue::queue_quarantined()
+}
+
+// Cleaned files kept in the quarantine folder, newest first
+#[tauri::command]
+fn get_staged_files() -> Vec<StagedFile> {
+    staging::list()
+}
+
+// Put a file from the quarantine folder back where it was cleaned from
+#[tauri::command]
+fn restore_staged_file(state: State<AppState>, id: String) -> Result<PathBuf, String> {
+    state.check_not_safe_mode()?;
+    staging::restore(&id)
+}
+
+// Delete everything in the quarantine folder now, returning the bytes freed
+#[tauri::command]
+async fn empty_staging(state: State<'_, AppState>) -> Result<u64, String> {
+    state.check_not_safe_mode()?;
+    run_blocking(|| staging::purge_expired(0)).await
+}
+
+// Report what the last restart deleted of the files queued before it
+fn reconcile_reboot_queue(config: &Config) {
+    let Some(outcome) = reboot_queue::reconcile() else {
+        return;
+    };
+    let freed = locale::format_size(outcome.freed_bytes, config);
+    if outcome.files_left > 0 {
+        notifications::push(
+            Level::Warning,
+            i18n::tr(
+                &config.language,
+                "Deleted at restart: {count} files ({freed}), {left} could not be deleted",
+                &[
+                    ("count", &outcome.files_deleted.to_string()),
+                    ("freed", &freed),
+                    ("left", &outcome.files_left.to_string()),
+                ],
+            ),
+        );
+    } else {
+        notifications::push(
+            Level::Success,
+            i18n::tr(
+                &config.language,
+                "Deleted at restart: {count} files ({freed})",
+                &[("count", &outcome.files_deleted.to_string()), ("freed", &freed)],
+            ),
+        );
+    }
+}
+
+// Quit the program that keeps a target's files open, so the target can be
+// cleaned
+#[tauri::command]
+async fn close_target_owner(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let owner = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .and_then(|target| target.owner)
+            .ok_or_else(|| format!("No program to close for {}", id))?;
+        detect::close_process(owner.process)
+    })
+    .await?
+}
+
+// Ask a program found keeping files of a previewed clean open to quit, so
+// the preview can be run again before cleaning
+#[tauri::command]
+async fn close_locking_app(state: State<'_, AppState>, pid: u32) -> Result<(), String> {
+    state.check_not_safe_mode()?;
+    run_blocking(move || file_locks::close_app(pid)).await?
+}
+
+// Subfolders, largest files and file ages of one target, walked only when
+// its report is opened
+#[tauri::command]
+async fn get_size_report(state: State<'_, AppState>, id: String) -> Result<SizeReport, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let target = targets::builtin_targets(&config)
+            .into_iter()
+            .find(|target| target.id == id)
+            .ok_or_else(|| format!("Unknown target {}", id))?;
+        Ok(size_report::build(&target))
+    })
+    .await?
+}
+
+// Stops the running clean after the file in progress
+#[tauri::command]
+fn cancel_clean() {
+    cleaner::cancel_clean();
+}
+
+#[tauri::command]
+fn is_safe_mode(state: State<AppState>) -> bool {
+    state.safe_mode
+}
+
+#[tauri::command]
+fn is_elevated() -> bool {
+    elevation::is_elevated()
+}
+
+// Hand the selected targets to an elevated instance, which cleans them once
+// its window is up, and quit this one
+#[tauri::command]
+fn restart_as_admin(app: AppHandle, state: State<AppState>, ids: Vec<String>) -> Result<(), String> {
+    let mut args: Vec<String> = ids.iter().map(|id| format!("--resume-clean={}", id)).collect();
+    if state.safe_mode {
+        args.push("--safe-mode".to_string());
+    }
+    elevation::relaunch_elevated(&args)?;
+    app.exit(0);
+    Ok(())
+}
+
+// The targets to clean right after a restart as administrator, handed out once
+#[tauri::command]
+fn take_resume_clean(state: State<AppState>) -> Vec<String> {
+    std::mem::take(&mut *state.resume_clean.lock().unwrap())
+}
+
+// Whether View report was clicked since the last call
+#[tauri::command]
+fn take_show_clean_report(state: State<AppState>) -> bool {
+    state.show_clean_report.swap(false, Ordering::Relaxed)
+}
+
+#[tauri::command]
+fn start_impact_analysis(state: State<AppState>, ids: Vec<String>) -> Result<CleanStats, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    let selected: Vec<_> = targets::builtin_targets(&config)
+        .into_iter()
+        .filter(|target| ids.contains(&target.id))
+        .collect();
+    let batch = undo::begin(config.deletion);
+    let stats = impact::start(&selected, config.impact_period_days);
+    undo::finish(batch);
+    stats
+}
+
+#[tauri::command]
+async fn get_impact_report(state: State<'_, AppState>) -> Result<Option<ImpactReport>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || impact::report(&targets::builtin_targets(&config))).await
+}
+
+// The cleaners of this platform, in the order they scan
+#[tauri::command]
+async fn get_cleaners(state: State<'_, AppState>) -> Result<Vec<CleanerInfo>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let targets = targets::builtin_targets(&config);
+        cleaners::available()
+            .map(|cleaner| CleanerInfo {
+                name: cleaner.name(),
+                platform: cleaner.platform(),
+                requires_admin: cleaner.requires_admin(),
+                target_count: targets.iter().filter(|t| t.cleaner == cleaner.name()).count(),
+            })
+            .collect()
+    })
+    .await
+}
+
+#[tauri::command]
+async fn get_maintenance_actions(
+    state: State<'_, AppState>,
+) -> Result<Vec<MaintenanceAction>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || maintenance::available_actions(&config)).await
+}
+
+#[tauri::command]
+fn run_maintenance_action(state: State<AppState>, id: String) -> Result<String, String> {
+    state.check_not_safe_mode()?;
+    let config = state.config.lock().unwrap().clone();
+    let result = maintenance::run_action(&id, &config);
+    match &result {
+        Ok(message) => notifications::push(Level::Success, message.clone()),
+        Err(error) => notifications::push(Level::Warning, error.clone()),
+    }
+    result
+}
+
+#[tauri::command]
+async fn get_device_backups() -> Result<Vec<DeviceBackup>, String> {
+    run_blocking(backups::list_backups).await
+}
+
+#[tauri::command]
+fn delete_device_backup(state: State<AppState>, id: String) -> Result<u64, String> {
+    state.check_not_safe_mode()?;
+    backups::delete_backup(&id)
+}
+
+// Lets the UI record events only it sees, such as auto-clean runs
+#[tauri::command]
+fn add_notification(level: Level, message: String) {
+    notifications::push(level, message);
+}
+
+#[tauri::command]
+fn get_notifications() -> Vec<Notification> {
+    notifications::list()
+}
+
+#[tauri::command]
+fn mark_notifications_read() {
+    notifications::mark_all_read();
+}
+
+#[tauri::command]
+fn clear_notifications() {
+    notifications::clear();
+}
+
+// Where temp files and browser caches are written, and where they could go
+#[tauri::command]
+async fn get_temp_advice() -> Result<TempAdvice, String> {
+    run_blocking(temp_advisor::advise).await
+}
+
+#[tauri::command]
+fn move_temp_folder(state: State<AppState>, dir: PathBuf) -> Result<String, String> {
+    state.check_not_safe_mode()?;
+    temp_advisor::move_temp(&dir)
+}
+
+#[tauri::command]
+fn move_browser_cache(state: State<AppState>, id: String, dir: PathBuf) -> Result<String, String> {
+    state.check_not_safe_mode()?;
+    temp_advisor::move_browser_cache(&id, &dir)
+}
+
+// Native folder picker for adding custom cache folders; None when cancelled
+#[tauri::command]
+async fn pick_folder(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    run_blocking(move || {
+        app.dialog()
+            .file()
+            .blocking_pick_folder()
+            .map(|folder| folder.to_string())
+    })
+    .await
+}
+
+// Native file picker for a rule file to simulate or an exclusion pack to
+// import; None when cancelled
+#[tauri::command]
+async fn pick_rule_file(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    run_blocking(move || {
+        app.dialog()
+            .file()
+            .add_filter("JSON", &["json"])
+            .blocking_pick_file()
+            .map(|file| file.to_string())
+    })
+    .await
+}
+
+// What each rule of the file would delete and where it conflicts, read
+// again on every call so edits show up; nothing is deleted
+#[tauri::command]
+async fn simulate_rule_file(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<Vec<rule_simulation::RuleResult>, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let rules = rule_simulation::load(Path::new(&path))?;
+        Ok(rule_simulation::simulate(&config, &rules))
+    })
+    .await?
+}
+
+// The trusted key a rule file is signed with, or why it is not
+#[tauri::command]
+fn check_rule_file_signature(state: State<AppState>, path: String) -> Result<String, String> {
+    rule_signatures::file_signer(&state.config.lock().unwrap(), Path::new(&path))
+}
+
+// Add the file's rules as custom folders, returning the saved settings. A
+// file not signed with a trusted key is only added when the user said to.
+#[tauri::command]
+fn activate_rule_file(state: State<AppState>, path: String, allow_unsigned: bool) -> Result<Config, String> {
+    state.check_not_safe_mode()?;
+    let rules = rule_simulation::load(Path::new(&path))?;
+    if !allow_unsigned {
+        rule_signatures::file_signer(&state.config.lock().unwrap(), Path::new(&path))?;
+    }
+    let mut config = state.config.lock().unwrap();
+    let mut updated = config.clone();
+    rule_simulation::activate(&mut updated, &rules)?;
+    config_file::save(&updated)?;
+    *config = updated.clone();
+    Ok(updated)
+}
+
+// The remote rule file's URL, when it was last fetched and how many rules
+// it has
+#[tauri::command]
+fn get_remote_rules_status(state: State<AppState>) -> remote_rules::RemoteRulesStatus {
+    remote_rules::status(&state.config.lock().unwrap())
+}
+
+// Fetch the remote rule file of the saved settings now
+#[tauri::command]
+async fn fetch_remote_rules(state: State<'_, AppState>) -> Result<remote_rules::RemoteRulesStatus, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || remote_rules::fetch(&config)).await
+}
+
+// Save the saved exclusion rules as a named pack where the user picks;
+// false when the dialog is cancelled
 #[tauri::command]
-fn save_config(state: State<AppState>, config: Config) -> Result<(), String> {
+async fn export_exclusion_pack(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<bool, String> {
+    let config = state.config.lock().unwrap().clone();
+    run_blocking(move || {
+        let Some(file) = app
+            .dialog()
+            .file()
+            .set_file_name("exclusion-pack.json")
+            .add_filter("JSON", &["json"])
+            .blocking_save_file()
+        else {
+            return Ok(false);
+        };
+        let path = file.into_path().map_err(|e| format!("Invalid export path: {}", e))?;
+        exclusion_packs::export(&config, &name, &path)?;
+        Ok(true)
+    })
+    .await?
+}
+
+// Each target of the pack with its patterns and this machine's
+#[tauri::command]
+fn preview_exclusion_pack(state: State<AppState>, path: String) -> Result<exclusion_packs::PackPreview, String> {
+    let pack = exclusion_packs::load(Path::new(&path))?;
+    Ok(exclusion_packs::preview(&state.config.lock().unwrap(), &pack))
+}
+
+// Merge the pack into the exclusions, returning the saved settings
+#[tauri::command]
+fn import_exclusion_pack(
+    state: State<AppState>,
+    path: String,
+    choices: BTreeMap<String, exclusion_packs::Resolution>,
+) -> Result<Config, String> {
+    let pack = exclusion_packs::load(Path::new(&path))?;
+    let mut config = state.config.lock().unwrap();
+    let mut updated = config.clone();
+    exclusion_packs::merge(&mut updated, &pack, &choices);
+    config_file::save(&updated)?;
+    *config = updated.clone();
+    Ok(updated)
+}
+
+#[tauri::command]
+fn save_config(app: AppHandle, state: State<AppState>, mut config: Config) -> Result<(), String> {
+    for patterns in config.exclusions.values() {
+        Exclusions::validate(patterns)?;
+    }
+    targets::validate_custom_dirs(&config.custom_cache_dirs)?;
+    remote_rules::validate_url(&config.remote_rules_url)?;
+    rule_signatures::validate_keys(&config.trusted_rule_keys)?;
+    let custom_cache_dirs = &config.custom_cache_dirs;
+    config.disabled_custom_dirs.retain(|dir, _| custom_cache_dirs.contains(dir));
+    config
+        .custom_dir_labels
+        .retain(|dir, label| custom_cache_dirs.contains(dir) && !label.is_empty());
+    network_shares::validate(&config.network_share_dirs)?;
+    config.memory_schedule.validate()?;
+    profiles::validate(&config.profiles)?;
+    i18n::validate(&config.language)?;
+    if config.staging_retention_days == 0 {
+        return Err("Files must stay in the quarantine folder for at least a day".to_string());
+    }
+    if config.start_at_login != state.config.lock().unwrap().start_at_login {
+        autostart::sync(config.start_at_login)?;
+    }
+    if config.memory_schedule != state.config.lock().unwrap().memory_schedule {
+        memory_schedule::sync_task(&config.memory_schedule)?;
+    }
+    // Another app may own the hotkey; keep the old one working then
+    let old_hotkey = state.config.lock().unwrap().quick_clean_hotkey.clone();
+    if config.quick_clean_hotkey != old_hotkey {
+        if let Err(e) = set_quick_clean_hotkey(&app, &config.quick_clean_hotkey) {
+            let _ = set_quick_clean_hotkey(&app, &old_hotkey);
+            return Err(e);
+        }
+    }
+    config_file::save(&config)?;
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        let _ = tray.pause.set_checked(!config.auto_clean_enabled);
+        tray.relabel(&config.language);
+        tray.show_schedule(&config);
+    }
     let mut app_config = state.config.lock().unwrap();
     *app_config = config;
     Ok(())
 }
 
+// Every drive with its free space, the system drive first
+#[tauri::command]
+async fn get_drive_space() -> Result<Vec<DriveSpace>, String> {
+    run_blocking(disk_space::drives).await
+}
+
+// Seconds since the Unix epoch; None when memory is not cleaned on a schedule
+#[tauri::command]
+fn get_next_memory_clean(state: State<AppState>) -> Option<i64> {
+    memory_schedule::next_run(&state.config.lock().unwrap().memory_schedule)
+}
+
 #[tauri::command]
 fn load_config(state: State<AppState>) -> Result<Config, String> {
     let config = state.config.lock().unwrap();
     Ok(config.clone())
 }
 
+// e.g. "VMware" or "Windows Sandbox", or None on real hardware
+#[tauri::command]
+async fn get_virtual_machine() -> Result<Option<&'static str>, String> {
+    run_blocking(detect::virtual_machine).await
+}
+
+#[tauri::command]
+fn get_languages() -> Vec<Language> {
+    i18n::languages()
+}
+
+// Emitted back so the window can tell that backend events reach it
+#[tauri::command]
+fn ping_events(app: AppHandle) -> Result<(), String> {
+    app.emit("events-ping", ()).map_err(|e| e.to_string())
+}
+
+// The window's text in `language`, keyed by its English text
+#[tauri::command]
+fn get_translations(language: String) -> BTreeMap<String, String> {
+    i18n::strings(&language)
+}
+
+#[tauri::command]
+fn get_config_scopes() -> BTreeMap<String, Scope> {
+    config_file::scopes()
+}
+
+#[tauri::command]
+fn get_config_backup_count() -> usize {
+    config_file::backup_count()
+}
+
+// Roll the settings back one saved version. In safe mode only the file is
+// restored; the app keeps running on defaults until restarted.
+#[tauri::command]
+fn restore_previous_config(app: AppHandle, state: State<AppState>) -> Result<Config, String> {
+    let config = config_file::restore_previous()?;
+    if !state.safe_mode {
+        let _ = set_quick_clean_hotkey(&app, &config.quick_clean_hotkey);
+        let _ = memory_schedule::sync_task(&config.memory_schedule);
+        *state.config.lock().unwrap() = config.clone();
+    }
+    Ok(config)
+}
+
+// Cleans memory as soon as the app runs, or in the running instance
+const CLEAN_NOW: &str = "--clean-now";
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
+    config_file::protect_app_files();
+    targets::skip_freed_space();
+
+    // Recovery start for when a bad config or rule breaks the app: built-in
+    // defaults only, and every command that deletes or changes files refuses
+    let safe_mode = args.iter().any(|arg| arg == "--safe-mode");
+    let at_login = args.iter().any(|arg| arg == autostart::AT_LOGIN);
+    let clean_at_start = args.iter().any(|arg| arg == CLEAN_NOW);
+    let resume_clean = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("--resume-clean="))
+        .map(str::to_string)
+        .collect();
+    let config = if safe_mode {
+        Config::default()
+    } else {
+        config_file::load()
+    };
+
     tauri::Builder::default()
-        .manage(AppState::default())
+        // First, so a second launch exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            handle_second_launch(app, argv.get(1..).unwrap_or_default());
+        }))
+        .manage(AppState {
+            config: Mutex::new(config),
+            safe_mode,
+            resume_clean: Mutex::new(resume_clean),
+            show_clean_report: AtomicBool::new(false),
+        })
+        .setup(move |app| {
+            // WebView2/WebKit profile data lives in the app's own directories
+            for dir in [app.path().app_local_data_dir(), app.path().app_data_dir()]
+                .into_iter()
+                .flatten()
+            {
+                cleaner::protect(&dir);
+            }
+            reconcile_reboot_queue(&app.state::<AppState>().config.lock().unwrap());
+            clean_events::init();
+            if !app.state::<AppState>().safe_mode {
+                check_custom_dirs(&mut app.state::<AppState>().config.lock().unwrap());
+                let handle = app.handle().clone();
+                std::thread::spawn(move || run_schedules(handle));
+                // Files that failed for lack of rights may go once elevated
+                if elevation::is_elevated() && !quarantine::list().is_empty() {
+                    let config = app.state::<AppState>().config.lock().unwrap().clone();
+                    std::thread::spawn(move || retry_quarantine(&config, false));
+                }
+            }
+            build_tray(app)?;
+            if at_login && app.state::<AppState>().config.lock().unwrap().start_minimized {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_scans(handle));
+            let hotkey = app.state::<AppState>().config.lock().unwrap().quick_clean_hotkey.clone();
+            if let Err(e) = set_quick_clean_hotkey(app.handle(), &hotkey) {
+                notifications::push(Level::Warning, e);
+            }
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_auto_clean(handle));
+            if clean_at_start {
+                clean_now(app.handle());
+            }
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let state = window.state::<AppState>();
+                if state.config.lock().unwrap().close_to_tray {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .plugin(tauri_plugin_shell::init())
+        // Restores size/position per monitor and falls back to the primary
+        // display when the saved monitor is no longer connected. Whether the
+        // window shows at start is left to the start minimized setting.
+        .plugin(
+            tauri_plugin_window_state::Builder::default()
+                .with_state_flags(StateFlags::all() & !StateFlags::VISIBLE)
+                .build(),
+        )
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        let app = app.clone();
+                        std::thread::spawn(move || quick_clean(&app));
+                    }
+                })
+                .build(),
+        )
         .invoke_handler(tauri::generate_handler![
             get_memory_info,
             clean_memory_cache,
+            get_cache_targets,
+            get_reclaimable,
+            get_scan_changes,
+            get_size_stats,
+            get_operation_costs,
+            get_clean_priorities,
+            get_emergency_status,
+            get_data_usage,
+            compact_app_data,
+            install_powershell_module,
+            check_quarantine_restore,
+            get_whats_new,
+            dismiss_whats_new,
+            preview_clean,
+            clean_cache_targets,
+            clean_profile,
+            cancel_clean,
+            get_last_recycled,
+            undo_last_clean,
+            get_clean_history,
+            get_clean_failures,
+            export_clean_history,
+            export_effective_rules,
+            get_interrupted_clean,
+            dismiss_interrupted_clean,
+            get_quarantined_files,
+            close_target_owner,
+            close_locking_app,
+            get_size_report,
+            get_treemap_children,
+            clean_target_folder,
+            find_files,
+            delete_found_file,
+            reveal_file,
+            retry_quarantined_files,
+            get_reboot_queue,
+            get_custom_dir_problems,
+            enable_custom_dir,
+            get_target_suggestions,
+            queue_quarantined_for_reboot,
+            get_staged_files,
+            restore_staged_file,
+            empty_staging,
+            is_elevated,
+            restart_as_admin,
+            take_resume_clean,
+            take_show_clean_report,
+            is_safe_mode,
+            start_impact_analysis,
+            get_impact_report,
+            get_cleaners,
+            get_maintenance_actions,
+            run_maintenance_action,
+            get_device_backups,
+            delete_device_backup,
+            add_notification,
+            get_notifications,
+            mark_notifications_read,
+            clear_notifications,
+            pick_folder,
+            pick_rule_file,
+            simulate_rule_file,
+            check_rule_file_signature,
+            activate_rule_file,
+            get_remote_rules_status,
+            fetch_remote_rules,
+            export_exclusion_pack,
+            preview_exclusion_pack,
+            import_exclusion_pack,
+            get_temp_advice,
+            move_temp_folder,
+            move_browser_cache,
             save_config,
-            load_config
+            load_config,
+            get_next_memory_clean,
+            get_drive_space,
+            get_config_scopes,
+            get_languages,
+            get_virtual_machine,
+            get_translations,
+            ping_events,
+            get_config_backup_count,
+            restore_previous_config
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
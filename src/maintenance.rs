@@ -0,0 +1,398 @@
+use crate::Config;
+use serde::Serialize;
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use crate::cleaner;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use crate::locale;
+#[cfg(target_os = "windows")]
+use crate::wsl;
+#[cfg(target_os = "windows")]
+use std::fs;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+#[cfg(target_os = "windows")]
+use std::path::PathBuf;
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+#[derive(Serialize, Clone)]
+pub struct MaintenanceAction {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    // Space the action is expected to reclaim, when it can be measured up front
+    pub estimated_bytes: Option<u64>,
+}
+
+#[cfg(target_os = "windows")]
+pub fn available_actions(_config: &Config) -> Vec<MaintenanceAction> {
+    let mut actions = vec![
+        MaintenanceAction {
+            id: "reset-store-cache",
+            name: "Reset Microsoft Store cache",
+            description: "Runs wsreset.exe and clears the Store app's LocalCache",
+            estimated_bytes: None,
+        },
+        MaintenanceAction {
+            id: "flush-dns",
+            name: "Flush DNS resolver cache",
+            description: "Forgets cached name lookups, e.g. after a site moved or DNS settings changed",
+            estimated_bytes: None,
+        },
+        MaintenanceAction {
+            id: "clear-thumbnail-cache",
+            name: "Clear Explorer thumbnail cache",
+            description: "Deletes thumbcache_*.db; Explorer is restarted and rebuilds thumbnails as folders are opened",
+            estimated_bytes: Some(explorer_cache_size(THUMBNAIL_CACHE_PREFIX)),
+        },
+        MaintenanceAction {
+            id: "rebuild-icon-cache",
+            name: "Rebuild icon cache",
+            description: "Fixes blank or outdated icons; Explorer is restarted and reloads icons from the programs",
+            estimated_bytes: Some(explorer_cache_size(ICON_CACHE_PREFIX)),
+        },
+    ];
+    if !wsl::distros().is_empty() {
+        actions.push(MaintenanceAction {
+            id: "compact-wsl-disks",
+            name: "Compact WSL disks",
+            description: "Shuts WSL down and shrinks each distribution's ext4.vhdx to the space its files use (needs administrator rights)",
+            estimated_bytes: None,
+        });
+    }
+    actions
+}
+
+#[cfg(target_os = "linux")]
+pub fn available_actions(config: &Config) -> Vec<MaintenanceAction> {
+    let mut actions = Vec::new();
+    if let Some(manager) = PackageManager::detect() {
+        actions.push(MaintenanceAction {
+            id: "clean-package-cache",
+            name: manager.action_name(),
+            description: "Removes downloaded package files (asks for administrator password)",
+            estimated_bytes: Some(cleaner::scan_dir(Path::new(manager.cache_dir())).size_bytes),
+        });
+    }
+
+    if Path::new("/run/systemd/system").is_dir() {
+        let cap_bytes = config.journal_max_mb * 1024 * 1024;
+        actions.push(MaintenanceAction {
+            id: "vacuum-journal",
+            name: "Vacuum systemd journal",
+            description: "Shrinks journald logs to the configured size cap (asks for administrator password)",
+            estimated_bytes: Some(journal_usage().saturating_sub(cap_bytes)),
+        });
+    }
+    actions
+}
+
+#[cfg(target_os = "macos")]
+pub fn available_actions(_config: &Config) -> Vec<MaintenanceAction> {
+    let mut actions = Vec::new();
+    if brew_installed() {
+        actions.push(MaintenanceAction {
+            id: "brew-cleanup",
+            name: "Run Homebrew cleanup",
+            description: "Removes outdated formula versions and stale downloads (brew cleanup)",
+            estimated_bytes: None,
+        });
+    }
+    actions
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn available_actions(_config: &Config) -> Vec<MaintenanceAction> {
+    Vec::new()
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(unused_variables))]
+pub fn run_action(id: &str, config: &Config) -> Result<String, String> {
+    match id {
+        #[cfg(target_os = "windows")]
+        "reset-store-cache" => reset_store_cache(),
+        #[cfg(target_os = "windows")]
+        "flush-dns" => flush_dns(),
+        #[cfg(target_os = "windows")]
+        "clear-thumbnail-cache" => clear_explorer_cache(
+            explorer_cache_files(THUMBNAIL_CACHE_PREFIX),
+            "Thumbnail cache cleared",
+            config,
+        ),
+        #[cfg(target_os = "windows")]
+        "rebuild-icon-cache" => rebuild_icon_cache(config),
+        #[cfg(target_os = "windows")]
+        "compact-wsl-disks" => wsl::compact(config),
+        #[cfg(target_os = "linux")]
+        "clean-package-cache" => clean_package_cache(config),
+        #[cfg(target_os = "linux")]
+        "vacuum-journal" => vacuum_journal(config),
+        #[cfg(target_os = "macos")]
+        "brew-cleanup" => brew_cleanup(),
+        _ => Err(format!("Unknown maintenance action: {}", id)),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn reset_store_cache() -> Result<String, String> {
+    let status = Command::new("wsreset.exe")
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| format!("Failed to run wsreset.exe: {}", e))?;
+    if !status.success() {
+        return Err(format!("wsreset.exe exited with {}", status));
+    }
+
+    let local_cache = std::env::var_os("LOCALAPPDATA").map(|p| {
+        PathBuf::from(p)
+            .join("Packages")
+            .join("Microsoft.WindowsStore_8wekyb3d8bbwe")
+            .join("LocalCache")
+    });
+    let stats = match local_cache {
+        Some(dir) if dir.is_dir() => cleaner::clean_dir(&dir, None, &cleaner::Exclusions::default()),
+        _ => Default::default(),
+    };
+
+    Ok(format!(
+        "Microsoft Store cache reset ({} files removed from LocalCache)",
+        stats.files_deleted
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn flush_dns() -> Result<String, String> {
+    let output = Command::new("ipconfig")
+        .arg("/flushdns")
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run ipconfig: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ipconfig /flushdns exited with {}", output.status));
+    }
+    Ok("DNS resolver cache flushed".to_string())
+}
+
+#[cfg(target_os = "windows")]
+const THUMBNAIL_CACHE_PREFIX: &str = "thumbcache_";
+#[cfg(target_os = "windows")]
+const ICON_CACHE_PREFIX: &str = "iconcache_";
+
+// Explorer keeps its thumbnail and icon caches here as <prefix>*.db files
+#[cfg(target_os = "windows")]
+fn explorer_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("LOCALAPPDATA").map(|p| PathBuf::from(p).join("Microsoft").join("Windows").join("Explorer"))
+}
+
+#[cfg(target_os = "windows")]
+fn explorer_cache_files(prefix: &str) -> Vec<(PathBuf, u64)> {
+    let Some(entries) = explorer_cache_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            name.starts_with(prefix) && name.ends_with(".db")
+        })
+        .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.len())))
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn explorer_cache_size(prefix: &str) -> u64 {
+    explorer_cache_files(prefix).iter().map(|(_, size)| size).sum()
+}
+
+// Explorer holds its cache databases open, so it is stopped while they are
+// deleted and started again afterwards, whatever `work` returns
+#[cfg(target_os = "windows")]
+fn with_explorer_stopped<T>(work: impl FnOnce() -> T) -> Result<T, String> {
+    Command::new("taskkill")
+        .args(["/f", "/im", "explorer.exe"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to stop Explorer: {}", e))?;
+    // Give the process time to release its file handles
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    let result = work();
+    Command::new("explorer.exe")
+        .spawn()
+        .map_err(|e| format!("Failed to restart Explorer: {}", e))?;
+    Ok(result)
+}
+
+#[cfg(target_os = "windows")]
+fn clear_explorer_cache(files: Vec<(PathBuf, u64)>, done: &str, config: &Config) -> Result<String, String> {
+    let (deleted, freed, failed) = with_explorer_stopped(|| {
+        let (mut deleted, mut freed, mut failed) = (0, 0, 0);
+        for (path, size) in files {
+            if fs::remove_file(&path).is_ok() {
+                deleted += 1;
+                freed += size;
+            } else {
+                failed += 1;
+            }
+        }
+        (deleted, freed, failed)
+    })?;
+    let mut message = format!("{} ({} files, {} freed)", done, deleted, locale::format_size(freed, config));
+    if failed > 0 {
+        message.push_str(&format!("; {} files were in use and are cleared after a restart", failed));
+    }
+    Ok(message)
+}
+
+// Besides the iconcache_*.db files, older Windows versions keep a single
+// IconCache.db in %LOCALAPPDATA%, and ie4uinit makes the shell reload icons
+#[cfg(target_os = "windows")]
+fn rebuild_icon_cache(config: &Config) -> Result<String, String> {
+    let mut files = explorer_cache_files(ICON_CACHE_PREFIX);
+    let legacy = std::env::var_os("LOCALAPPDATA").map(|p| PathBuf::from(p).join("IconCache.db"));
+    if let Some((path, meta)) = legacy.and_then(|path| Some((path.clone(), fs::metadata(path).ok()?))) {
+        files.push((path, meta.len()));
+    }
+    let message = clear_explorer_cache(files, "Icon cache rebuilt", config)?;
+    let _ = Command::new("ie4uinit.exe")
+        .arg("-show")
+        .creation_flags(CREATE_NO_WINDOW)
+        .status();
+    Ok(message)
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+}
+
+#[cfg(target_os = "linux")]
+impl PackageManager {
+    // Match the distro (or the distro it derives from) in /etc/os-release
+    fn detect() -> Option<Self> {
+        let os_release = std::fs::read_to_string("/etc/os-release").ok()?;
+        let ids: Vec<String> = os_release
+            .lines()
+            .filter_map(|line| line.strip_prefix("ID=").or_else(|| line.strip_prefix("ID_LIKE=")))
+            .flat_map(|value| value.trim_matches('"').split_whitespace())
+            .map(str::to_string)
+            .collect();
+
+        ids.iter().find_map(|id| match id.as_str() {
+            "debian" | "ubuntu" => Some(Self::Apt),
+            "fedora" | "rhel" | "centos" => Some(Self::Dnf),
+            "arch" | "manjaro" => Some(Self::Pacman),
+            _ => None,
+        })
+    }
+
+    fn action_name(self) -> &'static str {
+        match self {
+            Self::Apt => "Clean apt package cache",
+            Self::Dnf => "Clean dnf package cache",
+            Self::Pacman => "Clean pacman package cache",
+        }
+    }
+
+    fn cache_dir(self) -> &'static str {
+        match self {
+            Self::Apt => "/var/cache/apt/archives",
+            Self::Dnf => "/var/cache/dnf",
+            Self::Pacman => "/var/cache/pacman/pkg",
+        }
+    }
+
+    fn clean_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Apt => &["apt-get", "clean"],
+            Self::Dnf => &["dnf", "clean", "packages"],
+            Self::Pacman => &["pacman", "-Sc", "--noconfirm"],
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn clean_package_cache(config: &Config) -> Result<String, String> {
+    let manager = PackageManager::detect().ok_or("No supported package manager found")?;
+    let cache_dir = Path::new(manager.cache_dir());
+    let before = cleaner::scan_dir(cache_dir).size_bytes;
+
+    // pkexec shows the desktop's polkit password prompt
+    let status = Command::new("pkexec")
+        .args(manager.clean_args())
+        .status()
+        .map_err(|e| format!("Failed to run pkexec: {}", e))?;
+    if !status.success() {
+        return Err(format!("Package cache cleaning exited with {}", status));
+    }
+
+    let after = cleaner::scan_dir(cache_dir).size_bytes;
+    Ok(format!(
+        "Package cache cleaned ({} freed)",
+        locale::format_size(before.saturating_sub(after), config)
+    ))
+}
+
+// Persistent and volatile journals together, as `journalctl --disk-usage` reports
+#[cfg(target_os = "linux")]
+fn journal_usage() -> u64 {
+    cleaner::scan_dir(Path::new("/var/log/journal")).size_bytes
+        + cleaner::scan_dir(Path::new("/run/log/journal")).size_bytes
+}
+
+#[cfg(target_os = "linux")]
+fn vacuum_journal(config: &Config) -> Result<String, String> {
+    let before = journal_usage();
+
+    let status = Command::new("pkexec")
+        .arg("journalctl")
+        .arg(format!("--vacuum-size={}M", config.journal_max_mb))
+        .status()
+        .map_err(|e| format!("Failed to run pkexec: {}", e))?;
+    if !status.success() {
+        return Err(format!("journalctl exited with {}", status));
+    }
+
+    let after = journal_usage();
+    Ok(format!(
+        "Journal vacuumed to {} ({} freed)",
+        locale::format_size(after, config),
+        locale::format_size(before.saturating_sub(after), config)
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn brew_installed() -> bool {
+    Command::new("brew")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[cfg(target_os = "macos")]
+fn brew_cleanup() -> Result<String, String> {
+    let output = Command::new("brew")
+        .arg("cleanup")
+        .output()
+        .map_err(|e| format!("Failed to run brew: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("brew cleanup exited with {}", output.status));
+    }
+
+    // brew ends with e.g. "This operation has freed approximately 1.2GB of disk space."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let summary = stdout
+        .lines()
+        .rev()
+        .find(|line| line.contains("freed approximately"))
+        .unwrap_or("Homebrew cleanup finished");
+    Ok(summary.trim().to_string())
+}
@@ -0,0 +1,188 @@
+use crate::config_file;
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// Memory cleans at set times, next to the threshold check of auto-clean
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Repeat {
+    #[default]
+    Off,
+    EveryHours,
+    Daily,
+    Weekly,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct MemorySchedule {
+    #[serde(default)]
+    pub repeat: Repeat,
+    // For every_hours
+    #[serde(default = "default_hours")]
+    pub hours: u64,
+    // "HH:MM" in local time, for daily and weekly
+    #[serde(default = "default_time")]
+    pub time: String,
+    // 0 for Monday to 6 for Sunday, for weekly
+    #[serde(default)]
+    pub weekday: u32,
+    // Run by the Windows Task Scheduler, so the clean happens while the app
+    // is closed too; the app then leaves the schedule to it
+    #[serde(default)]
+    pub task_scheduler: bool,
+}
+
+fn default_hours() -> u64 {
+    6
+}
+
+fn default_time() -> String {
+    "03:00".to_string()
+}
+
+impl Default for MemorySchedule {
+    fn default() -> Self {
+        Self {
+            repeat: Repeat::Off,
+            hours: default_hours(),
+            time: default_time(),
+            weekday: 0,
+            task_scheduler: false,
+        }
+    }
+}
+
+impl MemorySchedule {
+    fn time_of_day(&self) -> Result<NaiveTime, String> {
+        NaiveTime::parse_from_str(&self.time, "%H:%M").map_err(|_| format!("Invalid time {}; use HH:MM", self.time))
+    }
+
+    fn day(&self) -> Result<Weekday, String> {
+        Weekday::try_from(self.weekday as u8).map_err(|_| format!("Invalid weekday {}", self.weekday))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.repeat {
+            Repeat::Off => Ok(()),
+            Repeat::EveryHours if self.hours == 0 => Err("Clean at least every 1 hour".to_string()),
+            Repeat::EveryHours => Ok(()),
+            Repeat::Daily => self.time_of_day().map(|_| ()),
+            Repeat::Weekly => self.time_of_day().and(self.day()).map(|_| ()),
+        }
+    }
+
+    // The first run after `after`
+    fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let weekday = match self.repeat {
+            Repeat::Off => return None,
+            Repeat::EveryHours => {
+                let hours = chrono::Duration::try_hours(i64::try_from(self.hours).ok()?)?;
+                return after.checked_add_signed(hours);
+            }
+            Repeat::Daily => None,
+            Repeat::Weekly => Some(self.day().ok()?),
+        };
+        let time = self.time_of_day().ok()?;
+        let mut date = after.date_naive();
+        // A week and a day covers every weekly slot; a time skipped by a
+        // daylight saving change moves to the next day
+        for _ in 0..8 {
+            if weekday.is_none_or(|day| date.weekday() == day) {
+                if let Some(at) = Local.from_local_datetime(&date.and_time(time)).earliest() {
+                    if at > after {
+                        return Some(at);
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("memory-schedule.json"))
+}
+
+// Seconds since the Unix epoch of the last scheduled clean, or of when the
+// schedule was first seen, so a new schedule starts counting from then
+fn last_run() -> Option<i64> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| text.trim().parse().ok())
+}
+
+pub fn record_run() {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, Local::now().timestamp().to_string());
+}
+
+// Seconds since the Unix epoch; None when the schedule is off
+pub fn next_run(schedule: &MemorySchedule) -> Option<i64> {
+    let last = last_run().and_then(|secs| Local.timestamp_opt(secs, 0).single());
+    schedule
+        .next_after(last.unwrap_or_else(Local::now))
+        .map(|next| next.timestamp())
+}
+
+// Whether the app should clean now. A run missed while the app was closed
+// happens once when it is next checked.
+pub fn is_due(schedule: &MemorySchedule) -> bool {
+    if schedule.repeat == Repeat::Off || (cfg!(target_os = "windows") && schedule.task_scheduler) {
+        return false;
+    }
+    if last_run().is_none() {
+        record_run();
+        return false;
+    }
+    next_run(schedule).is_some_and(|next| next <= Local::now().timestamp())
+}
+
+#[cfg(target_os = "windows")]
+const TASK_NAME: &str = "MemoryCacheManager\\Scheduled memory clean";
+
+// Create, replace or remove the Task Scheduler task for `schedule`
+#[cfg(target_os = "windows")]
+pub fn sync_task(schedule: &MemorySchedule) -> Result<(), String> {
+    use crate::targets::tool_output;
+
+    if schedule.repeat == Repeat::Off || !schedule.task_scheduler {
+        // Fails when there is no task, which is the state wanted anyway
+        let _ = tool_output("schtasks", &["/delete", "/tn", TASK_NAME, "/f"]);
+        return Ok(());
+    }
+    schedule.validate()?;
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let command = format!("\"{}\" clean-memory", exe.display());
+    let hours = schedule.hours.to_string();
+    const DAYS: [&str; 7] = ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    let mut args = vec!["/create", "/f", "/tn", TASK_NAME, "/tr", &command];
+    match schedule.repeat {
+        Repeat::EveryHours => args.extend(["/sc", "HOURLY", "/mo", &hours]),
+        Repeat::Daily => args.extend(["/sc", "DAILY", "/st", &schedule.time]),
+        Repeat::Weekly => args.extend([
+            "/sc",
+            "WEEKLY",
+            "/d",
+            DAYS[schedule.weekday as usize],
+            "/st",
+            &schedule.time,
+        ]),
+        Repeat::Off => {}
+    }
+    tool_output("schtasks", &args)
+        .map(|_| ())
+        .ok_or_else(|| "Failed to register the task with Task Scheduler".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn sync_task(_schedule: &MemorySchedule) -> Result<(), String> {
+    Ok(())
+}
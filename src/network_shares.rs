@@ -0,0 +1,80 @@
+use crate::cleaner;
+use crate::targets::expand_path;
+use std::path::{Path, PathBuf};
+
+// Folders on network shares whose size is tracked for admins watching them
+// grow. They are report-only targets and never cleaned; cleans stay on this
+// machine's own disks, so custom cache folders on a share are refused.
+
+// A UNC path or a mapped network drive
+#[cfg(target_os = "windows")]
+pub fn is_network_path(path: &Path) -> bool {
+    use std::path::{Component, Prefix};
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetDriveTypeW;
+    const DRIVE_REMOTE: u32 = 4;
+
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return false;
+    };
+    match prefix.kind() {
+        Prefix::UNC(..) | Prefix::VerbatimUNC(..) => true,
+        Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+            let root = format!("{}:\\", letter as char);
+            unsafe { GetDriveTypeW(&HSTRING::from(root)) == DRIVE_REMOTE }
+        }
+        _ => false,
+    }
+}
+
+// On an NFS, SMB, SSHFS or WebDAV mount
+#[cfg(target_os = "linux")]
+pub fn is_network_path(path: &Path) -> bool {
+    const NETWORK_FILESYSTEMS: &[&str] = &[
+        "nfs",
+        "nfs4",
+        "cifs",
+        "smb3",
+        "smbfs",
+        "fuse.sshfs",
+        "davfs",
+        "fuse.davfs2",
+    ];
+    crate::temp_advisor::mount_field(path, "FSTYPE")
+        .is_some_and(|fstype| NETWORK_FILESYSTEMS.contains(&fstype.as_str()))
+}
+
+// Mounted from "//user@server/share" (SMB, AFP), "server:/export" (NFS) or
+// "http://server/..." (WebDAV)
+#[cfg(target_os = "macos")]
+pub fn is_network_path(path: &Path) -> bool {
+    crate::disk_space::df_rows(&[&path.to_string_lossy()])
+        .first()
+        .is_some_and(|row| row[0].starts_with("//") || row[0].contains(":/"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn is_network_path(_path: &Path) -> bool {
+    false
+}
+
+// A share folder as entered, expanded; it must be an absolute path on a
+// network share
+pub fn share_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = expand_path(dir.trim());
+    if !path.is_absolute() {
+        return Err(format!("Network share {} is not an absolute path", dir));
+    }
+    cleaner::check_path(&path).map_err(|e| format!("Network share {}", e))?;
+    if !is_network_path(&path) {
+        return Err(format!(
+            "{} is not on a network share; add local folders as custom cache folders",
+            dir
+        ));
+    }
+    Ok(path)
+}
+
+pub fn validate(dirs: &[String]) -> Result<(), String> {
+    dirs.iter().try_for_each(|dir| share_dir(dir).map(|_| ()))
+}
@@ -0,0 +1,101 @@
+use crate::config_file;
+use crate::data_retention::{self, Policy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Oldest entries are dropped beyond this
+const MAX_NOTIFICATIONS: usize = 100;
+
+// Serializes read-modify-write cycles on the notifications file
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub timestamp: u64,
+    pub level: Level,
+    pub message: String,
+    pub read: bool,
+}
+
+fn notifications_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("notifications.json"))
+}
+
+fn read_all() -> Vec<Notification> {
+    notifications_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(notifications: &[Notification]) {
+    let Some(path) = notifications_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string_pretty(notifications) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Drop the notifications the retention settings no longer keep
+pub fn compact(policy: &Policy) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut notifications = read_all();
+    if data_retention::fit(&mut notifications, policy, |notification| notification.timestamp) {
+        write_all(&notifications);
+    }
+}
+
+// Record an event for the notification panel. Failures to persist are
+// ignored; notifications must never break the operation they describe.
+pub fn push(level: Level, message: impl Into<String>) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut notifications = read_all();
+    notifications.push(Notification {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        level,
+        message: message.into(),
+        read: false,
+    });
+    let excess = notifications.len().saturating_sub(MAX_NOTIFICATIONS);
+    notifications.drain(..excess);
+    write_all(&notifications);
+}
+
+// Newest first
+pub fn list() -> Vec<Notification> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut notifications = read_all();
+    notifications.reverse();
+    notifications
+}
+
+pub fn mark_all_read() {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut notifications = read_all();
+    for notification in &mut notifications {
+        notification.read = true;
+    }
+    write_all(&notifications);
+}
+
+pub fn clear() {
+    let _guard = FILE_LOCK.lock().unwrap();
+    write_all(&[]);
+}
@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+
+// A PowerShell module wrapping the command line, for admins' scripts:
+// Get-CacheStatus lists the targets with their sizes and Invoke-CacheClean
+// cleans them, both returning objects rather than text. It is written with
+// the path of this executable, so it keeps working from any folder.
+
+const MODULE_NAME: &str = "MemoryCacheManager";
+
+const MODULE: &str = r#"
+function Invoke-Manager {
+    param([string[]]$Arguments)
+    $output = & $script:Exe @Arguments
+    [pscustomobject]@{ ExitCode = $LASTEXITCODE; Json = ($output -join "`n") }
+}
+
+<#
+.SYNOPSIS
+Lists the cache targets found on this machine with their sizes.
+.PARAMETER Target
+Ids of the targets to list; all of them when omitted.
+#>
+function Get-CacheStatus {
+    [CmdletBinding()]
+    param([Parameter(Position = 0)][string[]]$Target)
+
+    $result = Invoke-Manager @('status', '--format', 'json')
+    if ($result.ExitCode -ne 0) {
+        throw "memory-cache-manager status failed with exit code $($result.ExitCode)"
+    }
+    $status = $result.Json | ConvertFrom-Json
+    foreach ($item in $status.targets) {
+        if ($Target -and $Target -notcontains $item.id) {
+            continue
+        }
+        [pscustomobject]@{
+            PSTypeName       = 'MemoryCacheManager.Target'
+            Id               = $item.id
+            Name             = $item.name
+            Category         = $item.category
+            SizeBytes        = $item.stats.size_bytes
+            ReclaimableBytes = $item.reclaimable_bytes
+            FileCount        = $item.stats.file_count
+            ReportOnly       = $item.report_only
+            RunningApp       = $item.running_app
+            Paths            = $item.paths
+        }
+    }
+}
+
+<#
+.SYNOPSIS
+Cleans cache targets, as memory-cache-manager clean does.
+.PARAMETER Target
+Ids of the targets to clean; also taken from Get-CacheStatus output.
+.PARAMETER Profile
+Cleans the targets of a profile, with its age policy and threshold.
+.PARAMETER MaxDuration
+Stops between files once this much time has passed.
+.EXAMPLE
+Invoke-CacheClean -Profile Deep
+.EXAMPLE
+Get-CacheStatus | Where-Object ReclaimableBytes -gt 1GB | Invoke-CacheClean
+#>
+function Invoke-CacheClean {
+    [CmdletBinding(SupportsShouldProcess, DefaultParameterSetName = 'Target')]
+    param(
+        [Parameter(ParameterSetName = 'Target', Position = 0, ValueFromPipelineByPropertyName)]
+        [Alias('Id')]
+        [string[]]$Target,
+        [Parameter(ParameterSetName = 'Profile', Mandatory)]
+        [Alias('Profile')]
+        [string]$ProfileName,
+        [double]$ThresholdGB,
+        [ValidateRange(1, 64)]
+        [int]$Concurrency,
+        [ValidateSet('Low', 'Normal')]
+        [string]$IOPriority,
+        [timespan]$MaxDuration,
+        [switch]$Verify
+    )
+
+    begin {
+        $ids = [System.Collections.Generic.List[string]]::new()
+    }
+    process {
+        foreach ($id in $Target) {
+            $ids.Add($id)
+        }
+    }
+    end {
+        $arguments = @('clean', '--format', 'json')
+        foreach ($id in $ids) {
+            $arguments += '--target', $id
+        }
+        if ($ProfileName) {
+            $arguments += '--profile', $ProfileName
+        }
+        if ($PSBoundParameters.ContainsKey('ThresholdGB')) {
+            $arguments += '--threshold', $ThresholdGB.ToString([cultureinfo]::InvariantCulture)
+        }
+        if ($Concurrency) {
+            $arguments += '--concurrency', $Concurrency
+        }
+        if ($IOPriority) {
+            $arguments += '--io-priority', $IOPriority.ToLowerInvariant()
+        }
+        if ($MaxDuration) {
+            $arguments += '--max-duration', ('{0}s' -f [math]::Ceiling($MaxDuration.TotalSeconds))
+        }
+        if ($Verify) {
+            $arguments += '--verify'
+        }
+
+        $what = if ($ProfileName) { "profile $ProfileName" } elseif ($ids.Count) { $ids -join ', ' } else { 'every target on by default' }
+        if (-not $PSCmdlet.ShouldProcess($what, 'Clean')) {
+            return
+        }
+        $result = Invoke-Manager $arguments
+        switch ($result.ExitCode) {
+            1 { Write-Warning 'Some files could not be deleted or came back' }
+            2 { throw "memory-cache-manager rejected the arguments: $($arguments -join ' ')" }
+            3 { Write-Warning 'Stopped at MaxDuration; the rest was not cleaned' }
+        }
+        if ($result.Json) {
+            $result.Json | ConvertFrom-Json
+        }
+    }
+}
+
+Export-ModuleMember -Function Get-CacheStatus, Invoke-CacheClean
+"#;
+
+// The module's text, calling the executable at its current path
+pub fn module_text() -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let exe = exe.display().to_string().replace('\'', "''");
+    Ok(format!("$script:Exe = '{}'{}", exe, MODULE))
+}
+
+// The user's Documents folder, wherever Windows was told to keep it
+#[cfg(target_os = "windows")]
+fn documents_dir() -> Option<PathBuf> {
+    let output = crate::targets::tool_output(
+        "reg",
+        &[
+            "query",
+            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\User Shell Folders",
+            "/v",
+            "Personal",
+        ],
+    );
+    output
+        .as_deref()
+        .and_then(|output| output.lines().find_map(|line| line.split_once("REG_EXPAND_SZ").or(line.split_once("REG_SZ"))))
+        .map(|(_, value)| crate::targets::expand_path(value.trim()))
+        .or_else(|| crate::targets::home_dir().map(|p| p.join("Documents")))
+}
+
+// Per-user module folders of Windows PowerShell and PowerShell 7
+#[cfg(target_os = "windows")]
+fn module_dirs() -> Vec<PathBuf> {
+    let Some(documents) = documents_dir() else {
+        return Vec::new();
+    };
+    vec![
+        documents.join("WindowsPowerShell").join("Modules"),
+        documents.join("PowerShell").join("Modules"),
+    ]
+}
+
+#[cfg(not(target_os = "windows"))]
+fn module_dirs() -> Vec<PathBuf> {
+    let data_home = crate::targets::env_path("XDG_DATA_HOME")
+        .or_else(|| crate::targets::home_dir().map(|p| p.join(".local").join("share")));
+    data_home.map(|p| p.join("powershell").join("Modules")).into_iter().collect()
+}
+
+// Write the module into each per-user module folder, or into `dir`, so
+// `Import-Module MemoryCacheManager` finds it. Returns the files written.
+pub fn install(dir: Option<PathBuf>) -> Result<Vec<PathBuf>, String> {
+    let text = module_text()?;
+    let dirs = match dir {
+        Some(dir) => vec![dir],
+        None => module_dirs(),
+    };
+    if dirs.is_empty() {
+        return Err("Could not find the PowerShell module folder".to_string());
+    }
+    let mut written = Vec::new();
+    for dir in dirs {
+        let module_dir = dir.join(MODULE_NAME);
+        std::fs::create_dir_all(&module_dir)
+            .map_err(|e| format!("Failed to create {}: {}", module_dir.display(), e))?;
+        let path = module_dir.join(format!("{}.psm1", MODULE_NAME));
+        std::fs::write(&path, &text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        written.push(path);
+    }
+    Ok(written)
+}
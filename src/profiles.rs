@@ -0,0 +1,142 @@
+use crate::cleaner::DeletionMode;
+use crate::detect;
+use crate::targets::{CacheTarget, ALL_TARGETS};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// A named set of targets cleaned together, from the window or with
+// `clean --profile`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    // Target ids, or "*" for every target that is not off by default; ids of
+    // targets not found on this machine are skipped
+    pub targets: Vec<String>,
+    // Files used within this many days are kept, instead of each target's
+    // own setting
+    #[serde(default)]
+    pub min_age_days: Option<u64>,
+    // Nothing is cleaned unless cleaning the targets would free at least this
+    // many gigabytes (in the configured size units); 0 always cleans
+    #[serde(default)]
+    pub threshold_gb: f64,
+    // Overwrite files before deleting them, for caches of private data;
+    // cleans take about twice as long
+    #[serde(default)]
+    pub secure_delete: bool,
+}
+
+impl Profile {
+    // How the profile's cleans delete files, given the configured way
+    pub fn deletion(&self, configured: DeletionMode) -> DeletionMode {
+        if self.secure_delete {
+            DeletionMode::Overwrite
+        } else {
+            configured
+        }
+    }
+}
+
+// Cleaned when no targets are chosen inside a virtual machine or Windows
+// Sandbox
+pub const VIRTUAL_MACHINE: &str = "VM";
+
+// The safest targets, cleaned at once when the system drive is nearly full
+pub const QUICK: &str = "Quick";
+
+pub fn defaults() -> Vec<Profile> {
+    let profile = |name: &str, targets: &[&str], min_age_days| Profile {
+        name: name.to_string(),
+        targets: targets.iter().map(|id| id.to_string()).collect(),
+        min_age_days,
+        threshold_gb: 0.0,
+        secure_delete: false,
+    };
+    vec![
+        profile(QUICK, &["temp-files"], None),
+        profile("Deep", &[ALL_TARGETS, "windows-update-cache"], None),
+        // Packages used in the last month are likely needed by the next build
+        profile(
+            "Dev",
+            &[
+                "pip-cache",
+                "npm-cache",
+                "yarn-cache",
+                "pnpm-cache",
+                "cargo-registry-cache",
+                "gradle-caches",
+                "nuget-cache",
+                "go-mod-cache",
+                "go-build-cache",
+                "dart-pub-cache",
+            ],
+            Some(30),
+        ),
+        // For VM image maintenance: recently used files go too, and Prefetch
+        // and update downloads, which are off by default, stay
+        profile(VIRTUAL_MACHINE, &[ALL_TARGETS], Some(0)),
+    ]
+}
+
+// Names must be unique and thresholds real numbers
+pub fn validate(profiles: &[Profile]) -> Result<(), String> {
+    for (i, profile) in profiles.iter().enumerate() {
+        if profile.name.trim().is_empty() {
+            return Err("Profiles need a name".to_string());
+        }
+        if profiles[..i].iter().any(|other| other.name.eq_ignore_ascii_case(&profile.name)) {
+            return Err(format!("There is more than one profile named {}", profile.name));
+        }
+        if !profile.threshold_gb.is_finite() || profile.threshold_gb < 0.0 {
+            return Err(format!("The threshold of {} must be 0 or more", profile.name));
+        }
+    }
+    Ok(())
+}
+
+pub fn find<'a>(profiles: &'a [Profile], name: &str) -> Result<&'a Profile, String> {
+    profiles
+        .iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown profile {}", name))
+}
+
+// The profile named `name` of `profiles`, or the built-in one if it was
+// removed
+fn find_or_default(profiles: &[Profile], name: &str) -> Option<Profile> {
+    find(profiles, name)
+        .ok()
+        .cloned()
+        .or_else(|| defaults().into_iter().find(|profile| profile.name == name))
+}
+
+// Inside a virtual machine, what it is with the profile to clean when none is
+// chosen: the VM profile of `profiles`, or the built-in one if it was removed
+pub fn for_virtual_machine(profiles: &[Profile]) -> Option<(&'static str, Profile)> {
+    let machine = detect::virtual_machine()?;
+    Some((machine, find_or_default(profiles, VIRTUAL_MACHINE)?))
+}
+
+// The Quick profile of `profiles`, or the built-in one if it was removed
+pub fn quick(profiles: &[Profile]) -> Option<Profile> {
+    find_or_default(profiles, QUICK)
+}
+
+// The profile's targets out of `available`, with its age policy applied;
+// report-only targets are left out
+pub fn select(profile: &Profile, available: &[CacheTarget]) -> Vec<CacheTarget> {
+    let all = profile.targets.iter().any(|id| id == ALL_TARGETS);
+    available
+        .iter()
+        .filter(|target| !target.report_only)
+        .filter(|target| (all && target.warning.is_none()) || profile.targets.contains(&target.id))
+        .map(|target| {
+            let mut target = target.clone();
+            if let Some(days) = profile.min_age_days {
+                let min_age = (days > 0).then(|| Duration::from_secs(days.saturating_mul(24 * 60 * 60)));
+                target.exclusions = target.exclusions.with_min_age(min_age);
+            }
+            target
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+use crate::cleaner::{self, CleanStats, Exclusions, FailedDelete};
+use crate::config_file;
+use crate::reboot_queue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A file is quarantined once this many cleans in a row failed to delete it
+const REPEATED_FAILURES: u32 = 2;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FailedFile {
+    pub path: PathBuf,
+    // The error of the latest attempt
+    pub error: String,
+    pub attempts: u32,
+    pub last_failed: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("quarantine.json"))
+}
+
+// Every file that failed to delete, keyed by target id
+fn read_all() -> BTreeMap<String, Vec<FailedFile>> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(failed: &BTreeMap<String, Vec<FailedFile>>) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(failed) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Add a clean's failures, given per target id, to the count of each file.
+// Files that are gone since (deleted by a later clean or by hand) drop out.
+pub fn record(failures: BTreeMap<String, Vec<FailedDelete>>) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut all = read_all();
+    let now = now();
+    for (target, failed) in failures {
+        let files = all.entry(target).or_default();
+        for FailedDelete { path, error, .. } in failed {
+            match files.iter_mut().find(|file| file.path == path) {
+                Some(file) => {
+                    file.error = error;
+                    file.attempts += 1;
+                    file.last_failed = now;
+                }
+                None => files.push(FailedFile {
+                    path,
+                    error,
+                    attempts: 1,
+                    last_failed: now,
+                }),
+            }
+        }
+    }
+    for files in all.values_mut() {
+        files.retain(|file| file.path.exists());
+    }
+    all.retain(|_, files| !files.is_empty());
+    write_all(&all);
+}
+
+// Files that failed repeatedly, keyed by target id, leaving out those
+// already queued for deletion at restart
+pub fn list() -> BTreeMap<String, Vec<FailedFile>> {
+    let mut all = {
+        let _guard = FILE_LOCK.lock().unwrap();
+        read_all()
+    };
+    let queued: Vec<_> = reboot_queue::pending().into_iter().map(|file| file.path).collect();
+    for files in all.values_mut() {
+        files.retain(|file| file.attempts >= REPEATED_FAILURES && file.path.exists() && !queued.contains(&file.path));
+    }
+    all.retain(|_, files| !files.is_empty());
+    all
+}
+
+// Try to delete every quarantined file again; those that still fail stay
+// listed with the new error
+pub fn retry() -> CleanStats {
+    let mut stats = CleanStats::default();
+    let mut failures = BTreeMap::new();
+    for (target, files) in list() {
+        for file in files {
+            if cleaner::is_cancelled() {
+                break;
+            }
+            let path = cleaner::verbatim(&file.path);
+            let Ok(meta) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if meta.is_file() && !cleaner::is_protected(&path) {
+                cleaner::remove_file(&path, &meta, None, &Exclusions::default(), &mut stats);
+            }
+        }
+        failures.insert(target, cleaner::take_failed());
+    }
+    record(failures);
+    stats
+}
@@ -0,0 +1,148 @@
+use crate::config_file;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Files that no clean could delete are handed to Windows to delete while it
+// starts next, before any program can open them again. The queue kept here
+// mirrors what was handed over, so the app can tell afterwards what went.
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingFile {
+    pub path: PathBuf,
+    pub target: String,
+    pub size_bytes: u64,
+    pub queued_at: u64,
+}
+
+// How a reboot went for the files queued before it
+#[derive(Default)]
+pub struct RebootOutcome {
+    pub files_deleted: u64,
+    pub freed_bytes: u64,
+    // Still there after the restart; they stay quarantined
+    pub files_left: u64,
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("reboot-queue.json"))
+}
+
+fn read_all() -> Vec<PendingFile> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn write_all(pending: &[PendingFile]) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(pending) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Seconds since the Unix epoch at which the system last started
+#[cfg(target_os = "windows")]
+fn boot_time() -> u64 {
+    use windows::Win32::System::SystemInformation::GetTickCount64;
+
+    now().saturating_sub(unsafe { GetTickCount64() } / 1000)
+}
+
+#[cfg(target_os = "windows")]
+fn delete_at_reboot(path: &std::path::Path) -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVEFILE_DELAY_UNTIL_REBOOT};
+
+    // Needs administrator rights; Windows keeps the list in the registry
+    unsafe { MoveFileExW(&HSTRING::from(path.as_os_str()), PCWSTR::null(), MOVEFILE_DELAY_UNTIL_REBOOT) }
+        .map_err(|e| format!("Failed to queue {}: {}", path.display(), e.message()))
+}
+
+// Files queued and not yet resolved by a restart
+pub fn pending() -> Vec<PendingFile> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    read_all()
+}
+
+// Queue every quarantined file for deletion at the next restart, returning
+// how many were queued
+#[cfg(target_os = "windows")]
+pub fn queue_quarantined() -> Result<usize, String> {
+    let quarantined = crate::quarantine::list();
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut queue = read_all();
+    let now = now();
+    let mut queued = 0;
+    for (target, files) in quarantined {
+        for file in files {
+            let size_bytes = fs::metadata(&file.path).map_or(0, |meta| meta.len());
+            if let Err(e) = delete_at_reboot(&file.path) {
+                write_all(&queue);
+                return Err(e);
+            }
+            queue.push(PendingFile {
+                path: file.path,
+                target: target.clone(),
+                size_bytes,
+                queued_at: now,
+            });
+            queued += 1;
+        }
+    }
+    write_all(&queue);
+    Ok(queued)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn queue_quarantined() -> Result<usize, String> {
+    Err("Deleting files at restart is only available on Windows".to_string())
+}
+
+// After a restart, drop the files queued before it from the queue and count
+// which of them went. None while nothing queued has seen a restart yet.
+#[cfg(target_os = "windows")]
+pub fn reconcile() -> Option<RebootOutcome> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let boot_time = boot_time();
+    let (settled, waiting): (Vec<_>, Vec<_>) = read_all()
+        .into_iter()
+        .partition(|file| file.queued_at < boot_time || !file.path.exists());
+    if settled.is_empty() {
+        return None;
+    }
+    let mut outcome = RebootOutcome::default();
+    for file in &settled {
+        if file.path.exists() {
+            outcome.files_left += 1;
+        } else {
+            outcome.files_deleted += 1;
+            outcome.freed_bytes += file.size_bytes;
+        }
+    }
+    write_all(&waiting);
+    Some(outcome)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn reconcile() -> Option<RebootOutcome> {
+    None
+}
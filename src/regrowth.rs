@@ -0,0 +1,108 @@
+use crate::clean_history::TargetRun;
+use crate::config_file;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// How fast each target fills up again after a clean, measured by the scans
+// that follow it, as the days it takes to get back to its size before
+
+// Growth over less than this says little about the days that follow
+const MIN_SPAN_SECS: u64 = 12 * 60 * 60;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Default)]
+struct Regrowth {
+    freed_bytes: u64,
+    // The first scan after the clean and the latest one, as (seconds since
+    // the Unix epoch, size)
+    first: Option<(u64, u64)>,
+    latest: Option<(u64, u64)>,
+    // Kept from before the last clean until the new one has enough scans
+    days: Option<f64>,
+}
+
+impl Regrowth {
+    fn estimate(&self) -> Option<f64> {
+        let ((first_at, first_size), (latest_at, latest_size)) = (self.first?, self.latest?);
+        let span = latest_at.saturating_sub(first_at);
+        if span < MIN_SPAN_SECS || latest_size <= first_size {
+            return None;
+        }
+        let bytes_per_day = (latest_size - first_size) as f64 / (span as f64 / 86_400.0);
+        Some(self.freed_bytes as f64 / bytes_per_day)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("regrowth.json"))
+}
+
+fn read_all() -> BTreeMap<String, Regrowth> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(all: &BTreeMap<String, Regrowth>) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(all) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Start measuring again for every target a clean freed space in
+pub fn record_clean(targets: &[TargetRun]) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut all = read_all();
+    for target in targets.iter().filter(|target| target.stats.freed_bytes > 0) {
+        let entry = all.entry(target.id.clone()).or_default();
+        let days = entry.estimate().or(entry.days);
+        *entry = Regrowth {
+            freed_bytes: target.stats.freed_bytes,
+            days,
+            ..Regrowth::default()
+        };
+    }
+    write_all(&all);
+}
+
+// Add the sizes of a finished scan, as (id, size), and return the days each
+// measured target takes to regrow
+pub fn observe(sizes: impl IntoIterator<Item = (String, u64)>) -> BTreeMap<String, f64> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut all = read_all();
+    let now = now();
+    for (id, size) in sizes {
+        let Some(entry) = all.get_mut(&id) else {
+            continue;
+        };
+        if entry.first.is_none() {
+            entry.first = Some((now, size));
+        }
+        entry.latest = Some((now, size));
+        if let Some(days) = entry.estimate() {
+            entry.days = Some(days);
+        }
+    }
+    write_all(&all);
+    all.into_iter()
+        .filter_map(|(id, entry)| Some((id, entry.days?)))
+        .collect()
+}
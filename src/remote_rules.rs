@@ -0,0 +1,186 @@
+use crate::config_file;
+use crate::rule_signatures;
+use crate::rule_simulation::{self, Rule};
+use crate::targets;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A rule file, in the format tried under Try a Rule File, fetched from a
+// configured URL every few hours, so an admin can add targets to every
+// machine without a new release. Its folders are offered as targets with
+// the rules' own exclusions and age policy. The last good copy is kept, and
+// used until a fetch brings a valid one. Unless it is signed with a trusted
+// key, its folders are only sized, never cleaned.
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// Rule files are small; anything bigger is not one
+const MAX_BYTES: u64 = 1024 * 1024;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Default)]
+struct Fetched {
+    url: String,
+    // The file as fetched, parsed again when read
+    text: String,
+    // Its detached signature, fetched from <url>.sig
+    #[serde(default)]
+    signature: Option<String>,
+    fetched_at: u64,
+    // The last fetch, and why it failed; a failed one keeps the text before
+    checked_at: u64,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RemoteRulesStatus {
+    pub url: String,
+    pub fetched_at: Option<u64>,
+    pub rule_count: usize,
+    pub error: Option<String>,
+    // The trusted key the file is signed with, else why its folders are
+    // not cleaned
+    pub signed_by: Option<String>,
+    pub untrusted: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("remote-rules.json"))
+}
+
+// What was fetched from the configured URL; nothing once the URL changes
+fn read(config: &Config) -> Option<Fetched> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str::<Fetched>(&text).ok())
+        .filter(|fetched| !config.remote_rules_url.is_empty() && fetched.url == config.remote_rules_url)
+}
+
+fn write(fetched: &Fetched) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(fetched) {
+        let _ = fs::write(path, text);
+    }
+}
+
+fn download(url: &str) -> Result<String, String> {
+    let response = ureq::get(url)
+        .timeout(TIMEOUT)
+        .call()
+        .map_err(|e| format!("Failed to fetch the rule file: {}", e))?;
+    let mut text = String::new();
+    response
+        .into_reader()
+        .take(MAX_BYTES + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+    if text.len() as u64 > MAX_BYTES {
+        return Err(format!("{} is larger than a rule file can be", url));
+    }
+    Ok(text)
+}
+
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if url.is_empty() || url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(format!("Invalid rule file URL {}; use an http:// or https:// address", url))
+    }
+}
+
+// The rules last fetched from the configured URL
+pub fn rules(config: &Config) -> Vec<Rule> {
+    read(config)
+        .and_then(|fetched| rule_simulation::parse(&fetched.text).ok())
+        .unwrap_or_default()
+}
+
+fn signer(config: &Config, fetched: &Fetched) -> Result<String, String> {
+    rule_signatures::signer(config, fetched.text.as_bytes(), fetched.signature.as_deref())
+}
+
+// Whether the folders of the remote rule file may be cleaned
+pub fn is_trusted(config: &Config) -> bool {
+    read(config).is_some_and(|fetched| signer(config, &fetched).is_ok())
+}
+
+pub fn status(config: &Config) -> RemoteRulesStatus {
+    let fetched = read(config);
+    let signed = fetched.as_ref().filter(|f| f.fetched_at > 0).map(|f| signer(config, f));
+    RemoteRulesStatus {
+        url: config.remote_rules_url.clone(),
+        fetched_at: fetched.as_ref().map(|f| f.fetched_at).filter(|&at| at > 0),
+        rule_count: rules(config).len(),
+        signed_by: signed.clone().and_then(Result::ok),
+        untrusted: signed.and_then(Result::err),
+        error: fetched.and_then(|f| f.error),
+    }
+}
+
+// Fetch the rule file now, keeping the copy before when the new one is
+// unreachable or not a valid rule file
+pub fn fetch(config: &Config) -> RemoteRulesStatus {
+    let url = config.remote_rules_url.clone();
+    if url.is_empty() {
+        return status(config);
+    }
+    let mut fetched = read(config).unwrap_or_else(|| Fetched {
+        url: url.clone(),
+        ..Fetched::default()
+    });
+    fetched.checked_at = now();
+    let valid = |text: String| match rule_simulation::parse(&text) {
+        Ok(_) => Ok(text),
+        Err(e) => Err(format!("{} is not a valid rule file: {}", url, e)),
+    };
+    match download(&url).and_then(valid) {
+        Ok(text) => {
+            // A missing signature leaves the folders sized only
+            let signature = download(&format!("{}.sig", url)).ok();
+            let changed = text != fetched.text || signature != fetched.signature;
+            fetched.text = text;
+            fetched.signature = signature;
+            fetched.fetched_at = fetched.checked_at;
+            fetched.error = None;
+            write(&fetched);
+            if changed {
+                targets::forget_found();
+            }
+        }
+        Err(e) => {
+            fetched.error = Some(e);
+            write(&fetched);
+        }
+    }
+    status(config)
+}
+
+// Fetch when the interval has passed since the last try, or the URL changed
+pub fn fetch_if_due(config: &Config) {
+    if config.remote_rules_url.is_empty() {
+        return;
+    }
+    let interval = config.remote_rules_hours.max(1).saturating_mul(60 * 60);
+    if read(config).is_none_or(|fetched| fetched.checked_at.saturating_add(interval) <= now()) {
+        fetch(config);
+    }
+}
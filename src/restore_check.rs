@@ -0,0 +1,175 @@
+use crate::cleaner::{self, DeletionMode, Exclusions};
+use crate::staging;
+use crate::undo;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A check of the quarantine folder's undo path, to run before relying on it:
+// a set of made-up files is cleaned into the quarantine folder, as a clean in
+// quarantine mode does, then put back, and each file must come back with the
+// same contents (SHA-256), size, modification time and read-only flag.
+
+// Path below the test folder and size of each made-up file
+const FILES: [(&str, usize); 5] = [
+    ("empty.tmp", 0),
+    ("notes.txt", 1_000),
+    ("readonly.log", 4_096),
+    ("nested/deeper/blob.bin", 256 * 1024),
+    ("nested/ünïcode näme.dat", 10_000),
+];
+
+#[derive(Serialize)]
+pub struct CheckedFile {
+    pub path: String,
+    // What did not survive the round trip; empty when the file came back intact
+    pub problems: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RestoreCheck {
+    pub passed: bool,
+    pub files: Vec<CheckedFile>,
+}
+
+struct Fingerprint {
+    sha256: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    readonly: bool,
+}
+
+fn fingerprint(path: &Path) -> io::Result<Fingerprint> {
+    let meta = fs::metadata(path)?;
+    let digest = Sha256::digest(fs::read(path)?);
+    Ok(Fingerprint {
+        sha256: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        size: meta.len(),
+        modified: meta.modified().ok(),
+        readonly: meta.permissions().readonly(),
+    })
+}
+
+// Bytes that neither repeat nor compress, from a xorshift generator
+fn made_up_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn create(dir: &Path) -> io::Result<()> {
+    // Three days back, in even seconds, which FAT keeps too
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let modified = UNIX_EPOCH + Duration::from_secs((now - 3 * 24 * 60 * 60) & !1);
+    for (index, (name, len)) in FILES.iter().enumerate() {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, made_up_bytes(*len, index as u64 + 1))?;
+        fs::File::options().write(true).open(&path)?.set_modified(modified)?;
+        if name.starts_with("readonly") {
+            let mut permissions = fs::metadata(&path)?.permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(&path, permissions)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove(dir: &Path) {
+    // Windows refuses to delete read-only files
+    #[cfg(target_os = "windows")]
+    for (name, _) in FILES {
+        let path = dir.join(name);
+        if let Ok(meta) = fs::metadata(&path) {
+            let mut permissions = meta.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            let _ = fs::set_permissions(&path, permissions);
+        }
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+pub fn run() -> Result<RestoreCheck, String> {
+    let dir = std::env::temp_dir().join(format!("memory-cache-manager-restore-check-{}", std::process::id()));
+    remove(&dir);
+    let before = create(&dir)
+        .and_then(|()| FILES.iter().map(|(name, _)| fingerprint(&dir.join(name))).collect::<io::Result<Vec<_>>>());
+    let before = match before {
+        Ok(before) => before,
+        Err(e) => {
+            remove(&dir);
+            return Err(format!("Failed to create the test files: {}", e));
+        }
+    };
+
+    let batch = undo::begin_automatic(DeletionMode::Staging);
+    cleaner::clean_dir(&dir, None, &Exclusions::default());
+    let undoable = undo::finish(batch);
+    // Not a clean of any target, so nothing to record
+    cleaner::take_failed();
+    cleaner::take_deleted();
+    let not_moved: Vec<bool> = FILES.iter().map(|(name, _)| dir.join(name).exists()).collect();
+
+    // Only what this check moved; cleans running alongside it keep theirs
+    let mut restore_errors = BTreeMap::new();
+    for file in staging::list() {
+        if !undoable.staged().contains(&file.id) {
+            continue;
+        }
+        if let Err(e) = staging::restore(&file.id) {
+            restore_errors.insert(cleaner::comparable(&file.original_path), e);
+        }
+    }
+
+    let mut files = Vec::new();
+    for (((name, _), before), not_moved) in FILES.iter().zip(before).zip(not_moved) {
+        let path = dir.join(name);
+        let mut problems = Vec::new();
+        if not_moved {
+            problems.push("not moved to the quarantine folder".to_string());
+        }
+        match fingerprint(&path) {
+            Err(_) => problems.push(
+                restore_errors
+                    .remove(&cleaner::comparable(&path))
+                    .unwrap_or_else(|| "not put back".to_string()),
+            ),
+            Ok(after) => {
+                if after.sha256 != before.sha256 {
+                    problems.push("contents differ".to_string());
+                }
+                if after.size != before.size {
+                    problems.push(format!("size {} instead of {}", after.size, before.size));
+                }
+                if after.modified != before.modified {
+                    problems.push("modification time changed".to_string());
+                }
+                if after.readonly != before.readonly {
+                    problems.push("read-only flag lost".to_string());
+                }
+            }
+        }
+        files.push(CheckedFile {
+            path: name.to_string(),
+            problems,
+        });
+    }
+    remove(&dir);
+    Ok(RestoreCheck {
+        passed: files.iter().all(|file| file.problems.is_empty()),
+        files,
+    })
+}
@@ -0,0 +1,121 @@
+use crate::Config;
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Rule files from elsewhere, the remote one or one picked to activate, have
+// their folders cleaned only when signed with one of the configured trusted
+// keys. The signature is a detached ed25519 one of the file's bytes, in hex,
+// next to the file as <file>.sig, or <url>.sig for the remote one; keys are
+// 32-byte public keys in hex.
+
+fn from_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let text = text.trim();
+    // from_str_radix also takes a sign, as in "+f"
+    if text.len() != N * 2 || !text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+fn parse_key(key: &str) -> Result<VerifyingKey, String> {
+    from_hex::<32>(key)
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| format!("Invalid trusted key {}; use a 32-byte ed25519 public key in hex", key.trim()))
+}
+
+pub fn validate_keys(keys: &[String]) -> Result<(), String> {
+    keys.iter().try_for_each(|key| parse_key(key).map(|_| ()))
+}
+
+// The trusted key that signed `contents`, or why none did
+pub fn signer(config: &Config, contents: &[u8], signature: Option<&str>) -> Result<String, String> {
+    if config.trusted_rule_keys.is_empty() {
+        return Err("No trusted keys are set to check the rule file's signature against".to_string());
+    }
+    let signature = signature.ok_or("The rule file is not signed")?;
+    let signature = from_hex::<64>(signature)
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .ok_or("The rule file's signature is not an ed25519 signature in hex")?;
+    config
+        .trusted_rule_keys
+        .iter()
+        .find(|key| parse_key(key).is_ok_and(|key| key.verify_strict(contents, &signature).is_ok()))
+        .map(|key| key.trim().to_string())
+        .ok_or_else(|| "The rule file's signature does not match any trusted key".to_string())
+}
+
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+// The trusted key that signed a rule file on disk
+pub fn file_signer(config: &Config, path: &Path) -> Result<String, String> {
+    let contents = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let signature = fs::read_to_string(signature_path(path)).ok();
+    signer(config, &contents, signature.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn config_trusting(keys: &[&SigningKey]) -> Config {
+        Config {
+            trusted_rule_keys: keys.iter().map(|key| hex(key.verifying_key().as_bytes())).collect(),
+            ..Config::default()
+        }
+    }
+
+    const RULES: &[u8] = br#"{"rules": [{"path": "%TEMP%\\Tool"}]}"#;
+
+    #[test]
+    fn a_trusted_signature_names_its_key() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let config = config_trusting(&[&SigningKey::from_bytes(&[1; 32]), &key]);
+        let signature = hex(&key.sign(RULES).to_bytes());
+        assert_eq!(signer(&config, RULES, Some(&signature)), Ok(hex(key.verifying_key().as_bytes())));
+    }
+
+    #[test]
+    fn changed_unsigned_or_untrusted_files_are_refused() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signature = hex(&key.sign(RULES).to_bytes());
+        let config = config_trusting(&[&key]);
+        assert!(signer(&config, br#"{"rules": [{"path": "C:\\"}]}"#, Some(&signature)).is_err());
+        assert!(signer(&config, RULES, None).is_err());
+        assert!(signer(&config, RULES, Some("not hex")).is_err());
+        let other = config_trusting(&[&SigningKey::from_bytes(&[1; 32])]);
+        assert!(signer(&other, RULES, Some(&signature)).is_err());
+        assert!(signer(&Config::default(), RULES, Some(&signature)).is_err());
+    }
+
+    #[test]
+    fn keys_must_be_32_bytes_of_hex() {
+        let key = hex(SigningKey::from_bytes(&[7; 32]).verifying_key().as_bytes());
+        assert!(validate_keys(std::slice::from_ref(&key)).is_ok());
+        assert!(validate_keys(&[key[..62].to_string()]).is_err());
+        assert!(validate_keys(&["zz".repeat(32)]).is_err());
+    }
+
+    #[test]
+    fn hex_takes_only_hex_digits() {
+        assert_eq!(from_hex::<2>("0aFf"), Some([0x0a, 0xff]));
+        assert_eq!(from_hex::<2>(" 0aff\n"), Some([0x0a, 0xff]));
+        assert_eq!(from_hex::<2>("+f0a"), None);
+        assert_eq!(from_hex::<2>("0a-f"), None);
+        assert_eq!(from_hex::<2>("0a f"), None);
+        assert_eq!(from_hex::<2>("0af"), None);
+    }
+}
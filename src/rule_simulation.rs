@@ -0,0 +1,183 @@
+use crate::cleaner::{self, Candidate, Exclusions, Strategy};
+use crate::file_locks::LockingApp;
+use crate::targets::{self, CacheTarget, ALL_TARGETS};
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Rules under development: a JSON file of folders that would become custom
+// folders, each with its own exclusions and age policy, like
+//   {"rules": [{"path": "%LOCALAPPDATA%\\Tool\\Cache", "exclusions": ["*.lock"], "min_age_days": 7}]}
+// A simulation lists what each would delete without deleting anything, and
+// where it runs into what the app keeps safe or already cleans, so the file
+// can be fixed before its rules are added.
+
+#[derive(Deserialize)]
+pub struct Rule {
+    // As it would be entered as a custom folder
+    pub path: String,
+    #[serde(default)]
+    pub exclusions: Vec<String>,
+    // None for the age policy of every target
+    #[serde(default)]
+    pub min_age_days: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    rules: Vec<Rule>,
+}
+
+// Files listed per rule, largest first; the totals count them all
+const LISTED_FILES: usize = 50;
+
+#[derive(Serialize)]
+pub struct RuleResult {
+    pub path: String,
+    // Why the folder could not be added; nothing is scanned then
+    pub refused: Option<String>,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub files: Vec<Candidate>,
+    // Where the rule meets the app's own files, the exclusions for every
+    // target or a target that already cleans the folder
+    pub conflicts: Vec<String>,
+    pub locked_by: Vec<LockingApp>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<Rule>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{} is not a valid rule file: {}", path.display(), e))
+}
+
+// The rules of a rule file's text, wherever it came from
+pub fn parse(text: &str) -> Result<Vec<Rule>, String> {
+    let file: RuleFile = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    for rule in &file.rules {
+        Exclusions::validate(&rule.exclusions).map_err(|e| format!("{}: {}", rule.path, e))?;
+    }
+    Ok(file.rules)
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string().trim_start_matches(r"\\?\").to_string()
+}
+
+fn simulate_rule(config: &Config, existing: &[CacheTarget], rule: &Rule) -> RuleResult {
+    let mut result = RuleResult {
+        path: rule.path.clone(),
+        refused: None,
+        file_count: 0,
+        total_bytes: 0,
+        files: Vec::new(),
+        conflicts: Vec::new(),
+        locked_by: Vec::new(),
+    };
+    let path = match targets::local_custom_dir(&rule.path) {
+        Ok(path) => path,
+        Err(e) => {
+            result.refused = Some(e);
+            return result;
+        }
+    };
+    let id = format!("custom:{}", rule.path);
+
+    if config.custom_cache_dirs.contains(&rule.path) {
+        result
+            .conflicts
+            .push("Already a custom folder; activating replaces its exclusions and age policy".to_string());
+    }
+    let root = cleaner::comparable(&path);
+    for target in existing.iter().filter(|target| target.id != id) {
+        let overlapping: Vec<_> = target
+            .paths
+            .iter()
+            .filter(|other| {
+                let other = cleaner::comparable(other);
+                other.starts_with(&root) || root.starts_with(&other)
+            })
+            .collect();
+        match overlapping[..] {
+            [] => {}
+            [other] => result
+                .conflicts
+                .push(format!("Overlaps the {} target at {}", target.name, display(other))),
+            [first, ref rest @ ..] => result.conflicts.push(format!(
+                "Overlaps the {} target at {} and {} more locations",
+                target.name,
+                display(first),
+                rest.len()
+            )),
+        }
+    }
+    if cleaner::is_protected(&path) {
+        result
+            .conflicts
+            .push("The folder holds the app's own files, none of which are deleted".to_string());
+    }
+    for protected in cleaner::protected_under(&path) {
+        result
+            .conflicts
+            .push(format!("{} is one of the app's own files and is never deleted", display(&protected)));
+    }
+
+    let min_age = rule
+        .min_age_days
+        .or_else(|| targets::min_age_days(config, ALL_TARGETS))
+        .filter(|&days| days > 0)
+        .map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60)));
+    let everywhere = config.exclusions.get(ALL_TARGETS).into_iter().flatten();
+    let exclusions = Exclusions::new(rule.exclusions.iter().chain(everywhere)).with_min_age(min_age);
+    let preview = targets::preview(&targets::trial_target(&rule.path, path.clone(), exclusions));
+    if config.exclusions.get(ALL_TARGETS).is_some_and(|patterns| !patterns.is_empty()) {
+        let own = Exclusions::new(&rule.exclusions).with_min_age(min_age);
+        let kept = cleaner::preview_target(&[path], Strategy::DeleteAll, &own)
+            .len()
+            .saturating_sub(preview.files.len());
+        if kept > 0 {
+            result
+                .conflicts
+                .push(format!("{} matched files are kept by the exclusions for every target", kept));
+        }
+    }
+
+    let mut files = preview.files;
+    files.sort_by_key(|file| Reverse(file.size));
+    result.file_count = files.len();
+    result.total_bytes = preview.total_bytes;
+    files.truncate(LISTED_FILES);
+    result.files = files;
+    result.locked_by = preview.locked_by;
+    result
+}
+
+pub fn simulate(config: &Config, rules: &[Rule]) -> Vec<RuleResult> {
+    let existing = targets::builtin_targets(config);
+    rules.iter().map(|rule| simulate_rule(config, &existing, rule)).collect()
+}
+
+// Add each rule as a custom folder with its exclusions and age policy,
+// replacing those of a folder added before
+pub fn activate(config: &mut Config, rules: &[Rule]) -> Result<(), String> {
+    let dirs: Vec<String> = rules.iter().map(|rule| rule.path.clone()).collect();
+    targets::validate_custom_dirs(&dirs)?;
+    for rule in rules {
+        if !config.custom_cache_dirs.contains(&rule.path) {
+            config.custom_cache_dirs.push(rule.path.clone());
+        }
+        let id = format!("custom:{}", rule.path);
+        if rule.exclusions.is_empty() {
+            config.exclusions.remove(&id);
+        } else {
+            config.exclusions.insert(id.clone(), rule.exclusions.clone());
+        }
+        match rule.min_age_days {
+            Some(days) => config.min_age_days.insert(id, days),
+            None => config.min_age_days.remove(&id),
+        };
+    }
+    Ok(())
+}
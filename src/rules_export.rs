@@ -0,0 +1,95 @@
+use crate::clean_history;
+use crate::cleaner::{DeletionMode, ScanLimits, Strategy};
+use crate::schedule::{self, Frequency};
+use crate::targets::{self, CacheFormat, CacheTarget, Category, TargetLabel};
+use crate::Config;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Everything the app would clean on this machine and how, with the config
+// applied, written out for review or attached to a bug report
+
+#[derive(Serialize)]
+pub struct TargetRules {
+    pub id: String,
+    pub name: String,
+    pub category: Category,
+    // Only the locations that exist
+    pub paths: Vec<PathBuf>,
+    pub strategy: String,
+    // "files", or "chromium" for caches cleaned through their index
+    pub format: &'static str,
+    pub report_only: bool,
+    // Set for targets that are off by default
+    pub warning: Option<&'static str>,
+    // Skipped while this program runs
+    pub skipped_while_running: Option<&'static str>,
+    pub scan_limits: ScanLimits,
+    // Glob patterns of files that are never deleted
+    pub exclusions: Vec<String>,
+    pub min_age_days: Option<u64>,
+    pub schedule: Frequency,
+    // The note and owner given to a custom folder
+    pub label: Option<TargetLabel>,
+}
+
+#[derive(Serialize)]
+pub struct EffectiveRules {
+    pub generated_at: String,
+    pub version: &'static str,
+    pub platform: &'static str,
+    pub deletion: DeletionMode,
+    pub targets: Vec<TargetRules>,
+}
+
+fn describe_strategy(strategy: Strategy) -> String {
+    strategy.describe().unwrap_or_else(|| "delete all".to_string())
+}
+
+fn target_rules(config: &Config, target: &CacheTarget) -> TargetRules {
+    TargetRules {
+        id: target.id.clone(),
+        name: target.name.clone(),
+        category: target.category,
+        paths: target.paths.clone(),
+        strategy: describe_strategy(target.strategy),
+        format: match target.format {
+            CacheFormat::Files => "files",
+            CacheFormat::Chromium => "chromium",
+        },
+        report_only: target.report_only,
+        warning: target.warning,
+        skipped_while_running: target.owner.map(|owner| owner.name),
+        scan_limits: target.scan_limits,
+        exclusions: targets::exclusion_patterns(config, &target.id).cloned().collect(),
+        min_age_days: targets::min_age_days(config, &target.id),
+        schedule: schedule::frequency(config, target),
+        label: target.label.clone(),
+    }
+}
+
+pub fn build(config: &Config) -> EffectiveRules {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    EffectiveRules {
+        generated_at: clean_history::utc_timestamp(now),
+        version: env!("CARGO_PKG_VERSION"),
+        platform: std::env::consts::OS,
+        deletion: config.deletion,
+        targets: targets::builtin_targets(config)
+            .iter()
+            .map(|target| target_rules(config, target))
+            .collect(),
+    }
+}
+
+pub fn to_json(config: &Config) -> Result<String, String> {
+    serde_json::to_string_pretty(&build(config)).map_err(|e| format!("Failed to write the rules: {}", e))
+}
+
+pub fn export(config: &Config, path: &Path) -> Result<(), String> {
+    fs::write(path, to_json(config)?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
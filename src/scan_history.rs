@@ -0,0 +1,147 @@
+use crate::config_file;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Oldest scans are dropped beyond this
+const MAX_SCANS: usize = 20;
+
+// A change is highlighted when it is at least this large outright, or at
+// least SIGNIFICANT_PERCENT of the earlier size and MIN_SIGNIFICANT_BYTES
+const SIGNIFICANT_BYTES: u64 = 1024 * 1024 * 1024;
+const MIN_SIGNIFICANT_BYTES: u64 = 50 * 1024 * 1024;
+const SIGNIFICANT_PERCENT: u64 = 25;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct TargetCounts {
+    pub size_bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScanRecord {
+    scanned_at: u64,
+    names: BTreeMap<String, String>,
+    targets: BTreeMap<String, TargetCounts>,
+}
+
+#[derive(Serialize)]
+pub struct TargetChange {
+    id: String,
+    name: String,
+    // None for targets that did not exist at the earlier scan, and the
+    // other way round
+    before: Option<TargetCounts>,
+    now: Option<TargetCounts>,
+    significant: bool,
+}
+
+#[derive(Serialize)]
+pub struct ScanChanges {
+    previous_at: u64,
+    scanned_at: u64,
+    // Largest change in size first; unchanged targets are left out
+    targets: Vec<TargetChange>,
+}
+
+fn history_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("scan-history.json"))
+}
+
+fn read_all() -> Vec<ScanRecord> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(records: &[ScanRecord]) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(records) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Append a finished scan; `targets` holds (id, name, counts) for each target
+pub fn record(targets: impl IntoIterator<Item = (String, String, TargetCounts)>) {
+    let mut record = ScanRecord {
+        scanned_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        names: BTreeMap::new(),
+        targets: BTreeMap::new(),
+    };
+    for (id, name, counts) in targets {
+        record.names.insert(id.clone(), name);
+        record.targets.insert(id, counts);
+    }
+
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut records = read_all();
+    records.push(record);
+    let excess = records.len().saturating_sub(MAX_SCANS);
+    records.drain(..excess);
+    write_all(&records);
+}
+
+fn is_significant(before: u64, now: u64) -> bool {
+    let change = before.abs_diff(now);
+    change >= SIGNIFICANT_BYTES
+        || (change >= MIN_SIGNIFICANT_BYTES && change * 100 >= before * SIGNIFICANT_PERCENT)
+}
+
+// How each target changed between the two most recent scans
+pub fn changes() -> Option<ScanChanges> {
+    let records = {
+        let _guard = FILE_LOCK.lock().unwrap();
+        read_all()
+    };
+    let [.., previous, latest] = records.as_slice() else {
+        return None;
+    };
+
+    let mut ids: Vec<&String> = previous.targets.keys().chain(latest.targets.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut targets: Vec<TargetChange> = ids
+        .into_iter()
+        .filter_map(|id| {
+            let before = previous.targets.get(id).copied();
+            let now = latest.targets.get(id).copied();
+            let size_before = before.map_or(0, |counts| counts.size_bytes);
+            let size_now = now.map_or(0, |counts| counts.size_bytes);
+            if before.is_some() && now.is_some() && size_before == size_now {
+                return None;
+            }
+            let name = latest.names.get(id).or_else(|| previous.names.get(id))?;
+            Some(TargetChange {
+                id: id.clone(),
+                name: name.clone(),
+                before,
+                now,
+                significant: is_significant(size_before, size_now),
+            })
+        })
+        .collect();
+    targets.sort_by_key(|change| {
+        let size = |counts: Option<TargetCounts>| counts.map_or(0, |counts| counts.size_bytes);
+        std::cmp::Reverse(size(change.before).abs_diff(size(change.now)))
+    });
+
+    Some(ScanChanges {
+        previous_at: previous.scanned_at,
+        scanned_at: latest.scanned_at,
+        targets,
+    })
+}
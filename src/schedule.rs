@@ -0,0 +1,244 @@
+use crate::clean_budget;
+use crate::config_file;
+use crate::targets::{self, CacheTarget, ALL_TARGETS};
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+// How often a target is cleaned in the background
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Never,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    fn interval_secs(self) -> Option<u64> {
+        match self {
+            Frequency::Never => None,
+            Frequency::Daily => Some(DAY_SECS),
+            Frequency::Weekly => Some(7 * DAY_SECS),
+            Frequency::Monthly => Some(30 * DAY_SECS),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("schedule.json"))
+}
+
+// When each target was last cleaned by the scheduler, keyed by target id
+pub fn last_runs() -> BTreeMap<String, u64> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// A target's own schedule, else the one for every target. Report-only
+// targets never run, and those that are off by default only on their own.
+pub fn frequency(config: &Config, target: &CacheTarget) -> Frequency {
+    if target.report_only {
+        return Frequency::Never;
+    }
+    if let Some(&frequency) = config.schedules.get(&target.id) {
+        return frequency;
+    }
+    match config.schedules.get(ALL_TARGETS) {
+        Some(&frequency) if target.warning.is_none() => frequency,
+        _ => Frequency::Never,
+    }
+}
+
+// Seconds since the Unix epoch; targets that never ran are due right away
+pub fn next_run(config: &Config, target: &CacheTarget, last_runs: &BTreeMap<String, u64>) -> Option<u64> {
+    let interval = frequency(config, target).interval_secs()?;
+    Some(last_runs.get(&target.id).map_or(0, |last| last.saturating_add(interval)))
+}
+
+// Targets whose next run came while the app was not running, for a check
+// at startup. Targets that never ran are not missed, only due.
+pub fn missed(config: &Config) -> Vec<CacheTarget> {
+    let last_runs = last_runs();
+    let now = now();
+    targets::builtin_targets(config)
+        .into_iter()
+        .filter(|target| next_run(config, target, &last_runs).is_some_and(|next| next > 0 && next <= now))
+        .collect()
+}
+
+// Targets whose next run has come, and scheduled ones the last automatic
+// clean ran out of time for
+pub fn due(config: &Config) -> Vec<CacheTarget> {
+    let last_runs = last_runs();
+    let now = now();
+    let carried_over = clean_budget::carried_over();
+    targets::builtin_targets(config)
+        .into_iter()
+        .filter(|target| {
+            next_run(config, target, &last_runs).is_some_and(|next| next <= now || carried_over.contains(&target.id))
+        })
+        .collect()
+}
+
+// The next scheduled clean, with the targets it cleans: those due within a
+// minute of the first, as the scheduler looks once a minute. Runs that are
+// already due are at `now`.
+pub struct Upcoming {
+    pub at: u64,
+    pub targets: Vec<CacheTarget>,
+}
+
+pub fn upcoming(config: &Config) -> Option<Upcoming> {
+    if config.schedules.is_empty() {
+        return None;
+    }
+    let last_runs = last_runs();
+    let now = now();
+    let runs: Vec<(u64, CacheTarget)> = targets::builtin_targets(config)
+        .into_iter()
+        .filter_map(|target| next_run(config, &target, &last_runs).map(|next| (next.max(now), target)))
+        .collect();
+    let at = runs.iter().map(|(next, _)| *next).min()?;
+    Some(Upcoming {
+        at,
+        targets: runs
+            .into_iter()
+            .filter(|(next, _)| *next <= at + 60)
+            .map(|(_, target)| target)
+            .collect(),
+    })
+}
+
+// Count the next run of each target as done without cleaning, so the one
+// after it comes next
+pub fn skip(config: &Config, skipped: &[CacheTarget]) {
+    let mut runs = last_runs();
+    let now = now();
+    for target in skipped {
+        if let Some(next) = next_run(config, target, &runs) {
+            runs.insert(target.id.clone(), next.max(now));
+        }
+    }
+    write_runs(&runs);
+}
+
+// Set by the tray's Run now; the scheduler cleans the next run's targets
+// on waking instead of waiting for them to come due
+static RUN_NOW: Mutex<bool> = Mutex::new(false);
+static WAKE: Condvar = Condvar::new();
+
+pub fn request_run_now() {
+    *RUN_NOW.lock().unwrap() = true;
+    WAKE.notify_all();
+}
+
+// Sleep up to `timeout`; true when Run now woke the scheduler
+pub fn wait(timeout: Duration) -> bool {
+    let requested = RUN_NOW.lock().unwrap();
+    let (mut requested, _) = WAKE.wait_timeout_while(requested, timeout, |requested| !*requested).unwrap();
+    std::mem::take(&mut *requested)
+}
+
+pub fn record_run(cleaned: &[CacheTarget]) {
+    let mut runs = last_runs();
+    let now = now();
+    for target in cleaned {
+        runs.insert(target.id.clone(), now);
+    }
+    write_runs(&runs);
+}
+
+fn write_runs(runs: &BTreeMap<String, u64>) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(runs) {
+        let _ = fs::write(path, text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::Exclusions;
+
+    fn target() -> CacheTarget {
+        targets::trial_target("/cache", PathBuf::from("/cache"), Exclusions::default())
+    }
+
+    fn config_with(id: &str, frequency: Frequency) -> Config {
+        let mut config = Config::default();
+        config.schedules.insert(id.to_string(), frequency);
+        config
+    }
+
+    #[test]
+    fn targets_that_never_ran_are_due_at_once() {
+        let config = config_with(ALL_TARGETS, Frequency::Daily);
+        assert_eq!(next_run(&config, &target(), &BTreeMap::new()), Some(0));
+    }
+
+    #[test]
+    fn next_run_is_one_interval_after_the_last() {
+        let config = config_with(ALL_TARGETS, Frequency::Weekly);
+        let target = target();
+        let last_runs = BTreeMap::from([(target.id.clone(), 1_000)]);
+        assert_eq!(next_run(&config, &target, &last_runs), Some(1_000 + 7 * DAY_SECS));
+    }
+
+    #[test]
+    fn a_last_run_far_in_the_future_does_not_overflow() {
+        let config = config_with(ALL_TARGETS, Frequency::Monthly);
+        let target = target();
+        let last_runs = BTreeMap::from([(target.id.clone(), u64::MAX - 1)]);
+        assert_eq!(next_run(&config, &target, &last_runs), Some(u64::MAX));
+    }
+
+    #[test]
+    fn a_target_schedule_overrides_the_one_for_every_target() {
+        let target = target();
+        let mut config = config_with(ALL_TARGETS, Frequency::Daily);
+        config.schedules.insert(target.id.clone(), Frequency::Monthly);
+        let last_runs = BTreeMap::from([(target.id.clone(), 0)]);
+        assert_eq!(next_run(&config, &target, &last_runs), Some(30 * DAY_SECS));
+
+        config.schedules.insert(target.id.clone(), Frequency::Never);
+        assert_eq!(next_run(&config, &target, &last_runs), None);
+    }
+
+    #[test]
+    fn unscheduled_report_only_and_warned_targets_do_not_run() {
+        assert_eq!(next_run(&Config::default(), &target(), &BTreeMap::new()), None);
+
+        let config = config_with(ALL_TARGETS, Frequency::Daily);
+        let mut report_only = target();
+        report_only.report_only = true;
+        assert_eq!(next_run(&config, &report_only, &BTreeMap::new()), None);
+
+        // Targets with a warning only run on a schedule of their own
+        let mut warned = target();
+        warned.warning = Some("Costly to refill");
+        assert_eq!(next_run(&config, &warned, &BTreeMap::new()), None);
+        let config = config_with(&warned.id, Frequency::Daily);
+        assert_eq!(next_run(&config, &warned, &BTreeMap::new()), Some(0));
+    }
+}
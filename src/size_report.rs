@@ -0,0 +1,224 @@
+use crate::cleaner;
+use crate::targets::CacheTarget;
+use crate::temp_attribution::{self, AppUsage};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Files listed among a target's largest
+const TOP_FILES: usize = 25;
+// Subfolders listed, the largest first
+const SUBFOLDERS: usize = 50;
+
+const DAY: u64 = 24 * 60 * 60;
+
+// Upper bounds of the age buckets, by time since a file was last used
+const AGE_BUCKETS: [(&str, u64); 5] = [
+    ("Today", DAY),
+    ("This week", 7 * DAY),
+    ("This month", 30 * DAY),
+    ("Last 6 months", 182 * DAY),
+    ("This year", 365 * DAY),
+];
+
+#[derive(Serialize, Default)]
+pub struct FolderSize {
+    pub path: String,
+    pub size_bytes: u64,
+    pub file_count: u64,
+    // The files directly in `path`, not counting its subfolders
+    pub loose_files: bool,
+}
+
+#[derive(Serialize)]
+pub struct FileSize {
+    pub path: String,
+    pub size_bytes: u64,
+    // Seconds since the Unix epoch
+    pub last_used: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct AgeBucket {
+    pub label: &'static str,
+    pub size_bytes: u64,
+    pub file_count: u64,
+}
+
+// A closer look at one target: where its bytes are and how old they are
+#[derive(Serialize, Default)]
+pub struct SizeReport {
+    // The largest folders directly inside the target's paths
+    pub subfolders: Vec<FolderSize>,
+    pub top_files: Vec<FileSize>,
+    // From most to least recently used, the last bucket holding everything
+    // older than a year
+    pub ages: Vec<AgeBucket>,
+    // Only for the temp folders: their usage by the program that made it
+    pub apps: Vec<AppUsage>,
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string().trim_start_matches(r"\\?\").to_string()
+}
+
+impl SizeReport {
+    fn new() -> Self {
+        let mut ages: Vec<_> = AGE_BUCKETS
+            .iter()
+            .map(|&(label, _)| AgeBucket { label, ..Default::default() })
+            .collect();
+        ages.push(AgeBucket {
+            label: "Older",
+            ..Default::default()
+        });
+        Self {
+            ages,
+            ..Default::default()
+        }
+    }
+
+    fn add_file(&mut self, path: &Path, meta: &fs::Metadata, now: SystemTime) {
+        let last_used = cleaner::last_used(meta);
+        let age = now.duration_since(last_used).unwrap_or(Duration::ZERO).as_secs();
+        let bucket = AGE_BUCKETS
+            .iter()
+            .position(|&(_, limit)| age < limit)
+            .unwrap_or(AGE_BUCKETS.len());
+        self.ages[bucket].size_bytes += meta.len();
+        self.ages[bucket].file_count += 1;
+
+        // Only files that may make the list are kept; it is trimmed when it
+        // has grown to twice its length
+        self.top_files.push(FileSize {
+            path: display(path),
+            size_bytes: meta.len(),
+            last_used: last_used.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        });
+        if self.top_files.len() >= 2 * TOP_FILES {
+            self.trim_top_files();
+        }
+    }
+
+    fn trim_top_files(&mut self) {
+        self.top_files.sort_by_key(|file| Reverse(file.size_bytes));
+        self.top_files.truncate(TOP_FILES);
+    }
+
+    fn merge(&mut self, other: SizeReport) {
+        self.subfolders.extend(other.subfolders);
+        self.top_files.extend(other.top_files);
+        self.trim_top_files();
+        for (bucket, other) in self.ages.iter_mut().zip(other.ages) {
+            bucket.size_bytes += other.size_bytes;
+            bucket.file_count += other.file_count;
+        }
+    }
+}
+
+// Add every file below `path` to `report`, returning the folder's total.
+// Symlinks and junctions are not followed.
+fn walk(path: &Path, report: &mut SizeReport, now: SystemTime) -> FolderSize {
+    let mut folder = FolderSize {
+        path: display(path),
+        ..Default::default()
+    };
+    let Ok(entries) = fs::read_dir(path) else {
+        return folder;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if meta.is_dir() {
+            let subfolder = walk(&entry.path(), report, now);
+            folder.size_bytes += subfolder.size_bytes;
+            folder.file_count += subfolder.file_count;
+        } else if meta.is_file() {
+            report.add_file(&entry.path(), &meta, now);
+            folder.size_bytes += meta.len();
+            folder.file_count += 1;
+        }
+    }
+    folder
+}
+
+// Build the report for `target`, the folders directly inside its paths
+// spread across threads
+pub fn build(target: &CacheTarget) -> SizeReport {
+    let now = SystemTime::now();
+    let mut report = SizeReport::new();
+    let mut subdirs = Vec::new();
+    for path in &target.paths {
+        let path = cleaner::verbatim(path);
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_file() {
+            report.add_file(&path, &meta, now);
+            report.subfolders.push(FolderSize {
+                path: display(&path),
+                size_bytes: meta.len(),
+                file_count: 1,
+                loose_files: true,
+            });
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&path) else {
+            continue;
+        };
+        // Files directly in the folder are counted together
+        let mut loose = FolderSize {
+            path: display(&path),
+            loose_files: true,
+            ..Default::default()
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if meta.is_dir() {
+                subdirs.push(entry.path());
+            } else if meta.is_file() {
+                report.add_file(&entry.path(), &meta, now);
+                loose.size_bytes += meta.len();
+                loose.file_count += 1;
+            }
+        }
+        if loose.file_count > 0 {
+            report.subfolders.push(loose);
+        }
+    }
+
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk_size = subdirs.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = subdirs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut subtree = SizeReport::new();
+                    for dir in chunk {
+                        let folder = walk(dir, &mut subtree, now);
+                        subtree.subfolders.push(folder);
+                    }
+                    subtree
+                })
+            })
+            .collect();
+        for worker in workers {
+            if let Ok(subtree) = worker.join() {
+                report.merge(subtree);
+            }
+        }
+    });
+    report.trim_top_files();
+    report.subfolders.sort_by_key(|folder| Reverse(folder.size_bytes));
+    report.subfolders.truncate(SUBFOLDERS);
+    if target.id == "temp-files" {
+        report.apps = temp_attribution::breakdown(target);
+    }
+    report
+}
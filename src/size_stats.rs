@@ -0,0 +1,150 @@
+use crate::clean_history;
+use crate::config_file;
+use crate::data_retention::{self, Policy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Cache sizes over months, for the growth chart. Unlike the scan history,
+// which only keeps the last few scans to compare, scans within the same hour
+// share one snapshot so the file stays small.
+
+const SNAPSHOT_SECS: u64 = 60 * 60;
+// Snapshots older than this are dropped
+const MAX_AGE_SECS: u64 = 180 * 24 * 60 * 60;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+// Time of the newest snapshot, kept so checking whether one is due does not
+// read the file; 0 until known
+static LAST_AT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    // Seconds since the Unix epoch
+    pub at: u64,
+    pub total_bytes: u64,
+    // Free space on the system drive, to compare with the low-disk threshold
+    pub system_free_bytes: Option<u64>,
+    // Size of each target by id
+    pub targets: BTreeMap<String, u64>,
+}
+
+// What a clean reclaimed, for marking it on the chart
+#[derive(Serialize)]
+pub struct CleanMark {
+    pub at: u64,
+    pub freed_bytes: u64,
+    pub source: String,
+    // Bytes freed in each target by id
+    pub targets: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+pub struct SizeStats {
+    // Oldest first
+    pub snapshots: Vec<Snapshot>,
+    // Cleans since the oldest snapshot, oldest first
+    pub cleans: Vec<CleanMark>,
+}
+
+fn stats_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("size-stats.json"))
+}
+
+fn read_all() -> Vec<Snapshot> {
+    stats_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(snapshots: &[Snapshot]) {
+    let Some(path) = stats_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(snapshots) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Add a finished scan, given as (id, size) for each target
+pub fn record(targets: impl IntoIterator<Item = (String, u64)>, system_free_bytes: Option<u64>) {
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let targets: BTreeMap<String, u64> = targets.into_iter().collect();
+    let snapshot = Snapshot {
+        at,
+        total_bytes: targets.values().sum(),
+        system_free_bytes,
+        targets,
+    };
+
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut snapshots = read_all();
+    if snapshots.last().is_some_and(|last| last.at / SNAPSHOT_SECS == at / SNAPSHOT_SECS) {
+        snapshots.pop();
+    }
+    snapshots.push(snapshot);
+    LAST_AT.store(at, Ordering::Relaxed);
+    snapshots.retain(|snapshot| at.saturating_sub(snapshot.at) <= MAX_AGE_SECS);
+    write_all(&snapshots);
+}
+
+// Drop the snapshots the retention settings no longer keep
+pub fn compact(policy: &Policy) {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut snapshots = read_all();
+    if data_retention::fit(&mut snapshots, policy, |snapshot| snapshot.at) {
+        write_all(&snapshots);
+    }
+}
+
+// Whether `interval_hours` have gone by since the last snapshot; never
+// when 0
+pub fn snapshot_due(interval_hours: u64) -> bool {
+    if interval_hours == 0 {
+        return false;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut last = LAST_AT.load(Ordering::Relaxed);
+    if last == 0 {
+        let _guard = FILE_LOCK.lock().unwrap();
+        last = read_all().last().map_or(0, |snapshot| snapshot.at);
+        LAST_AT.store(last, Ordering::Relaxed);
+    }
+    now.saturating_sub(last) >= interval_hours.saturating_mul(60 * 60)
+}
+
+pub fn load() -> SizeStats {
+    let snapshots = {
+        let _guard = FILE_LOCK.lock().unwrap();
+        read_all()
+    };
+    let since = snapshots.first().map_or(0, |snapshot| snapshot.at);
+    let mut cleans: Vec<_> = clean_history::recent(usize::MAX)
+        .into_iter()
+        .filter(|run| run.started_at >= since && run.total.freed_bytes > 0)
+        .map(|run| CleanMark {
+            at: run.started_at,
+            freed_bytes: run.total.freed_bytes,
+            source: run.source,
+            targets: run
+                .targets
+                .into_iter()
+                .map(|target| (target.id, target.stats.freed_bytes))
+                .collect(),
+        })
+        .collect();
+    cleans.reverse();
+    SizeStats { snapshots, cleans }
+}
@@ -0,0 +1,251 @@
+use crate::cleaner;
+use crate::clean_history::utc_timestamp;
+use crate::config_file;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The quarantine folder: cleaned files moved under the app's data folder, one
+// folder per day, instead of being deleted. Any of them can be put back until
+// they are older than the retention period, when they are deleted for good.
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// Numbers files so ones of the same name from different folders do not collide
+static NEXT_NUMBER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StagedFile {
+    // Where the file is kept, relative to the quarantine folder
+    pub id: String,
+    pub original_path: PathBuf,
+    pub size_bytes: u64,
+    pub staged_at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+pub fn staging_dir() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("staging"))
+}
+
+fn index_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("staging.json"))
+}
+
+fn read_all() -> Vec<StagedFile> {
+    index_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(files: &[StagedFile]) {
+    let Some(path) = index_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(files) {
+        let _ = fs::write(path, text);
+    }
+}
+
+// Rename within a volume; across volumes copy, then delete the original.
+// The copy gets the original's times and permissions, so a file put back
+// looks as it did and age rules treat it the same.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    // Read before copying, which counts as an access
+    let meta = fs::metadata(from)?;
+    fs::copy(from, to)?;
+    let copied = copy_metadata(&meta, to).and_then(|()| fs::remove_file(from));
+    copied.inspect_err(|_| {
+        let _ = fs::remove_file(to);
+    })
+}
+
+fn copy_metadata(meta: &fs::Metadata, to: &Path) -> io::Result<()> {
+    let mut times = fs::FileTimes::new();
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = meta.modified() {
+        times = times.set_modified(modified);
+    }
+    #[cfg(target_os = "windows")]
+    if let Ok(created) = meta.created() {
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(created);
+    }
+    // Opened without write access, which a read-only copy would refuse:
+    // setting times only takes FILE_WRITE_ATTRIBUTES on Windows and owning
+    // the file elsewhere
+    let mut options = fs::OpenOptions::new();
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x100;
+        options.access_mode(FILE_WRITE_ATTRIBUTES);
+    }
+    #[cfg(not(target_os = "windows"))]
+    options.read(true);
+    options.open(to)?.set_times(times)?;
+    fs::set_permissions(to, meta.permissions())
+}
+
+// Move a file being cleaned into today's folder. The clean keeps what this
+// returns and lists it once it finishes.
+pub fn stage(path: &Path) -> io::Result<StagedFile> {
+    let dir = staging_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No app data folder"))?;
+    let staged_at = now();
+    let day = utc_timestamp(staged_at)[..10].to_string();
+    fs::create_dir_all(dir.join(&day))?;
+
+    let name = path.file_name().map_or_else(|| "file".into(), |name| name.to_string_lossy());
+    let id = loop {
+        let id = format!("{}/{}-{}", day, NEXT_NUMBER.fetch_add(1, Ordering::Relaxed), name);
+        if !dir.join(&id).exists() {
+            break id;
+        }
+    };
+    let size_bytes = fs::symlink_metadata(path)?.len();
+    move_file(path, &dir.join(&id))?;
+
+    let display = path.display().to_string();
+    Ok(StagedFile {
+        id,
+        original_path: PathBuf::from(display.trim_start_matches(r"\\?\")),
+        size_bytes,
+        staged_at,
+    })
+}
+
+// Add the files one clean moved to the index, returning their ids
+pub fn record(staged: Vec<StagedFile>) -> Vec<String> {
+    if staged.is_empty() {
+        return Vec::new();
+    }
+    let ids = staged.iter().map(|file| file.id.clone()).collect();
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut files = read_all();
+    files.extend(staged);
+    write_all(&files);
+    ids
+}
+
+// Every file in the quarantine folder, newest first
+pub fn list() -> Vec<StagedFile> {
+    let mut files = {
+        let _guard = FILE_LOCK.lock().unwrap();
+        read_all()
+    };
+    files.reverse();
+    files
+}
+
+// Move a file back where it was cleaned from, returning that path
+pub fn restore(id: &str) -> Result<PathBuf, String> {
+    let dir = staging_dir().ok_or("Could not find the quarantine folder")?;
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut files = read_all();
+    let index = files
+        .iter()
+        .position(|file| file.id == id)
+        .ok_or("The file is no longer in the quarantine folder")?;
+    let stored = dir.join(&files[index].id);
+    if !stored.is_file() {
+        files.remove(index);
+        write_all(&files);
+        return Err("The file is no longer in the quarantine folder".to_string());
+    }
+
+    let original_path = files[index].original_path.clone();
+    let original = cleaner::verbatim(&original_path);
+    if original.exists() {
+        return Err(format!("{} exists again; move it away first", original_path.display()));
+    }
+    // Directories emptied by the clean are gone and must exist again first
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    move_file(&stored, &original).map_err(|e| format!("Failed to restore {}: {}", original_path.display(), e))?;
+    files.remove(index);
+    write_all(&files);
+    Ok(original_path)
+}
+
+// Delete for good the files moved more than `retention_days` ago, and day
+// folders from before then that the index lost track of. Returns the bytes
+// freed.
+pub fn purge_expired(retention_days: u64) -> u64 {
+    let Some(dir) = staging_dir() else {
+        return 0;
+    };
+    let cutoff = now().saturating_sub(retention_days.saturating_mul(24 * 60 * 60));
+    let _guard = FILE_LOCK.lock().unwrap();
+    let (expired, kept): (Vec<_>, Vec<_>) = read_all().into_iter().partition(|file| file.staged_at <= cutoff);
+    let mut freed = 0;
+    for file in &expired {
+        if fs::remove_file(dir.join(&file.id)).is_ok() {
+            freed += file.size_bytes;
+        }
+    }
+    if !expired.is_empty() {
+        write_all(&kept);
+    }
+
+    let first_kept_day = utc_timestamp(cutoff)[..10].to_string();
+    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+        if entry.file_name().to_string_lossy().as_ref() < first_kept_day.as_str() {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+    freed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn copies_keep_the_times_and_permissions_of_the_original() {
+        let dir = std::env::temp_dir().join(format!("cache-manager-test-{}-staging", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (from, to) = (dir.join("from"), dir.join("to"));
+        fs::write(&from, "x").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
+        fs::File::options().write(true).open(&from).unwrap().set_modified(modified).unwrap();
+        let mut read_only = fs::metadata(&from).unwrap().permissions();
+        read_only.set_readonly(true);
+        fs::set_permissions(&from, read_only).unwrap();
+
+        let meta = fs::metadata(&from).unwrap();
+        fs::copy(&from, &to).unwrap();
+        copy_metadata(&meta, &to).unwrap();
+        let copied = fs::metadata(&to).unwrap();
+        assert_eq!(copied.modified().unwrap(), modified);
+        assert!(copied.permissions().readonly());
+
+        for path in [&from, &to] {
+            let mut writable = fs::metadata(path).unwrap().permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            writable.set_readonly(false);
+            fs::set_permissions(path, writable).unwrap();
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::cleaner;
+use crate::targets::{self, CacheTarget, ELECTRON_CACHE_DIRS};
+use crate::Config;
+use serde::Serialize;
+use std::path::PathBuf;
+
+// Cache folders of Electron and WebView2 apps no target knows about, found in
+// the apps' data folders and offered to be added as custom folders. Ones the
+// user always ignores are kept in the config.
+
+// Smaller caches are not worth a suggestion
+const MIN_SIZE_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Serialize)]
+pub struct Suggestion {
+    // As added to the custom folders
+    pub path: String,
+    // The app's data folder name
+    pub app: String,
+    pub size_bytes: u64,
+}
+
+// Folders apps keep their data in, one app per child
+fn app_data_roots() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return [targets::app_config_dir(), targets::local_cache_dir()].into_iter().flatten().collect();
+
+    #[cfg(not(target_os = "windows"))]
+    return targets::app_config_dir().into_iter().collect();
+}
+
+// Biggest first
+pub fn find(config: &Config, known: &[CacheTarget]) -> Vec<Suggestion> {
+    let covered = |dir: &PathBuf| {
+        known
+            .iter()
+            .flat_map(|target| &target.paths)
+            .any(|path| path.starts_with(dir) || dir.starts_with(path))
+    };
+    let mut suggestions = Vec::new();
+    for root in app_data_roots() {
+        for app_dir in std::fs::read_dir(&root).into_iter().flatten().flatten().map(|entry| entry.path()) {
+            for cache_dir in ELECTRON_CACHE_DIRS.iter().map(|name| app_dir.join(name)) {
+                let path = cache_dir.display().to_string();
+                if !cache_dir.is_dir()
+                    || covered(&cache_dir)
+                    || config.custom_cache_dirs.contains(&path)
+                    || config.ignored_suggestions.contains(&path)
+                    || targets::check_custom_dir(&path).is_err()
+                {
+                    continue;
+                }
+                let size_bytes = cleaner::scan_path(&cache_dir).size_bytes;
+                if size_bytes >= MIN_SIZE_BYTES {
+                    suggestions.push(Suggestion {
+                        path,
+                        app: app_dir.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                        size_bytes,
+                    });
+                }
+            }
+        }
+    }
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.size_bytes));
+    suggestions
+}
@@ -0,0 +1,68 @@
+use crate::cleaner;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Every user on a machine may run an instance of the app. Targets outside the
+// user's own folders (the Windows temp folder, Prefetch, /tmp, system package
+// caches) are shared between them, so those are cleaned under a lock on a
+// file all users can reach, one instance at a time.
+
+// Readable by every user, writable by whoever creates it first
+pub fn lock_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return std::env::var_os("ProgramData")
+        .map(|p| PathBuf::from(p).join("MemoryCacheManager").join("system-clean.lock"));
+
+    #[cfg(not(target_os = "windows"))]
+    return Some(PathBuf::from("/tmp/memory-cache-manager-system-clean.lock"));
+}
+
+fn open_lock_file() -> Option<File> {
+    let path = lock_path()?;
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    // Locks work on read-only handles, which is all a file another user
+    // created may allow
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .or_else(|_| File::open(&path))
+        .ok()
+}
+
+// Released when dropped
+pub struct SystemLock(Option<File>);
+
+// Wait until no other instance is cleaning shared targets, reporting it as
+// the clean's progress. None when the clean is cancelled meanwhile. Without
+// a usable lock file the clean goes ahead unguarded rather than not at all.
+pub fn acquire() -> Option<SystemLock> {
+    let Some(file) = open_lock_file() else {
+        return Some(SystemLock(None));
+    };
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Some(SystemLock(Some(file))),
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(_)) => return Some(SystemLock(None)),
+        }
+        if cleaner::is_cancelled() {
+            return None;
+        }
+        cleaner::record_waiting("Waiting for another user's clean of shared folders…");
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+impl Drop for SystemLock {
+    fn drop(&mut self) {
+        if let Some(file) = &self.0 {
+            let _ = file.unlock();
+        }
+    }
+}
@@ -0,0 +1,2051 @@
+use crate::clean_budget;
+use crate::clean_history::Recorder;
+use crate::cleaner::{self, Candidate, CleanStats, DirStats, Exclusions, ScanLimits, Strategy};
+use crate::cleaners;
+use crate::cloud_sync;
+use crate::confirmation;
+use crate::cooldown;
+use crate::detect::{self, Condition};
+use crate::file_locks::{self, LockingApp};
+use crate::network_shares;
+use crate::remote_rules;
+use crate::staging;
+use crate::system_lock;
+use crate::wsl;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Key in the config's exclusions that applies to every target
+pub const ALL_TARGETS: &str = "*";
+
+// Log and crash-dump targets keep their most recent files for debugging
+const KEEP_LOG_FILES: usize = 10;
+
+// Not every platform has targets in every category
+#[allow(dead_code)]
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    System,
+    Browsers,
+    Games,
+    Developer,
+    Media,
+    Applications,
+    Custom,
+}
+
+// How a target's files depend on each other when cleaning
+#[derive(Clone, Copy, PartialEq)]
+pub enum CacheFormat {
+    // Independent files, any of which may be deleted
+    Files,
+    // A Chromium disk cache whose index must match its entries
+    Chromium,
+}
+
+// What a custom folder is for and whose it is, e.g. "CI workspace, ok to
+// wipe nightly" owned by "build team" on a shared build machine
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TargetLabel {
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub owner: String,
+}
+
+impl TargetLabel {
+    pub fn is_empty(&self) -> bool {
+        self.note.trim().is_empty() && self.owner.trim().is_empty()
+    }
+}
+
+// A program that keeps the target's files open while it runs; deleting them
+// under it fails or corrupts its profile
+#[derive(Clone, Copy)]
+pub struct OwnerApp {
+    pub name: &'static str,
+    // Process name without ".exe"
+    pub process: &'static str,
+}
+
+#[derive(Clone)]
+pub struct CacheTarget {
+    pub id: String,
+    pub name: String,
+    pub category: Category,
+    pub paths: Vec<PathBuf>,
+    pub strategy: Strategy,
+    pub scan_limits: ScanLimits,
+    // All must hold for the target to be offered
+    pub conditions: Vec<Condition>,
+    // Volume of the first path; targets on one volume are cleaned one at a time
+    pub volume: Option<String>,
+    // Shown with its size but never cleaned by the app
+    pub report_only: bool,
+    pub exclusions: Exclusions,
+    pub format: CacheFormat,
+    // Why cleaning the target has a cost. Such targets are off by default:
+    // they are only cleaned when picked on their own, never as part of a
+    // category or every-target selection.
+    pub warning: Option<&'static str>,
+    // Skipped by cleans while it runs
+    pub owner: Option<OwnerApp>,
+    // The release that added the target, for the notes shown after an
+    // update; None for targets of the first release
+    pub added_in: Option<&'static str>,
+    // The user's note and owner, for custom folders
+    pub label: Option<TargetLabel>,
+    // Exclusions and age policy the target's rule comes with, for folders
+    // of the remote rule file; kept along with the user's own
+    pub rule_exclusions: Vec<String>,
+    pub rule_min_age_days: Option<u64>,
+    // Name of the cleaner that found it, which also cleans it
+    pub cleaner: &'static str,
+}
+
+impl CacheTarget {
+    fn new(id: &str, name: &str, category: Category, paths: Vec<PathBuf>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            category,
+            paths,
+            strategy: Strategy::DeleteAll,
+            scan_limits: ScanLimits::default(),
+            conditions: Vec::new(),
+            volume: None,
+            report_only: false,
+            exclusions: Exclusions::default(),
+            format: CacheFormat::Files,
+            warning: None,
+            owner: None,
+            added_in: None,
+            label: None,
+            rule_exclusions: Vec::new(),
+            rule_min_age_days: None,
+            cleaner: "",
+        }
+    }
+
+    fn with_warning(mut self, warning: &'static str) -> Self {
+        self.warning = Some(warning);
+        self
+    }
+
+    fn owned_by(mut self, name: &'static str, process: &'static str) -> Self {
+        self.owner = Some(OwnerApp { name, process });
+        self
+    }
+
+    // Outside the user's own folders, where other users' instances clean too
+    pub fn is_shared(&self) -> bool {
+        let home = home_dir();
+        self.paths
+            .iter()
+            .any(|path| !home.as_ref().is_some_and(|home| path.starts_with(home)))
+    }
+
+    // The name of the target's owner when it is running; `processes` as for
+    // Condition::holds
+    pub fn running_owner(&self, processes: &OnceCell<Vec<String>>) -> Option<&'static str> {
+        self.owner
+            .filter(|owner| detect::is_running(owner.process, processes))
+            .map(|owner| owner.name)
+    }
+
+    fn with_format(mut self, format: CacheFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn with_scan_limits(mut self, scan_limits: ScanLimits) -> Self {
+        self.scan_limits = scan_limits;
+        self
+    }
+
+    fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    // Set on targets added after the first release, e.g. .added_in("1.1.0")
+    #[allow(dead_code)]
+    fn added_in(mut self, version: &'static str) -> Self {
+        self.added_in = Some(version);
+        self
+    }
+
+    fn report_only(mut self) -> Self {
+        self.report_only = true;
+        self
+    }
+
+    // Size every path of the target within its scan limits
+    pub fn scan(&self) -> DirStats {
+        let mut stats = DirStats::default();
+        for path in &self.paths {
+            stats.add(cleaner::scan_path_limited(path, self.scan_limits));
+        }
+        stats
+    }
+}
+
+pub fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).map(PathBuf::from)
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env_path("USERPROFILE");
+
+    #[cfg(not(target_os = "windows"))]
+    return env_path("HOME");
+}
+
+// Subdirectories of `dir` whose names start with `prefix` (e.g. one per installed version)
+fn subdirs_with_prefix(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn package_dir(family: &str) -> Option<PathBuf> {
+    env_path("LOCALAPPDATA").map(|p| p.join("Packages").join(family))
+}
+
+// Fixed drive roots (C:\, D:\, ...) that currently exist
+#[cfg(target_os = "windows")]
+pub fn drive_roots() -> Vec<PathBuf> {
+    (b'C'..=b'Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+        .filter(|root| root.exists())
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+pub fn games_targets(targets: &mut Vec<CacheTarget>) {
+    const XBOX_PACKAGES: [&str; 3] = [
+        "Microsoft.GamingApp_8wekyb3d8bbwe",
+        "Microsoft.XboxGamingOverlay_8wekyb3d8bbwe",
+        "Microsoft.XboxIdentityProvider_8wekyb3d8bbwe",
+    ];
+
+    let mut xbox_paths = Vec::new();
+    for family in XBOX_PACKAGES {
+        if let Some(dir) = package_dir(family) {
+            xbox_paths.push(dir.join("LocalCache"));
+            xbox_paths.push(dir.join("TempState"));
+            xbox_paths.push(dir.join("AC").join("INetCache"));
+        }
+    }
+    targets.push(CacheTarget::new(
+        "xbox-app",
+        "Xbox App Cache",
+        Category::Games,
+        xbox_paths,
+    ));
+
+    let mut gaming_services_paths = Vec::new();
+    if let Some(dir) = package_dir("Microsoft.GamingServices_8wekyb3d8bbwe") {
+        gaming_services_paths.push(dir.join("LocalCache"));
+        gaming_services_paths.push(dir.join("TempState"));
+    }
+    // Game Pass delivery temp lives under each drive's WindowsApps folder,
+    // which is usually ACL-protected; unreadable folders simply scan as empty
+    if let Some(program_files) = env_path("ProgramFiles") {
+        gaming_services_paths.push(program_files.join("WindowsApps").join("Temp"));
+    }
+    for root in drive_roots() {
+        gaming_services_paths.push(root.join("WindowsApps").join("Temp"));
+    }
+    targets.push(CacheTarget::new(
+        "gaming-services",
+        "Gaming Services Temp",
+        Category::Games,
+        gaming_services_paths,
+    ));
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn games_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// Chromium disk caches Electron and WebView2 apps keep in their data
+// folder, as a browser profile does
+pub const ELECTRON_CACHE_DIRS: [&str; 3] = ["Cache", "Code Cache", "GPUCache"];
+
+fn electron_cache_paths(root: &Path) -> Vec<PathBuf> {
+    ELECTRON_CACHE_DIRS.iter().map(|dir| root.join(dir)).collect()
+}
+
+// Chat and music apps; each is offered only where the app keeps its data
+pub fn app_targets(targets: &mut Vec<CacheTarget>) {
+    let config_dir = app_config_dir();
+    if let Some(dir) = &config_dir {
+        targets.push(
+            CacheTarget::new(
+                "discord-cache",
+                "Discord Cache",
+                Category::Applications,
+                electron_cache_paths(&dir.join("discord")),
+            )
+            .with_format(CacheFormat::Chromium)
+            .owned_by("Discord", "Discord"),
+        );
+    }
+
+    // Classic Teams; the new one is a Store app with a browser profile of
+    // its own
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let teams = config_dir.map(|dir| dir.join("Microsoft").join("Microsoft Teams"));
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    let teams = config_dir.map(|dir| dir.join("Microsoft").join("Teams"));
+    if let Some(teams) = teams {
+        targets.push(
+            CacheTarget::new("teams-cache", "Microsoft Teams Cache", Category::Applications, electron_cache_paths(&teams))
+                .with_format(CacheFormat::Chromium)
+                .owned_by("Microsoft Teams", "Teams"),
+        );
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(dir) = package_dir("MSTeams_8wekyb3d8bbwe") {
+        let profile = dir.join("LocalCache").join("Microsoft").join("MSTeams").join("EBWebView").join("Default");
+        targets.push(
+            CacheTarget::new(
+                "new-teams-cache",
+                "Microsoft Teams (new) Cache",
+                Category::Applications,
+                electron_cache_paths(&profile),
+            )
+            .with_format(CacheFormat::Chromium)
+            .owned_by("Microsoft Teams", "ms-teams"),
+        );
+    }
+
+    // Streamed songs share the folder with those downloaded for offline use
+    let mut spotify_paths = Vec::new();
+    #[cfg(target_os = "windows")]
+    {
+        spotify_paths.extend(local_cache_dir().map(|p| p.join("Spotify").join("Storage")));
+        spotify_paths.extend(
+            package_dir("SpotifyAB.SpotifyMusic_zpdnekdrzrea0").map(|p| p.join("LocalCache").join("Spotify").join("Data")),
+        );
+    }
+    #[cfg(target_os = "macos")]
+    spotify_paths.extend(local_cache_dir().map(|p| p.join("com.spotify.client").join("Data")));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    spotify_paths.extend(local_cache_dir().map(|p| p.join("spotify").join("Data")));
+    targets.push(
+        CacheTarget::new("spotify-cache", "Spotify Cache", Category::Media, spotify_paths)
+            .owned_by("Spotify", "Spotify")
+            .with_warning("Songs downloaded for offline listening are deleted too and have to be downloaded again"),
+    );
+}
+
+// The LocalCache folder of every Store app without a target of its own
+#[cfg(target_os = "windows")]
+pub fn store_app_targets(targets: &mut Vec<CacheTarget>) {
+    // Covered above, or holding more than a cache: the Python app installs
+    // pip packages into its LocalCache
+    const SKIPPED_PREFIXES: [&str; 5] = [
+        "Microsoft.GamingApp_",
+        "Microsoft.Xbox",
+        "Microsoft.GamingServices_",
+        "MSTeams_",
+        "PythonSoftwareFoundation.",
+    ];
+    let Some(packages) = env_path("LOCALAPPDATA").map(|p| p.join("Packages")) else {
+        return;
+    };
+    let paths = subdirs_with_prefix(&packages, "")
+        .into_iter()
+        .filter(|dir| {
+            let name = dir.file_name().unwrap_or_default().to_string_lossy();
+            !SKIPPED_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+        })
+        .map(|dir| dir.join("LocalCache"))
+        .filter(|dir| !targets.iter().flat_map(|target| &target.paths).any(|path| path.starts_with(dir)))
+        .collect();
+    targets.push(
+        CacheTarget::new("store-app-caches", "Store App Caches", Category::Applications, paths)
+            .with_warning("Some Store apps keep downloads or add-ons here, which they fetch again"),
+    );
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn store_app_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// Prefetch traces let Windows load programs faster; it rebuilds them as they
+// run, so only traces of programs unused for a month go
+#[cfg(target_os = "windows")]
+pub fn prefetch_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(windows_dir) = env_path("SystemRoot") else {
+        return;
+    };
+    targets.push(
+        CacheTarget::new(
+            "windows-prefetch",
+            "Windows Prefetch",
+            Category::System,
+            vec![windows_dir.join("Prefetch")],
+        )
+        .with_strategy(Strategy::DeleteOlderThan(30 * DAY))
+        .with_warning("Programs start slower until Windows rebuilds their prefetch traces; this does not free meaningful space"),
+    );
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn prefetch_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// Updates Windows Update has downloaded; those not installed yet are
+// downloaded again
+#[cfg(target_os = "windows")]
+pub fn windows_update_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(windows_dir) = env_path("SystemRoot") else {
+        return;
+    };
+    targets.push(
+        CacheTarget::new(
+            "windows-update-cache",
+            "Windows Update Downloads",
+            Category::System,
+            vec![windows_dir.join("SoftwareDistribution").join("Download")],
+        )
+        .with_warning("Updates waiting to be installed are downloaded again; do not clean while Windows is updating"),
+    );
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn windows_update_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// The disk image of each WSL distribution. Only compacting it, a maintenance
+// action, gives space back, so the size is just shown.
+pub fn wsl_targets(targets: &mut Vec<CacheTarget>) {
+    for distro in wsl::distros() {
+        targets.push(
+            CacheTarget::new(
+                &format!("wsl:{}", distro.name),
+                &format!("WSL {} Disk", distro.name),
+                Category::Developer,
+                vec![distro.vhdx],
+            )
+            .report_only(),
+        );
+    }
+}
+
+// Per-user application config root (%APPDATA%, ~/Library/Application Support, ~/.config)
+pub fn app_config_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env_path("APPDATA");
+
+    #[cfg(target_os = "macos")]
+    return home_dir().map(|p| p.join("Library/Application Support"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return env_path("XDG_CONFIG_HOME").or_else(|| home_dir().map(|p| p.join(".config")));
+}
+
+// Root of Plex Media Server's data directory
+fn plex_data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env_path("LOCALAPPDATA").map(|p| p.join("Plex Media Server"));
+
+    #[cfg(target_os = "macos")]
+    return home_dir().map(|p| p.join("Library/Application Support/Plex Media Server"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return Some(PathBuf::from(
+        "/var/lib/plexmediaserver/Library/Application Support/Plex Media Server",
+    ));
+}
+
+// Kodi's userdata and temp live side by side under one data directory
+fn kodi_data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env_path("APPDATA").map(|p| p.join("Kodi"));
+
+    #[cfg(target_os = "macos")]
+    return home_dir().map(|p| p.join("Library/Application Support/Kodi"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return home_dir().map(|p| p.join(".kodi"));
+}
+
+pub fn media_targets(targets: &mut Vec<CacheTarget>) {
+    if let Some(plex) = plex_data_dir() {
+        let cache = plex.join("Cache");
+        // Deleting segments mid-transcode breaks the stream being played
+        let target = CacheTarget::new(
+            "plex-transcode",
+            "Plex Transcoder Cache",
+            Category::Media,
+            vec![cache.join("Transcode"), cache.join("PhotoTranscoder")],
+        )
+        .when(Condition::ProcessNotRunning("Plex Transcoder"));
+        // Plex Media Server keeps its preferences under this key on Windows
+        #[cfg(target_os = "windows")]
+        let target = target.when(Condition::RegistryKey(
+            "HKCU\\Software\\Plex, Inc.\\Plex Media Server",
+        ));
+        targets.push(target);
+    }
+
+    if let Some(kodi) = kodi_data_dir() {
+        targets.push(CacheTarget::new(
+            "kodi-thumbnails",
+            "Kodi Thumbnails",
+            Category::Media,
+            vec![kodi.join("userdata").join("Thumbnails")],
+        ));
+        // Kodi holds its cache and temp files open while running
+        targets.push(
+            CacheTarget::new(
+                "kodi-cache",
+                "Kodi Cache",
+                Category::Media,
+                vec![kodi.join("cache"), kodi.join("temp")],
+            )
+            .when(Condition::ProcessNotRunning("kodi"))
+            .when(Condition::ProcessNotRunning("kodi.bin")),
+        );
+    }
+
+    if let Some(obs) = app_config_dir().map(|p| p.join("obs-studio")) {
+        targets.push(
+            CacheTarget::new(
+                "obs-logs",
+                "OBS Studio Logs & Crash Dumps",
+                Category::Media,
+                vec![
+                    obs.join("logs"),
+                    obs.join("crashes"),
+                    obs.join("profiler_data"),
+                ],
+            )
+            .with_strategy(Strategy::KeepNewestN(KEEP_LOG_FILES)),
+        );
+    }
+}
+
+// Per-user local cache root (%LOCALAPPDATA%, ~/Library/Caches, ~/.cache)
+pub fn local_cache_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env_path("LOCALAPPDATA");
+
+    #[cfg(target_os = "macos")]
+    return home_dir().map(|p| p.join("Library/Caches"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return env_path("XDG_CACHE_HOME").or_else(|| home_dir().map(|p| p.join(".cache")));
+}
+
+pub fn game_engine_targets(targets: &mut Vec<CacheTarget>, config: &Config) {
+    #[cfg(target_os = "windows")]
+    let unity_gi_cache = local_cache_dir().map(|p| p.join("Unity").join("Caches").join("GiCache"));
+    #[cfg(target_os = "macos")]
+    let unity_gi_cache = local_cache_dir().map(|p| p.join("com.unity3d.UnityEditor/GiCache"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let unity_gi_cache = app_config_dir().map(|p| p.join("unity3d/Caches/GiCache"));
+
+    if let Some(gi_cache) = unity_gi_cache {
+        targets.push(CacheTarget::new(
+            "unity-gi-cache",
+            "Unity GI Cache",
+            Category::Developer,
+            vec![gi_cache],
+        ));
+    }
+
+    #[cfg(target_os = "windows")]
+    let unreal_root = local_cache_dir().map(|p| p.join("UnrealEngine"));
+    #[cfg(not(target_os = "windows"))]
+    let unreal_root = app_config_dir().map(|p| p.join("Epic").join("UnrealEngine"));
+
+    if let Some(unreal) = unreal_root {
+        targets.push(CacheTarget::new(
+            "unreal-ddc",
+            "Unreal Engine Shared DDC",
+            Category::Developer,
+            vec![unreal.join("Common").join("DerivedDataCache")],
+        ));
+    }
+
+    // Per-project caches; a folder can be either engine's project root
+    let projects: Vec<PathBuf> = config.game_project_dirs.iter().map(PathBuf::from).collect();
+    targets.push(CacheTarget::new(
+        "unity-project-shadercache",
+        "Unity Project Shader Caches",
+        Category::Games,
+        projects.iter().map(|p| p.join("Library").join("ShaderCache")).collect(),
+    ));
+    targets.push(CacheTarget::new(
+        "unreal-project-ddc",
+        "Unreal Project DDCs",
+        Category::Games,
+        projects.iter().map(|p| p.join("DerivedDataCache")).collect(),
+    ));
+}
+
+fn android_sdk_dir() -> Option<PathBuf> {
+    if let Some(sdk) = env_path("ANDROID_HOME").or_else(|| env_path("ANDROID_SDK_ROOT")) {
+        return Some(sdk);
+    }
+
+    #[cfg(target_os = "windows")]
+    return local_cache_dir().map(|p| p.join("Android").join("Sdk"));
+
+    #[cfg(target_os = "macos")]
+    return home_dir().map(|p| p.join("Library/Android/sdk"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    return home_dir().map(|p| p.join("Android/Sdk"));
+}
+
+pub fn android_targets(targets: &mut Vec<CacheTarget>) {
+    // Android Studio keeps a separate system directory per installed version
+    if let Some(google) = local_cache_dir().map(|p| p.join("Google")) {
+        let mut studio_paths = Vec::new();
+        for system_dir in subdirs_with_prefix(&google, "AndroidStudio") {
+            studio_paths.push(system_dir.join("caches"));
+            studio_paths.push(system_dir.join("log"));
+            studio_paths.push(system_dir.join("tmp"));
+        }
+        targets.push(CacheTarget::new(
+            "android-studio-caches",
+            "Android Studio System Caches",
+            Category::Developer,
+            studio_paths,
+        ));
+    }
+
+    if let Some(home) = home_dir() {
+        targets.push(
+            CacheTarget::new(
+                "gradle-daemon-logs",
+                "Gradle Daemon Logs",
+                Category::Developer,
+                vec![home.join(".gradle").join("daemon")],
+            )
+            .with_strategy(Strategy::KeepNewestN(KEEP_LOG_FILES)),
+        );
+
+        let avd_root = home.join(".android").join("avd");
+        targets.push(CacheTarget::new(
+            "android-avd-snapshots",
+            "Android Emulator Snapshots",
+            Category::Developer,
+            subdirs_with_prefix(&avd_root, "")
+                .into_iter()
+                .filter(|p| p.extension().is_some_and(|ext| ext == "avd"))
+                .map(|p| p.join("snapshots"))
+                .collect(),
+        ));
+    }
+
+    let mut temp_paths = vec![std::env::temp_dir().join("AndroidEmulator")];
+    if let Some(home) = home_dir() {
+        temp_paths.push(home.join(".android").join("cache"));
+    }
+    if let Some(sdk) = android_sdk_dir() {
+        temp_paths.push(sdk.join(".temp"));
+    }
+    targets.push(CacheTarget::new(
+        "android-sdk-temp",
+        "Android SDK & ADB Temp",
+        Category::Developer,
+        temp_paths,
+    ));
+}
+
+// Locate an executable on PATH, trying the Windows script/binary extensions too
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    let candidates: &[&str] = if cfg!(target_os = "windows") {
+        &["exe", "bat", "cmd"]
+    } else {
+        &[""]
+    };
+
+    std::env::split_paths(&path_var).find_map(|dir| {
+        candidates
+            .iter()
+            .map(|ext| dir.join(name).with_extension(ext))
+            .find(|p| p.is_file())
+    })
+}
+
+pub fn flutter_targets(targets: &mut Vec<CacheTarget>) {
+    #[cfg(target_os = "windows")]
+    let default_pub_cache = local_cache_dir().map(|p| p.join("Pub").join("Cache"));
+    #[cfg(not(target_os = "windows"))]
+    let default_pub_cache = home_dir().map(|p| p.join(".pub-cache"));
+
+    let mut pub_paths: Vec<PathBuf> = env_path("PUB_CACHE").into_iter().collect();
+    pub_paths.extend(default_pub_cache);
+    targets.push(
+        CacheTarget::new("dart-pub-cache", "Dart Pub Cache", Category::Developer, pub_paths)
+            .with_strategy(Strategy::DeleteOlderThan(30 * DAY)),
+    );
+
+    // The Flutter SDK root is two levels above bin/flutter
+    let flutter_root = env_path("FLUTTER_ROOT").or_else(|| {
+        find_in_path("flutter").and_then(|exe| exe.parent()?.parent().map(Path::to_path_buf))
+    });
+    if let Some(root) = flutter_root {
+        targets.push(
+            CacheTarget::new(
+                "flutter-artifacts",
+                "Flutter SDK Artifacts",
+                Category::Developer,
+                vec![root.join("bin").join("cache")],
+            )
+            .with_strategy(Strategy::DeleteOlderThan(30 * DAY)),
+        );
+    }
+}
+
+// Run a tool and capture its stdout, without flashing a console window on Windows
+pub fn tool_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = std::process::Command::new(program);
+    command.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+    }
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn go_targets(targets: &mut Vec<CacheTarget>) {
+    // `go env` prints one value per line in the order requested
+    let (gopath, gocache) = match tool_output("go", &["env", "GOPATH", "GOCACHE"]) {
+        Some(output) => {
+            let mut lines = output.lines().map(str::trim);
+            let gopath = lines
+                .next()
+                .and_then(|list| std::env::split_paths(list).next())
+                .filter(|p| !p.as_os_str().is_empty());
+            let gocache = lines.next().filter(|p| !p.is_empty() && *p != "off").map(PathBuf::from);
+            (gopath, gocache)
+        }
+        None => (
+            env_path("GOPATH").or_else(|| home_dir().map(|p| p.join("go"))),
+            env_path("GOCACHE").or_else(|| local_cache_dir().map(|p| p.join("go-build"))),
+        ),
+    };
+
+    if let Some(gopath) = gopath {
+        targets.push(CacheTarget::new(
+            "go-mod-cache",
+            "Go Module Download Cache",
+            Category::Developer,
+            vec![gopath.join("pkg").join("mod").join("cache")],
+        ));
+    }
+    if let Some(gocache) = gocache {
+        targets.push(CacheTarget::new(
+            "go-build-cache",
+            "Go Build Cache",
+            Category::Developer,
+            vec![gocache],
+        ));
+    }
+}
+
+// ~/.local/share on Linux; Windows and macOS tools keep such data elsewhere
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn data_home() -> Option<PathBuf> {
+    env_path("XDG_DATA_HOME").or_else(|| home_dir().map(|p| p.join(".local/share")))
+}
+
+// Download caches of package managers, each offered only when the tool is
+// installed; every path honours the tool's own override variable
+pub fn package_manager_targets(targets: &mut Vec<CacheTarget>) {
+    let cache = local_cache_dir();
+    let home = home_dir();
+
+    #[cfg(target_os = "windows")]
+    let pip_cache = cache.as_ref().map(|p| p.join("pip").join("cache"));
+    #[cfg(not(target_os = "windows"))]
+    let pip_cache = cache.as_ref().map(|p| p.join("pip"));
+    targets.push(
+        CacheTarget::new(
+            "pip-cache",
+            "pip Cache",
+            Category::Developer,
+            env_path("PIP_CACHE_DIR").or(pip_cache).into_iter().collect(),
+        )
+        .when(Condition::AnyExecutable(&["pip", "pip3", "py"])),
+    );
+
+    #[cfg(target_os = "windows")]
+    let npm_cache = cache.as_ref().map(|p| p.join("npm-cache"));
+    #[cfg(not(target_os = "windows"))]
+    let npm_cache = home.as_ref().map(|p| p.join(".npm"));
+    // Logs and npx installs sit beside the cache and are kept
+    targets.push(
+        CacheTarget::new(
+            "npm-cache",
+            "npm Cache",
+            Category::Developer,
+            env_path("npm_config_cache")
+                .or(npm_cache)
+                .map(|p| p.join("_cacache"))
+                .into_iter()
+                .collect(),
+        )
+        .when(Condition::Executable("npm")),
+    );
+
+    #[cfg(target_os = "windows")]
+    let yarn_cache = cache.as_ref().map(|p| p.join("Yarn").join("Cache"));
+    #[cfg(target_os = "macos")]
+    let yarn_cache = cache.as_ref().map(|p| p.join("Yarn"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let yarn_cache = cache.as_ref().map(|p| p.join("yarn"));
+    // Yarn 2+ keeps its global cache apart from Yarn 1's
+    let mut yarn_paths: Vec<PathBuf> = env_path("YARN_CACHE_FOLDER").or(yarn_cache).into_iter().collect();
+    yarn_paths.extend(home.as_ref().map(|p| p.join(".yarn").join("berry").join("cache")));
+    targets.push(
+        CacheTarget::new("yarn-cache", "Yarn Cache", Category::Developer, yarn_paths)
+            .when(Condition::Executable("yarn")),
+    );
+
+    #[cfg(target_os = "windows")]
+    let (pnpm_cache, pnpm_store) = (
+        cache.as_ref().map(|p| p.join("pnpm-cache")),
+        cache.as_ref().map(|p| p.join("pnpm").join("store")),
+    );
+    #[cfg(target_os = "macos")]
+    let (pnpm_cache, pnpm_store) = (
+        cache.as_ref().map(|p| p.join("pnpm")),
+        home.as_ref().map(|p| p.join("Library/pnpm/store")),
+    );
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let (pnpm_cache, pnpm_store) = (
+        cache.as_ref().map(|p| p.join("pnpm")),
+        data_home().map(|p| p.join("pnpm").join("store")),
+    );
+    // Projects hard-link their packages from the store, so files still in
+    // use free little until those projects are removed too
+    targets.push(
+        CacheTarget::new(
+            "pnpm-cache",
+            "pnpm Cache & Store",
+            Category::Developer,
+            pnpm_cache.into_iter().chain(pnpm_store).collect(),
+        )
+        .when(Condition::Executable("pnpm")),
+    );
+
+    let cargo_home = env_path("CARGO_HOME").or_else(|| home.as_ref().map(|p| p.join(".cargo")));
+    if let Some(cargo_home) = cargo_home {
+        targets.push(
+            CacheTarget::new(
+                "cargo-registry-cache",
+                "Cargo Registry Cache",
+                Category::Developer,
+                vec![cargo_home.join("registry").join("cache")],
+            )
+            .when(Condition::Executable("cargo")),
+        );
+    }
+
+    // Gradle and Maven usually run through project wrappers or an IDE rather
+    // than from PATH, so only their folders existing tells they are used
+    let gradle_home = env_path("GRADLE_USER_HOME").or_else(|| home.as_ref().map(|p| p.join(".gradle")));
+    targets.push(CacheTarget::new(
+        "gradle-caches",
+        "Gradle Caches",
+        Category::Developer,
+        gradle_home.map(|p| p.join("caches")).into_iter().collect(),
+    ));
+    targets.push(
+        CacheTarget::new(
+            "maven-repository",
+            "Maven Local Repository",
+            Category::Developer,
+            home.as_ref().map(|p| p.join(".m2").join("repository")).into_iter().collect(),
+        )
+        .with_warning("Also holds artifacts installed locally with mvn install, which have to be built again"),
+    );
+
+    #[cfg(target_os = "windows")]
+    let nuget_http_cache = cache.as_ref().map(|p| p.join("NuGet").join("v3-cache"));
+    #[cfg(not(target_os = "windows"))]
+    let nuget_http_cache = home.as_ref().map(|p| p.join(".local/share/NuGet/http-cache"));
+    let nuget_packages = env_path("NUGET_PACKAGES").or_else(|| home.as_ref().map(|p| p.join(".nuget").join("packages")));
+    targets.push(
+        CacheTarget::new(
+            "nuget-cache",
+            "NuGet Packages & HTTP Cache",
+            Category::Developer,
+            nuget_packages
+                .into_iter()
+                .chain(env_path("NUGET_HTTP_CACHE_PATH").or(nuget_http_cache))
+                .collect(),
+        )
+        .when(Condition::AnyExecutable(&["dotnet", "nuget"])),
+    );
+}
+
+// Temp folders that stray tool artifacts end up in
+fn temp_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![std::env::temp_dir()];
+    #[cfg(target_os = "windows")]
+    dirs.extend(env_path("SystemRoot").map(|p| p.join("Temp")));
+    #[cfg(not(target_os = "windows"))]
+    dirs.push(PathBuf::from("/tmp"));
+    dirs.dedup();
+    dirs
+}
+
+// Direct children of `dir` whose names start with `prefix` and end with `suffix`
+fn entries_matching(dir: &Path, prefix: &str, suffix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.starts_with(prefix) && name.ends_with(suffix)
+        })
+        .map(|e| e.path())
+        .collect()
+}
+
+pub fn jvm_targets(targets: &mut Vec<CacheTarget>) {
+    let mut paths = Vec::new();
+    for temp in temp_dirs() {
+        // hsperfdata_<user> belongs to live JVMs too, hence the age limit below
+        paths.extend(entries_matching(&temp, "hsperfdata_", ""));
+        paths.extend(entries_matching(&temp, "jar_cache", ""));
+        paths.extend(entries_matching(&temp, "hs_err_pid", ".log"));
+    }
+    targets.push(
+        CacheTarget::new("jvm-temp", "JVM Temp Artifacts", Category::Developer, paths)
+            .with_strategy(Strategy::DeleteOlderThan(DAY)),
+    );
+}
+
+struct ChromiumBrowser {
+    id: &'static str,
+    name: &'static str,
+    process: &'static str,
+    // Holds profiles plus GPU/shader caches and crash reports
+    user_data: PathBuf,
+    // Where profile HTTP/code caches live; differs from user_data on macOS/Linux
+    cache_root: PathBuf,
+}
+
+enum ChromiumScope {
+    Profile,
+    UserData,
+}
+
+struct ChromiumCacheKind {
+    id: &'static str,
+    label: &'static str,
+    scope: ChromiumScope,
+    relative: &'static [&'static str],
+    strategy: Strategy,
+    format: CacheFormat,
+}
+
+// HTTP caches are trimmed rather than wiped so browsing stays warm
+const BROWSER_CACHE_BUDGET: u64 = 500 * 1024 * 1024;
+
+const CHROMIUM_CACHE_KINDS: [ChromiumCacheKind; 7] = [
+    ChromiumCacheKind {
+        id: "cache",
+        label: "Cache",
+        scope: ChromiumScope::Profile,
+        relative: &["Cache"],
+        strategy: Strategy::TrimToSize(BROWSER_CACHE_BUDGET),
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "code-cache",
+        label: "Code Cache",
+        scope: ChromiumScope::Profile,
+        relative: &["Code Cache"],
+        strategy: Strategy::DeleteAll,
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "gpu-cache",
+        label: "GPU Cache",
+        scope: ChromiumScope::Profile,
+        relative: &["GPUCache"],
+        strategy: Strategy::DeleteAll,
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "service-worker-cache",
+        label: "Service Worker Cache",
+        scope: ChromiumScope::Profile,
+        relative: &["Service Worker", "CacheStorage"],
+        strategy: Strategy::DeleteAll,
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "shader-cache",
+        label: "Shader Cache",
+        scope: ChromiumScope::UserData,
+        relative: &["ShaderCache"],
+        strategy: Strategy::DeleteAll,
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "gr-shader-cache",
+        label: "Graphite Shader Cache",
+        scope: ChromiumScope::UserData,
+        relative: &["GrShaderCache"],
+        strategy: Strategy::DeleteAll,
+        format: CacheFormat::Chromium,
+    },
+    ChromiumCacheKind {
+        id: "crashpad",
+        label: "Crash Reports",
+        scope: ChromiumScope::UserData,
+        relative: &["Crashpad", "reports"],
+        strategy: Strategy::KeepNewestN(KEEP_LOG_FILES),
+        format: CacheFormat::Files,
+    },
+];
+
+fn chromium_browsers() -> Vec<ChromiumBrowser> {
+    // (id, name, per-platform location below the config/cache roots, process)
+    #[cfg(target_os = "windows")]
+    const BROWSERS: [(&str, &str, &str, &str); 4] = [
+        ("chrome", "Chrome", "Google\\Chrome\\User Data", "chrome"),
+        ("edge", "Edge", "Microsoft\\Edge\\User Data", "msedge"),
+        ("brave", "Brave", "BraveSoftware\\Brave-Browser\\User Data", "brave"),
+        ("vivaldi", "Vivaldi", "Vivaldi\\User Data", "vivaldi"),
+    ];
+    #[cfg(target_os = "macos")]
+    const BROWSERS: [(&str, &str, &str, &str); 4] = [
+        ("chrome", "Chrome", "Google/Chrome", "Google Chrome"),
+        ("edge", "Edge", "Microsoft Edge", "Microsoft Edge"),
+        ("brave", "Brave", "BraveSoftware/Brave-Browser", "Brave Browser"),
+        ("vivaldi", "Vivaldi", "Vivaldi", "Vivaldi"),
+    ];
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    const BROWSERS: [(&str, &str, &str, &str); 4] = [
+        ("chrome", "Chrome", "google-chrome", "chrome"),
+        ("edge", "Edge", "microsoft-edge", "msedge"),
+        ("brave", "Brave", "BraveSoftware/Brave-Browser", "brave"),
+        ("vivaldi", "Vivaldi", "vivaldi", "vivaldi-bin"),
+    ];
+
+    // Windows keeps everything under %LOCALAPPDATA%; elsewhere caches are split out
+    #[cfg(target_os = "windows")]
+    let config_root = local_cache_dir();
+    #[cfg(not(target_os = "windows"))]
+    let config_root = app_config_dir();
+    let (Some(config_root), Some(cache_root)) = (config_root, local_cache_dir()) else {
+        return Vec::new();
+    };
+
+    BROWSERS
+        .iter()
+        .map(|(id, name, relative, process)| ChromiumBrowser {
+            id,
+            name,
+            process,
+            user_data: config_root.join(relative),
+            cache_root: cache_root.join(relative),
+        })
+        .collect()
+}
+
+// "Default", "Profile 1", ... under a Chromium user data directory
+fn chromium_profiles(root: &Path) -> Vec<PathBuf> {
+    let mut profiles = subdirs_with_prefix(root, "Profile ");
+    profiles.push(root.join("Default"));
+    profiles
+}
+
+pub fn chromium_targets(targets: &mut Vec<CacheTarget>) {
+    for browser in chromium_browsers() {
+        let mut profiles = chromium_profiles(&browser.user_data);
+        if browser.cache_root != browser.user_data {
+            profiles.extend(chromium_profiles(&browser.cache_root));
+        }
+
+        for kind in &CHROMIUM_CACHE_KINDS {
+            let roots = match kind.scope {
+                ChromiumScope::Profile => profiles.clone(),
+                ChromiumScope::UserData => vec![browser.user_data.clone()],
+            };
+            let paths = roots
+                .into_iter()
+                .map(|root| kind.relative.iter().fold(root, |path, part| path.join(part)))
+                .collect();
+
+            targets.push(
+                CacheTarget::new(
+                    &format!("{}-{}", browser.id, kind.id),
+                    &format!("{} {}", browser.name, kind.label),
+                    Category::Browsers,
+                    paths,
+                )
+                .with_strategy(kind.strategy)
+                .with_format(kind.format)
+                .owned_by(browser.name, browser.process),
+            );
+        }
+    }
+}
+
+// Firefox splits each profile; the local (non-roaming) half holds the caches
+pub fn firefox_targets(targets: &mut Vec<CacheTarget>) {
+    #[cfg(target_os = "windows")]
+    let profiles_root = local_cache_dir().map(|p| p.join("Mozilla").join("Firefox").join("Profiles"));
+    #[cfg(target_os = "macos")]
+    let profiles_root = local_cache_dir().map(|p| p.join("Firefox/Profiles"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let profiles_root = local_cache_dir().map(|p| p.join("mozilla/firefox"));
+
+    let Some(profiles_root) = profiles_root else {
+        return;
+    };
+    let profiles = subdirs_with_prefix(&profiles_root, "");
+
+    const FIREFOX_CACHE_KINDS: [(&str, &str, &str, Strategy); 4] = [
+        ("firefox-cache", "Firefox Cache", "cache2", Strategy::TrimToSize(BROWSER_CACHE_BUDGET)),
+        ("firefox-startup-cache", "Firefox Startup Cache", "startupCache", Strategy::DeleteAll),
+        ("firefox-shader-cache", "Firefox Shader Cache", "shader-cache", Strategy::DeleteAll),
+        ("firefox-thumbnails", "Firefox Thumbnails", "thumbnails", Strategy::DeleteAll),
+    ];
+    for (id, name, folder, strategy) in FIREFOX_CACHE_KINDS {
+        targets.push(
+            CacheTarget::new(
+                id,
+                name,
+                Category::Browsers,
+                profiles.iter().map(|p| p.join(folder)).collect(),
+            )
+            .with_strategy(strategy)
+            .owned_by("Firefox", "firefox"),
+        );
+    }
+}
+
+// System Integrity Protection roots; nothing below them is ever a target
+#[cfg(target_os = "macos")]
+fn is_sip_protected(path: &Path) -> bool {
+    const SIP_ROOTS: [&str; 5] = ["/System", "/bin", "/sbin", "/usr", "/private/var/db"];
+    SIP_ROOTS.iter().any(|root| path.starts_with(root)) && !path.starts_with("/usr/local")
+}
+
+// Cache folders that need Full Disk Access (TCC) are readable only when the
+// app was granted it; anything we cannot list is left out of the target
+#[cfg(target_os = "macos")]
+fn is_listable(path: &Path) -> bool {
+    std::fs::read_dir(path).is_ok()
+}
+
+#[cfg(target_os = "macos")]
+pub fn macos_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(home) = home_dir() else {
+        return;
+    };
+    let caches = home.join("Library/Caches");
+
+    targets.push(
+        CacheTarget::new(
+            "safari-cache",
+            "Safari Cache",
+            Category::Browsers,
+            vec![
+                caches.join("com.apple.Safari"),
+                home.join("Library/Containers/com.apple.Safari/Data/Library/Caches"),
+            ],
+        )
+        .owned_by("Safari", "Safari"),
+    );
+
+    let apple_caches = subdirs_with_prefix(&caches, "com.apple.")
+        .into_iter()
+        .filter(|p| !p.ends_with("com.apple.Safari"))
+        .filter(|p| !is_sip_protected(p) && is_listable(p))
+        .collect();
+    targets.push(CacheTarget::new(
+        "macos-apple-caches",
+        "macOS System App Caches",
+        Category::System,
+        apple_caches,
+    ));
+
+    // Prefer brew's own answer, which honours HOMEBREW_CACHE
+    let brew_cache = tool_output("brew", &["--cache"])
+        .map(|out| PathBuf::from(out.trim()))
+        .unwrap_or_else(|| caches.join("Homebrew"));
+    targets.push(CacheTarget::new(
+        "homebrew-cache",
+        "Homebrew Download Cache",
+        Category::Developer,
+        vec![brew_cache],
+    ));
+
+    let xcode = home.join("Library/Developer/Xcode");
+    targets.push(CacheTarget::new(
+        "xcode-derived-data",
+        "Xcode DerivedData",
+        Category::Developer,
+        vec![xcode.join("DerivedData")],
+    ));
+    // Archives hold shipped builds and their dSYMs, so only old ones go
+    targets.push(
+        CacheTarget::new(
+            "xcode-archives",
+            "Xcode Archives",
+            Category::Developer,
+            vec![xcode.join("Archives")],
+        )
+        .with_strategy(Strategy::DeleteOlderThan(90 * DAY)),
+    );
+    // Symbols for OS versions no device has been attached with in months
+    targets.push(
+        CacheTarget::new(
+            "xcode-device-support",
+            "Xcode Device Support",
+            Category::Developer,
+            ["iOS DeviceSupport", "watchOS DeviceSupport", "tvOS DeviceSupport"]
+                .iter()
+                .map(|folder| xcode.join(folder))
+                .collect(),
+        )
+        .with_strategy(Strategy::DeleteOlderThan(90 * DAY)),
+    );
+
+    targets.push(CacheTarget::new(
+        "ios-simulator-caches",
+        "iOS Simulator Caches",
+        Category::Developer,
+        vec![home.join("Library/Developer/CoreSimulator/Caches")],
+    ));
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn macos_targets(_targets: &mut Vec<CacheTarget>) {}
+
+#[cfg(target_os = "linux")]
+pub fn linux_app_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(home) = home_dir() else {
+        return;
+    };
+
+    targets.push(
+        CacheTarget::new(
+            "snap-app-caches",
+            "Snap App Caches",
+            Category::Applications,
+            subdirs_with_prefix(&home.join("snap"), "")
+                .into_iter()
+                .map(|app| app.join("common").join(".cache"))
+                .collect(),
+        )
+        .when(Condition::Executable("snap")),
+    );
+
+    targets.push(
+        CacheTarget::new(
+            "flatpak-app-caches",
+            "Flatpak App Caches",
+            Category::Applications,
+            subdirs_with_prefix(&home.join(".var").join("app"), "")
+                .into_iter()
+                .map(|app| app.join("cache"))
+                .collect(),
+        )
+        .when(Condition::Executable("flatpak")),
+    );
+
+    // Runtimes are removed with `flatpak uninstall --unused`; only their size is shown
+    targets.push(
+        CacheTarget::new(
+            "flatpak-runtimes",
+            "Flatpak Runtimes",
+            Category::Applications,
+            vec![
+                PathBuf::from("/var/lib/flatpak/runtime"),
+                home.join(".local/share/flatpak/runtime"),
+            ],
+        )
+        .report_only(),
+    );
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn linux_app_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// Real uid of this process, read from procfs to avoid a libc dependency
+#[cfg(target_os = "linux")]
+fn current_uid() -> Option<u32> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+// Mount points from /proc/mounts, decoding the octal escapes used for spaces
+#[cfg(target_os = "linux")]
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = std::fs::read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|mount| {
+            PathBuf::from(
+                mount
+                    .replace("\\040", " ")
+                    .replace("\\011", "\t")
+                    .replace("\\134", "\\"),
+            )
+        })
+        .collect()
+}
+
+// Home trash plus the per-volume trash directories from the XDG trash spec
+#[cfg(target_os = "linux")]
+fn trash_dirs() -> Vec<PathBuf> {
+    let mut trash_dirs: Vec<PathBuf> = data_home().map(|p| p.join("Trash")).into_iter().collect();
+
+    if let Some(uid) = current_uid() {
+        for mount in mount_points() {
+            trash_dirs.push(mount.join(format!(".Trash-{}", uid)));
+            trash_dirs.push(mount.join(".Trash").join(uid.to_string()));
+        }
+    }
+    trash_dirs
+}
+
+#[cfg(target_os = "linux")]
+pub fn trash_targets(targets: &mut Vec<CacheTarget>) {
+    // Trashed project trees can hold millions of files; an estimate is
+    // enough since the whole trash is emptied anyway
+    targets.push(
+        CacheTarget::new(
+            "xdg-trash",
+            "Trash",
+            Category::System,
+            trash_dirs()
+                .into_iter()
+                .flat_map(|trash| [trash.join("files"), trash.join("info"), trash.join("expunged")])
+                .collect(),
+        )
+        .with_scan_limits(ScanLimits {
+            max_depth: None,
+            estimate: true,
+            skip_unchanged: false,
+            streaming: false,
+        }),
+    );
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn trash_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// The recycle bin of every drive
+#[cfg(target_os = "windows")]
+fn recycle_bins() -> Vec<PathBuf> {
+    drive_roots().into_iter().map(|root| root.join("$Recycle.Bin")).collect()
+}
+
+// Only the Trash target counts the trash; scans of other targets skip it
+#[cfg(target_os = "linux")]
+fn recycle_bins() -> Vec<PathBuf> {
+    trash_dirs()
+}
+
+// The home folder's Trash plus that of every mounted volume
+#[cfg(target_os = "macos")]
+fn recycle_bins() -> Vec<PathBuf> {
+    let volumes = std::fs::read_dir("/Volumes")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join(".Trashes"));
+    home_dir().map(|p| p.join(".Trash")).into_iter().chain(volumes).collect()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn recycle_bins() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+// Keep the quarantine folder and the recycle bins out of target sizes, so
+// space a clean gave back but can still restore is not counted as cache again
+pub fn skip_freed_space() {
+    for dir in staging::staging_dir().into_iter().chain(recycle_bins()) {
+        cleaner::skip_in_scans(&dir);
+    }
+}
+
+// Children of `dir` that no target found so far covers, for catch-all targets
+// that must not count or clean the same files twice
+fn uncovered_children(dir: &Path, targets: &[CacheTarget]) -> Vec<PathBuf> {
+    let covered = |child: &Path| {
+        targets
+            .iter()
+            .flat_map(|target| &target.paths)
+            .any(|path| path.starts_with(child) || child.starts_with(path))
+    };
+    entries_matching(dir, "", "")
+        .into_iter()
+        .filter(|child| !covered(child))
+        .collect()
+}
+
+// Whatever programs leave in the temp folders. Recent files may belong to a
+// running installer or build, so only old ones go. /tmp is shared on Linux:
+// other users' files are left to them.
+pub fn temp_targets(targets: &mut Vec<CacheTarget>) {
+    let mut paths = Vec::new();
+    for temp in temp_dirs() {
+        paths.extend(uncovered_children(&temp, targets).into_iter().filter(|child| {
+            let name = child.file_name().unwrap_or_default().to_string_lossy();
+            // X11/ICE sockets, tmux and ssh-agent sockets and systemd's
+            // private service folders
+            !(cfg!(unix)
+                && (name.starts_with('.')
+                    || name.starts_with("tmux-")
+                    || name.starts_with("ssh-")
+                    || name.starts_with("systemd-private-")))
+        }));
+    }
+    let target = CacheTarget::new("temp-files", "Temporary Files", Category::System, paths)
+        .with_strategy(Strategy::DeleteOlderThan(7 * DAY));
+    #[cfg(target_os = "linux")]
+    let target = CacheTarget { exclusions: Exclusions::default().with_owner(current_uid()), ..target };
+    targets.push(target);
+}
+
+// Per-user cache folders of apps without a target of their own:
+// $XDG_CACHE_HOME (~/.cache) on Linux, ~/Library/Caches on macOS
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn user_cache_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(cache_home) = local_cache_dir() else {
+        return;
+    };
+    let paths = uncovered_children(&cache_home, targets);
+    targets.push(
+        CacheTarget::new("xdg-cache", "Other App Caches (~/.cache)", Category::Applications, paths)
+            .with_strategy(Strategy::DeleteOlderThan(7 * DAY)),
+    );
+}
+
+#[cfg(target_os = "macos")]
+pub fn user_cache_targets(targets: &mut Vec<CacheTarget>) {
+    let Some(caches) = local_cache_dir() else {
+        return;
+    };
+    // Apple's own caches are left to macos-apple-caches
+    let paths = uncovered_children(&caches, targets)
+        .into_iter()
+        .filter(|p| !p.file_name().unwrap_or_default().to_string_lossy().starts_with("com.apple."))
+        .filter(|p| is_listable(p))
+        .collect();
+    targets.push(
+        CacheTarget::new(
+            "macos-app-caches",
+            "Other App Caches (~/Library/Caches)",
+            Category::Applications,
+            paths,
+        )
+        .with_strategy(Strategy::DeleteOlderThan(7 * DAY)),
+    );
+}
+
+#[cfg(target_os = "windows")]
+pub fn user_cache_targets(_targets: &mut Vec<CacheTarget>) {}
+
+// Expand environment variables in a user-entered path: %VAR% on Windows,
+// $VAR and a leading ~ elsewhere. Unknown variables are left as written.
+pub fn expand_path(text: &str) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let mut expanded = String::new();
+        let mut parts = text.split('%');
+        expanded.push_str(parts.next().unwrap_or_default());
+        while let Some(name) = parts.next() {
+            match (std::env::var(name), parts.clone().next()) {
+                (Ok(value), Some(_)) => {
+                    expanded.push_str(&value);
+                    expanded.push_str(parts.next().unwrap_or_default());
+                }
+                _ => {
+                    expanded.push('%');
+                    expanded.push_str(name);
+                }
+            }
+        }
+        PathBuf::from(expanded)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let text = match (text.strip_prefix('~'), home_dir()) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", home.display(), rest)
+            }
+            _ => text.to_string(),
+        };
+        let mut expanded = String::new();
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find('$') {
+            expanded.push_str(&rest[..start]);
+            let name_len = rest[start + 1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len() - start - 1);
+            let name = &rest[start + 1..start + 1 + name_len];
+            match std::env::var(name) {
+                Ok(value) if !name.is_empty() => expanded.push_str(&value),
+                _ => expanded.push_str(&rest[start..start + 1 + name_len]),
+            }
+            rest = &rest[start + 1 + name_len..];
+        }
+        expanded.push_str(rest);
+        PathBuf::from(expanded)
+    }
+}
+
+// Folders of the operating system and installed programs; a custom folder
+// must not be one, be inside one or contain one
+fn system_dirs() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        let mut dirs: Vec<PathBuf> = [r"\Windows", r"\Program Files", r"\Program Files (x86)"]
+            .iter()
+            .map(|dir| PathBuf::from(format!("{}{}", drive, dir)))
+            .collect();
+        dirs.extend(["SystemRoot", "ProgramFiles", "ProgramFiles(x86)"].into_iter().filter_map(env_path));
+        dirs
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    ["/etc", "/usr", "/bin", "/sbin", "/lib", "/boot", "/System"]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+}
+
+// Folders holding every user's files; a custom folder must not be one or
+// contain one, but may be inside one
+fn shared_dirs() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        let mut dirs = vec![PathBuf::from(format!(r"{}\Users", drive))];
+        dirs.extend(env_path("ProgramData"));
+        dirs
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    ["/home", "/Users"].iter().map(PathBuf::from).collect()
+}
+
+// Why cleaning `path` would reach into the system, every user's files or
+// the home folder, compared as cleaner::comparable does
+fn protected_overlap(path: &Path, home: Option<&Path>, system: &[PathBuf], shared: &[PathBuf]) -> Option<String> {
+    let path = cleaner::comparable(path);
+    if let Some(home) = home {
+        let home_comparable = cleaner::comparable(home);
+        if home_comparable == path {
+            return Some("is the home folder".to_string());
+        }
+        if home_comparable.starts_with(&path) {
+            return Some(format!("contains the home folder {}", home.display()));
+        }
+    }
+    for dir in system {
+        let dir_comparable = cleaner::comparable(dir);
+        if path.starts_with(&dir_comparable) {
+            return Some(format!("is in the system folder {}", dir.display()));
+        }
+        if dir_comparable.starts_with(&path) {
+            return Some(format!("contains the system folder {}", dir.display()));
+        }
+    }
+    shared
+        .iter()
+        .find(|dir| cleaner::comparable(dir).starts_with(&path))
+        .map(|dir| format!("is or contains {}, which holds every user's files", dir.display()))
+}
+
+// A folder the user registered as a cache, expanded. Drive roots, system
+// folders, the home folder and the folders holding it, and paths check_path
+// refuses are not accepted.
+fn custom_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = expand_path(dir.trim());
+    if !path.is_absolute() {
+        return Err(format!("Custom folder {} is not an absolute path", dir));
+    }
+    if path.parent().is_none() {
+        return Err(format!("Custom folder {} is a drive root", dir));
+    }
+    if let Some(problem) = protected_overlap(&path, home_dir().as_deref(), &system_dirs(), &shared_dirs()) {
+        return Err(format!("Custom folder {} {}", dir, problem));
+    }
+    cleaner::check_path(&path).map_err(|e| format!("Custom folder {}", e))?;
+    // A clean would empty whatever the link points to instead
+    if std::fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        return Err(format!("Custom folder {} is a link or junction to another folder", dir));
+    }
+    // Deletions there would reach the cloud copy and every synced device
+    if let Some(synced) = cloud_sync::overlap(&path) {
+        return Err(format!(
+            "Custom folder {} overlaps the {} folder {}, which syncs deletions to the cloud",
+            dir,
+            synced.provider,
+            synced.path.display()
+        ));
+    }
+    Ok(path)
+}
+
+// A custom folder as custom_dir checks it, and not on a network share:
+// shares are only ever reported on
+pub fn local_custom_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = custom_dir(dir)?;
+    if network_shares::is_network_path(&path) {
+        return Err(format!(
+            "Custom folder {} is on a network share, which is never cleaned; add it as a network share to track its size",
+            dir
+        ));
+    }
+    Ok(path)
+}
+
+pub fn validate_custom_dirs(dirs: &[String]) -> Result<(), String> {
+    dirs.iter().try_for_each(|dir| local_custom_dir(dir).map(|_| ()))
+}
+
+// The startup check of a custom folder: it must still pass the checks it
+// was added with, and still be there
+pub fn check_custom_dir(dir: &str) -> Result<(), String> {
+    if local_custom_dir(dir)?.is_dir() {
+        Ok(())
+    } else {
+        Err(format!("Custom folder {} no longer exists", dir))
+    }
+}
+
+// Why each custom folder that is skipped is, keyed by the folder as entered;
+// folders saved before a check existed, or set machine-wide, can fail it, and
+// those the startup check turned off stay skipped until turned back on
+pub fn custom_dir_problems(config: &Config) -> BTreeMap<String, String> {
+    config
+        .custom_cache_dirs
+        .iter()
+        .filter_map(|dir| {
+            let problem = custom_dir(dir).err().or_else(|| config.disabled_custom_dirs.get(dir).cloned());
+            problem.map(|e| (dir.clone(), e))
+        })
+        .collect()
+}
+
+// Folders the user registered as caches; everything inside them is cleaned
+pub fn custom_targets(targets: &mut Vec<CacheTarget>, config: &Config) {
+    for dir in &config.custom_cache_dirs {
+        if config.disabled_custom_dirs.contains_key(dir) {
+            continue;
+        }
+        let Ok(path) = custom_dir(dir) else {
+            continue;
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| dir.clone(), |name| name.to_string_lossy().into_owned());
+        let mut target = CacheTarget::new(&format!("custom:{}", dir), &name, Category::Custom, vec![path]);
+        target.label = config.custom_dir_labels.get(dir).filter(|label| !label.is_empty()).cloned();
+        targets.push(target);
+    }
+}
+
+// A folder not yet added as a custom folder, as the target it would become,
+// for trying out a rule before it is
+pub fn trial_target(dir: &str, path: PathBuf, exclusions: Exclusions) -> CacheTarget {
+    let mut target = CacheTarget::new(&format!("custom:{}", dir), dir, Category::Custom, vec![path]);
+    target.exclusions = exclusions;
+    target
+}
+
+// Folders of the remote rule file, as a custom folder of theirs would be,
+// unless the user added them as one already. Without a trusted signature
+// they are only sized.
+pub fn remote_targets(targets: &mut Vec<CacheTarget>, config: &Config) {
+    let trusted = remote_rules::is_trusted(config);
+    for rule in remote_rules::rules(config) {
+        if config.custom_cache_dirs.contains(&rule.path) {
+            continue;
+        }
+        let Ok(path) = local_custom_dir(&rule.path) else {
+            continue;
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| rule.path.clone(), |name| name.to_string_lossy().into_owned());
+        let mut target = CacheTarget::new(&format!("remote:{}", rule.path), &name, Category::Custom, vec![path]);
+        target.rule_exclusions = rule.exclusions;
+        target.rule_min_age_days = rule.min_age_days;
+        targets.push(if trusted { target } else { target.report_only() });
+    }
+}
+
+// Network share folders whose size is only reported, never cleaned
+pub fn share_targets(targets: &mut Vec<CacheTarget>, config: &Config) {
+    for dir in &config.network_share_dirs {
+        let Ok(path) = network_shares::share_dir(dir) else {
+            continue;
+        };
+        targets.push(
+            CacheTarget::new(&format!("share:{}", dir), dir.trim(), Category::Custom, vec![path]).report_only(),
+        );
+    }
+}
+
+// The configured exclusion patterns that apply to target `id`, those for
+// every target first
+pub fn exclusion_patterns<'a>(config: &'a Config, id: &'a str) -> impl Iterator<Item = &'a String> {
+    [ALL_TARGETS, id]
+        .into_iter()
+        .filter_map(|key| config.exclusions.get(key))
+        .flatten()
+}
+
+// Files used within this many days are kept; None keeps none for their age
+pub fn min_age_days(config: &Config, id: &str) -> Option<u64> {
+    config
+        .min_age_days
+        .get(id)
+        .or_else(|| config.min_age_days.get(ALL_TARGETS))
+        .copied()
+        .filter(|&days| days > 0)
+}
+
+// The targets last found, with the config they were found for. Finding them
+// runs tools such as `go env` and `brew --cache`, so the checks made every
+// minute reuse the list until the config changes or a scan looks again.
+static FOUND: Mutex<Option<(String, Vec<CacheTarget>)>> = Mutex::new(None);
+
+// Have the next builtin_targets look for targets again, as scans do
+pub fn forget_found() {
+    *FOUND.lock().unwrap() = None;
+}
+
+// Built-in targets for this platform, keeping only locations that exist
+pub fn builtin_targets(config: &Config) -> Vec<CacheTarget> {
+    let key = serde_json::to_string(config).unwrap_or_default();
+    let mut found = FOUND.lock().unwrap();
+    let mut targets = match &*found {
+        Some((found_for, targets)) if *found_for == key => targets.clone(),
+        _ => {
+            let targets = find_targets(config);
+            *found = Some((key, targets.clone()));
+            targets
+        }
+    };
+    drop(found);
+    // Conditions may change from one minute to the next, with programs
+    // started and closed
+    let processes = OnceCell::new();
+    targets.retain(|t| !t.paths.is_empty() && t.conditions.iter().all(|c| c.holds(&processes)));
+    targets
+}
+
+fn find_targets(config: &Config) -> Vec<CacheTarget> {
+    let mut targets = Vec::new();
+    for cleaner in cleaners::available() {
+        let found = targets.len();
+        cleaner.scan(config, &mut targets);
+        for target in &mut targets[found..] {
+            target.cleaner = cleaner.name();
+        }
+    }
+
+    for target in &mut targets {
+        // Limits from the config replace the built-in ones of the same strategy
+        if let (Strategy::TrimToSize(_), Some(budget_mb)) =
+            (target.strategy, config.size_budgets_mb.get(&target.id))
+        {
+            target.strategy = Strategy::TrimToSize(budget_mb * 1024 * 1024);
+        }
+        if let (Strategy::KeepNewestN(_), Some(&count)) =
+            (target.strategy, config.keep_newest_counts.get(&target.id))
+        {
+            target.strategy = Strategy::KeepNewestN(count);
+        }
+        if let Some(&limits) = config.scan_limits.get(&target.id) {
+            target.scan_limits = limits;
+        }
+        target.scan_limits.streaming |= config.streaming_scan;
+        // The longer of the user's and the rule's age policy keeps more
+        let min_age_days = min_age_days(config, &target.id).max(target.rule_min_age_days.filter(|&days| days > 0));
+        target.exclusions = Exclusions::new(exclusion_patterns(config, &target.id).chain(&target.rule_exclusions))
+            .with_min_age(min_age_days.map(|days| DAY.saturating_mul(u32::try_from(days).unwrap_or(u32::MAX))))
+            .with_owner(target.exclusions.owner());
+        target.paths.retain(|p| p.exists());
+        target.paths.dedup();
+        target.volume = target.paths.first().and_then(|p| cleaner::volume_id(p));
+    }
+    targets.sort_by_key(|t| t.category);
+    targets
+}
+
+// Volumes cleaned at once; 0 for no limit
+static MAX_PARALLEL_VOLUMES: AtomicUsize = AtomicUsize::new(0);
+
+// At most `count` volumes are cleaned at once from then on, for cleans that
+// must leave the machine usable
+pub fn limit_parallel_volumes(count: usize) {
+    MAX_PARALLEL_VOLUMES.store(count, Ordering::Relaxed);
+}
+
+// Clean targets on different volumes in parallel, but the targets of any one
+// volume in sequence, so a hard disk is never thrashed by several deleters
+// while another drive sits idle. `on_done` is called as each target finishes,
+// and what each one freed goes into `history`, how fast into the rates
+// automatic cleans are ordered by.
+pub fn clean_targets(targets: &[CacheTarget], history: &Recorder, on_done: impl Fn(&CacheTarget) + Sync) -> CleanStats {
+    let clean = |target: &CacheTarget| {
+        let start = Instant::now();
+        let stats = history.track(target, clean_target(target));
+        clean_budget::record_rate(&target.id, stats.freed_bytes, start.elapsed());
+        cooldown::start([target.id.as_str()]);
+        confirmation::clear([target.id.as_str()]);
+        stats
+    };
+    clean_targets_with(targets, clean, on_done)
+}
+
+// Clean one target according to its strategy
+pub fn clean_target(target: &CacheTarget) -> CleanStats {
+    cleaners::of(target).clean(target)
+}
+
+// Delete exactly the files a user reviewed from the target's preview
+pub fn clean_reviewed(target: &CacheTarget, files: &[PathBuf]) -> CleanStats {
+    cleaners::of(target).clean_files(target, files)
+}
+
+// The targets a clean may touch now: neither report-only nor in use by a
+// running program
+pub fn cleanable(targets: &[CacheTarget]) -> Vec<&CacheTarget> {
+    let processes = OnceCell::new();
+    targets
+        .iter()
+        .filter(|t| !t.report_only && t.running_owner(&processes).is_none())
+        .collect()
+}
+
+// Like clean_targets, with `clean` deciding what to delete for each target
+pub fn clean_targets_with(
+    targets: &[CacheTarget],
+    clean: impl Fn(&CacheTarget) -> CleanStats + Sync,
+    on_done: impl Fn(&CacheTarget) + Sync,
+) -> CleanStats {
+    let mut by_volume: BTreeMap<Option<&str>, Vec<&CacheTarget>> = BTreeMap::new();
+    for target in cleanable(targets) {
+        by_volume.entry(target.volume.as_deref()).or_default().push(target);
+    }
+
+    let worker_count = match MAX_PARALLEL_VOLUMES.load(Ordering::Relaxed) {
+        0 => by_volume.len(),
+        limit => limit.min(by_volume.len()),
+    };
+    // Each worker takes the next volume's targets when done with its last
+    let groups = Mutex::new(by_volume.into_values());
+
+    let mut total = CleanStats::default();
+    let run = cleaner::current_run();
+    std::thread::scope(|scope| {
+        let clean = &clean;
+        let on_done = &on_done;
+        let groups = &groups;
+        let workers: Vec<_> = (0..worker_count)
+            .map(|_| {
+                let run = run.clone();
+                scope.spawn(move || {
+                    cleaner::join_run(run);
+                    let mut stats = CleanStats::default();
+                    while !cleaner::is_cancelled() {
+                        let Some(group) = groups.lock().unwrap().next() else {
+                            break;
+                        };
+                        for target in group {
+                            let _lock = if target.is_shared() {
+                                let Some(lock) = system_lock::acquire() else {
+                                    break;
+                                };
+                                Some(lock)
+                            } else {
+                                None
+                            };
+                            stats.add(clean(target));
+                            if cleaner::is_cancelled() {
+                                break;
+                            }
+                            on_done(target);
+                        }
+                    }
+                    stats
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            if let Ok(stats) = worker.join() {
+                total.add(stats);
+            }
+        }
+    });
+    total
+}
+
+// What cleaning a target would delete, for review before anything is removed
+#[derive(Serialize)]
+pub struct TargetPreview {
+    pub id: String,
+    pub name: String,
+    pub strategy: Option<String>,
+    pub total_bytes: u64,
+    pub files: Vec<Candidate>,
+    // Running programs that would keep some of the files from being deleted
+    pub locked_by: Vec<LockingApp>,
+}
+
+fn candidates(target: &CacheTarget) -> Vec<Candidate> {
+    cleaners::of(target).candidates(target)
+}
+
+// Bytes a clean would free now: the files the target's strategy, age policy
+// and exclusions let go, less those another program keeps from being deleted
+pub fn reclaimable(target: &CacheTarget) -> u64 {
+    if target.report_only {
+        return 0;
+    }
+    candidates(target)
+        .iter()
+        .filter(|file| !file_locks::is_locked(&file.path))
+        .map(|file| file.size)
+        .sum()
+}
+
+pub fn preview(target: &CacheTarget) -> TargetPreview {
+    let files = candidates(target);
+    TargetPreview {
+        id: target.id.clone(),
+        name: target.name.clone(),
+        strategy: target.strategy.describe(),
+        total_bytes: files.iter().map(|file| file.size).sum(),
+        locked_by: file_locks::locking_apps(files.iter().map(|file| (file.path.as_path(), file.size))),
+        files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn expand_path_replaces_variables_and_home() {
+        std::env::set_var("CACHE_MANAGER_TEST_DIR", "/srv/cache");
+        assert_eq!(expand_path("$CACHE_MANAGER_TEST_DIR/app"), PathBuf::from("/srv/cache/app"));
+        assert_eq!(expand_path("/a/$CACHE_MANAGER_TEST_DIR"), PathBuf::from("/a//srv/cache"));
+        let home = home_dir().unwrap();
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/.cache"), home.join(".cache"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn expand_path_leaves_unknown_variables_as_written() {
+        assert_eq!(expand_path("$CACHE_MANAGER_NO_SUCH_VAR/app"), PathBuf::from("$CACHE_MANAGER_NO_SUCH_VAR/app"));
+        assert_eq!(expand_path("/price$/x$"), PathBuf::from("/price$/x$"));
+        assert_eq!(expand_path("~other/cache"), PathBuf::from("~other/cache"));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn expand_path_replaces_percent_variables() {
+        std::env::set_var("CACHE_MANAGER_TEST_DIR", r"D:\cache");
+        assert_eq!(expand_path(r"%CACHE_MANAGER_TEST_DIR%\app"), PathBuf::from(r"D:\cache\app"));
+        assert_eq!(expand_path(r"%CACHE_MANAGER_NO_SUCH_VAR%\app"), PathBuf::from(r"%CACHE_MANAGER_NO_SUCH_VAR%\app"));
+        assert_eq!(expand_path(r"C:\100%\app"), PathBuf::from(r"C:\100%\app"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn custom_folders_stay_out_of_system_and_home_folders() {
+        let home = Path::new("/home/alice");
+        let overlap = |path: &str| protected_overlap(Path::new(path), Some(home), &system_dirs(), &shared_dirs());
+        for refused in ["/home", "/home/", "/home/alice", "/Users", "/etc", "/usr", "/usr/share/app", "/System"] {
+            assert!(overlap(refused).is_some(), "{}", refused);
+        }
+        for accepted in ["/home/alice/.cache/app", "/home/bob/cache", "/var/cache/app", "/srv/cache", "/tmp/build"] {
+            assert_eq!(overlap(accepted), None, "{}", accepted);
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn custom_folders_stay_out_of_system_and_home_folders() {
+        let home = Path::new(r"C:\Users\alice");
+        let system = [PathBuf::from(r"C:\Windows"), PathBuf::from(r"C:\Program Files")];
+        let shared = [PathBuf::from(r"C:\Users"), PathBuf::from(r"C:\ProgramData")];
+        let overlap = |path: &str| protected_overlap(Path::new(path), Some(home), &system, &shared);
+        for refused in [
+            r"C:\Users",
+            r"c:\users\",
+            r"C:\USERS\Alice",
+            r"C:\Windows",
+            r"C:\WINDOWS\System32",
+            r"C:\Program Files",
+            r"c:\program files\App",
+            r"C:\ProgramData",
+        ] {
+            assert!(overlap(refused).is_some(), "{}", refused);
+        }
+        for accepted in [r"C:\Users\alice\AppData\Local\App\Cache", r"C:\ProgramData\App\Cache", r"D:\Cache"] {
+            assert_eq!(overlap(accepted), None, "{}", accepted);
+        }
+    }
+}
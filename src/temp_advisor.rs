@@ -0,0 +1,279 @@
+use crate::cleaner;
+use crate::disk_space;
+use crate::targets;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Temp files and browser caches are written constantly. When they sit on the
+// system SSD they wear it and fill the drive Windows or macOS boots from;
+// another volume, or a RAM disk, takes that load instead.
+
+#[derive(Serialize)]
+pub struct Volume {
+    pub root: String,
+    pub free_bytes: Option<u64>,
+    // None when the drive type could not be read
+    pub solid_state: Option<bool>,
+}
+
+// A browser whose disk cache can be moved, and how
+#[derive(Serialize)]
+pub struct BrowserCache {
+    pub id: &'static str,
+    pub name: &'static str,
+    // Whether move_browser_cache can make the change; otherwise `how` says
+    // what to do by hand
+    pub automatic: bool,
+    pub how: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct TempAdvice {
+    pub temp_dir: String,
+    pub size_bytes: u64,
+    pub on_system_volume: bool,
+    pub solid_state: Option<bool>,
+    // Already a RAM disk (tmpfs), so there is nothing to gain by moving it
+    pub in_memory: bool,
+    // Other volumes temp files could go to
+    pub destinations: Vec<Volume>,
+    pub browsers: Vec<BrowserCache>,
+}
+
+// Whether the drive holding `path` is an SSD, from the physical disk's
+// media type
+#[cfg(target_os = "windows")]
+fn is_solid_state(path: &Path) -> Option<bool> {
+    let letter = path.to_string_lossy().chars().next()?;
+    let script = format!(
+        "(Get-Partition -DriveLetter {} | Get-Disk | Get-PhysicalDisk).MediaType",
+        letter
+    );
+    let media = targets::tool_output("powershell", &["-NoProfile", "-Command", &script])?;
+    match media.trim() {
+        "SSD" => Some(true),
+        "HDD" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_solid_state(path: &Path) -> Option<bool> {
+    let source = mount_field(path, "SOURCE")?;
+    let rotational = targets::tool_output("lsblk", &["-ndo", "ROTA", &source])?;
+    match rotational.trim() {
+        "0" => Some(true),
+        "1" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_solid_state(path: &Path) -> Option<bool> {
+    let info = targets::tool_output("diskutil", &["info", &mount_point(path)?])?;
+    let solid_state = info.lines().find_map(|line| line.trim().strip_prefix("Solid State:"))?;
+    Some(solid_state.trim() == "Yes")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn is_solid_state(_path: &Path) -> Option<bool> {
+    None
+}
+
+// A column of findmnt for the filesystem holding `path`
+#[cfg(target_os = "linux")]
+pub fn mount_field(path: &Path, field: &str) -> Option<String> {
+    let output = targets::tool_output("findmnt", &["-no", field, "--target", &path.to_string_lossy()])?;
+    Some(output.trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn is_in_memory(path: &Path) -> bool {
+    mount_field(path, "FSTYPE").is_some_and(|fstype| fstype == "tmpfs" || fstype == "ramfs")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_in_memory(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(target_os = "macos")]
+fn mount_point(path: &Path) -> Option<String> {
+    disk_space::df_rows(&[&path.to_string_lossy()]).first().map(|row| row[5..].join(" "))
+}
+
+pub fn advise() -> TempAdvice {
+    let temp = std::env::temp_dir();
+    let system = disk_space::system_root();
+    let destinations = disk_space::volume_roots()
+        .into_iter()
+        .filter(|root| !disk_space::on_same_volume(root, &system))
+        .map(|root| Volume {
+            free_bytes: disk_space::free_bytes(&root),
+            solid_state: is_solid_state(&root),
+            root: root.display().to_string(),
+        })
+        .collect();
+    TempAdvice {
+        temp_dir: temp.display().to_string(),
+        size_bytes: cleaner::scan_path(&temp).size_bytes,
+        on_system_volume: disk_space::on_same_volume(&temp, &system),
+        solid_state: is_solid_state(&temp),
+        in_memory: is_in_memory(&temp),
+        destinations,
+        browsers: browser_caches(),
+    }
+}
+
+// Where a move puts its folder; it must exist and not be on the system volume
+fn destination(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    cleaner::check_path(dir)?;
+    if !dir.is_absolute() || !dir.is_dir() {
+        return Err(format!("{} is not an existing folder", dir.display()));
+    }
+    if disk_space::on_same_volume(dir, &disk_space::system_root()) {
+        return Err(format!("{} is on the system volume; choose another drive", dir.display()));
+    }
+    let target = dir.join(name);
+    fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    Ok(target)
+}
+
+// Point the user's TEMP and TMP at a folder below `dir`. Programs started
+// afterwards use it; those already running keep the old folder.
+#[cfg(target_os = "windows")]
+pub fn move_temp(dir: &Path) -> Result<String, String> {
+    let target = destination(dir, "Temp")?;
+    let target = target.to_string_lossy();
+    for var in ["TEMP", "TMP"] {
+        // setx writes HKCU\Environment and tells running programs about it
+        targets::tool_output("setx", &[var, &target]).ok_or_else(|| format!("Failed to set {}", var))?;
+    }
+    Ok(format!("Temp folder moved to {}; sign out and back in for every program to use it", target))
+}
+
+// systemd reads ~/.config/environment.d when the user session starts
+#[cfg(target_os = "linux")]
+pub fn move_temp(dir: &Path) -> Result<String, String> {
+    let target = destination(dir, "tmp")?;
+    let conf_dir = targets::app_config_dir()
+        .ok_or("No config directory")?
+        .join("environment.d");
+    fs::create_dir_all(&conf_dir).map_err(|e| format!("Failed to create {}: {}", conf_dir.display(), e))?;
+    let conf = conf_dir.join("60-memory-cache-manager-tmpdir.conf");
+    fs::write(&conf, format!("TMPDIR={}\n", target.display()))
+        .map_err(|e| format!("Failed to write {}: {}", conf.display(), e))?;
+    Ok(format!(
+        "TMPDIR set to {} in {}; sign out and back in for it to take effect",
+        target.display(),
+        conf.display()
+    ))
+}
+
+// macOS gives each user a temp folder of its own, which is not meant to be moved
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn move_temp(_dir: &Path) -> Result<String, String> {
+    Err("The temp folder cannot be moved on this system".to_string())
+}
+
+// Chromium browsers read DiskCacheDir from their policy key; writing it
+// takes administrator rights
+#[cfg(target_os = "windows")]
+const CHROMIUM_POLICIES: [(&str, &str, &str); 3] = [
+    ("chrome", "Chrome", "HKCU\\Software\\Policies\\Google\\Chrome"),
+    ("edge", "Edge", "HKCU\\Software\\Policies\\Microsoft\\Edge"),
+    ("brave", "Brave", "HKCU\\Software\\Policies\\BraveSoftware\\Brave"),
+];
+
+fn browser_caches() -> Vec<BrowserCache> {
+    let mut browsers = vec![BrowserCache {
+        id: "firefox",
+        name: "Firefox",
+        automatic: true,
+        how: "Sets browser.cache.disk.parent_directory in each profile's user.js",
+    }];
+    #[cfg(target_os = "windows")]
+    browsers.extend(CHROMIUM_POLICIES.iter().map(|&(id, name, _)| BrowserCache {
+        id,
+        name,
+        automatic: true,
+        how: "Sets the DiskCacheDir policy; needs administrator rights",
+    }));
+    #[cfg(not(target_os = "windows"))]
+    browsers.push(BrowserCache {
+        id: "chromium",
+        name: "Chrome, Edge, Brave and Vivaldi",
+        automatic: false,
+        how: "Start the browser with --disk-cache-dir=<folder>, or set the DiskCacheDir policy",
+    });
+    browsers
+}
+
+// Firefox profiles with their prefs (the roaming side on Windows)
+fn firefox_profiles() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let root = targets::app_config_dir().map(|p| p.join("Mozilla").join("Firefox").join("Profiles"));
+    #[cfg(target_os = "macos")]
+    let root = targets::app_config_dir().map(|p| p.join("Firefox/Profiles"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let root = targets::home_dir().map(|p| p.join(".mozilla/firefox"));
+
+    let Some(Ok(entries)) = root.map(fs::read_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|profile| profile.join("prefs.js").is_file())
+        .collect()
+}
+
+// Replace any earlier setting of `pref` in the profile's user.js
+fn set_user_pref(profile: &Path, pref: &str, value: &str) -> Result<(), String> {
+    let user_js = profile.join("user.js");
+    let existing = fs::read_to_string(&user_js).unwrap_or_default();
+    let mut lines: Vec<String> = existing
+        .lines()
+        .filter(|line| !line.contains(&format!("\"{}\"", pref)))
+        .map(str::to_string)
+        .collect();
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    lines.push(format!("user_pref(\"{}\", \"{}\");", pref, value));
+    fs::write(&user_js, lines.join("\n") + "\n").map_err(|e| format!("Failed to write {}: {}", user_js.display(), e))
+}
+
+// Move a browser's disk cache to a folder below `dir`. The browser picks it
+// up on its next start; the old cache is left for the cache targets to clean.
+pub fn move_browser_cache(id: &str, dir: &Path) -> Result<String, String> {
+    if id == "firefox" {
+        let profiles = firefox_profiles();
+        if profiles.is_empty() {
+            return Err("No Firefox profile found".to_string());
+        }
+        let target = destination(dir, "BrowserCache")?.join("Firefox");
+        for profile in &profiles {
+            let name = profile.file_name().unwrap_or_default();
+            let cache = target.join(name);
+            fs::create_dir_all(&cache).map_err(|e| format!("Failed to create {}: {}", cache.display(), e))?;
+            set_user_pref(profile, "browser.cache.disk.parent_directory", &cache.to_string_lossy())?;
+        }
+        return Ok(format!(
+            "Firefox cache moved to {} for {} profile(s); restart Firefox to use it",
+            target.display(),
+            profiles.len()
+        ));
+    }
+
+    #[cfg(target_os = "windows")]
+    if let Some(&(_, name, key)) = CHROMIUM_POLICIES.iter().find(|(browser, _, _)| *browser == id) {
+        let target = destination(dir, "BrowserCache")?.join(name);
+        fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        let target = target.to_string_lossy();
+        targets::tool_output("reg", &["add", key, "/v", "DiskCacheDir", "/t", "REG_SZ", "/d", &target, "/f"])
+            .ok_or_else(|| format!("Failed to set the {} policy; run as administrator", name))?;
+        return Ok(format!("{} cache moved to {}; restart {} to use it", name, target, name));
+    }
+
+    Err(format!("Moving the cache of {} is not supported", id))
+}
@@ -0,0 +1,114 @@
+use crate::cleaner;
+use crate::detect;
+use crate::targets::CacheTarget;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+
+// Which programs fill the temp folders, guessed from the names they give
+// their temp files and folders
+
+// Name prefixes, matched ignoring case, and the program using them
+const PREFIXES: &[(&str, &str)] = &[
+    ("WPDNSE", "Windows Portable Devices"),
+    ("nv_", "NVIDIA drivers"),
+    ("NVIDIA", "NVIDIA drivers"),
+    ("msi", "Windows Installer"),
+    ("dd_", "Visual Studio Installer"),
+    ("chrome_", "Google Chrome"),
+    ("scoped_dir", "Chromium browsers"),
+    ("msedge_", "Microsoft Edge"),
+    ("mozilla-temp", "Firefox"),
+    ("tmpaddon", "Firefox"),
+    ("~DF", "Microsoft Office"),
+    ("OICE_", "Microsoft Office"),
+    ("Outlook", "Microsoft Office"),
+    ("vscode", "Visual Studio Code"),
+    ("npm-", "npm"),
+    ("yarn--", "Yarn"),
+    ("pip-", "pip"),
+    ("_MEI", "PyInstaller apps"),
+    ("go-build", "Go"),
+    ("rustc", "Rust"),
+    ("hsperfdata_", "Java"),
+    ("NuGetScratch", ".NET"),
+    ("MSBuild", ".NET"),
+    ("dotnet-", ".NET"),
+    ("AdobeARM", "Adobe"),
+    ("snap-private-tmp", "Snap"),
+];
+
+// Shorter process names would match too many unrelated entries
+const MIN_PROCESS_NAME: usize = 4;
+
+const UNKNOWN: &str = "Unknown";
+
+// The temp usage of one program
+#[derive(Serialize)]
+pub struct AppUsage {
+    pub app: String,
+    pub size_bytes: u64,
+    pub file_count: u64,
+    // Files and folders directly in the temp folders
+    pub entries: u64,
+}
+
+// Names like "{0F3B1C2D-...}" or without the braces, which installers
+// unpack themselves into
+fn is_guid(name: &str) -> bool {
+    let name = name.trim_start_matches('{').trim_end_matches('}');
+    let groups: Vec<_> = name.split('-').collect();
+    groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups.iter().all(|group| group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+// The program an entry of a temp folder most likely belongs to: by known
+// prefixes, then by the name of a running program it starts with
+fn attribute(name: &str, processes: &[String]) -> String {
+    let lower = name.to_lowercase();
+    if let Some((_, app)) = PREFIXES.iter().find(|(prefix, _)| lower.starts_with(&prefix.to_lowercase())) {
+        return app.to_string();
+    }
+    if is_guid(name) {
+        return "Installers".to_string();
+    }
+    processes
+        .iter()
+        .filter(|process| process.len() >= MIN_PROCESS_NAME && lower.starts_with(process.as_str()))
+        .max_by_key(|process| process.len())
+        .cloned()
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
+
+// The temp folders' usage by program, the most bytes first with the
+// unattributed entries last
+pub fn breakdown(target: &CacheTarget) -> Vec<AppUsage> {
+    let processes = detect::running_processes();
+    let mut apps: BTreeMap<String, AppUsage> = BTreeMap::new();
+    for path in &target.paths {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            continue;
+        };
+        let (size_bytes, file_count) = if meta.is_dir() {
+            let stats = cleaner::scan_path_limited(path, target.scan_limits);
+            (stats.size_bytes, stats.file_count)
+        } else {
+            (meta.len(), 1)
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let app = attribute(&name, &processes);
+        let usage = apps.entry(app.clone()).or_insert(AppUsage {
+            app,
+            size_bytes: 0,
+            file_count: 0,
+            entries: 0,
+        });
+        usage.size_bytes += size_bytes;
+        usage.file_count += file_count;
+        usage.entries += 1;
+    }
+    let mut apps: Vec<_> = apps.into_values().collect();
+    apps.sort_by_key(|usage| (usage.app == UNKNOWN, Reverse(usage.size_bytes)));
+    apps
+}
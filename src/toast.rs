@@ -0,0 +1,121 @@
+// Toasts with buttons, for cleans that ran by themselves: View report and,
+// when the clean can be undone, Undo. Windows shows them through WinRT, which
+// reports the button clicked while the app is running. Elsewhere, or when
+// Windows refuses the toast, the caller falls back to a plain notification.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ViewReport,
+    Undo,
+}
+
+impl Action {
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn argument(self) -> &'static str {
+        match self {
+            Action::ViewReport => "view-report",
+            Action::Undo => "undo",
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Clicking the toast itself views the report
+#[cfg(target_os = "windows")]
+fn toast_xml(title: &str, body: &str, buttons: &[(Action, String)]) -> String {
+    let actions: String = buttons
+        .iter()
+        .map(|(action, label)| {
+            format!(
+                r#"<action content="{}" arguments="{}" activationType="foreground"/>"#,
+                escape(label),
+                action.argument()
+            )
+        })
+        .collect();
+    format!(
+        concat!(
+            r#"<toast launch="{}"><visual><binding template="ToastGeneric">"#,
+            "<text>{}</text><text>{}</text></binding></visual><actions>{}</actions></toast>"
+        ),
+        Action::ViewReport.argument(),
+        escape(title),
+        escape(body),
+        actions
+    )
+}
+
+// Toasts shown, so Windows can still report their buttons; the oldest go
+// once there are more than fit in the action center
+#[cfg(target_os = "windows")]
+static SHOWN: std::sync::Mutex<Vec<windows::UI::Notifications::ToastNotification>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(target_os = "windows")]
+const KEPT_TOASTS: usize = 20;
+
+#[cfg(target_os = "windows")]
+pub fn show(
+    app_id: &str,
+    title: &str,
+    body: &str,
+    buttons: &[(Action, String)],
+    on_action: impl Fn(Action) + Send + Sync + 'static,
+) -> Result<(), String> {
+    use windows::core::{ComInterface, IInspectable, HSTRING};
+    use windows::Data::Xml::Dom::XmlDocument;
+    use windows::Foundation::TypedEventHandler;
+    use windows::UI::Notifications::{ToastActivatedEventArgs, ToastNotification, ToastNotificationManager};
+
+    let failed = |e: windows::core::Error| format!("Failed to show the notification: {}", e);
+    let document = XmlDocument::new().map_err(failed)?;
+    document
+        .LoadXml(&HSTRING::from(toast_xml(title, body, buttons)))
+        .map_err(failed)?;
+    let toast = ToastNotification::CreateToastNotification(&document).map_err(failed)?;
+    let actions: Vec<Action> = buttons.iter().map(|(action, _)| *action).chain([Action::ViewReport]).collect();
+    toast
+        .Activated(&TypedEventHandler::new(
+            move |_: &Option<ToastNotification>, args: &Option<IInspectable>| {
+                let argument = args
+                    .as_ref()
+                    .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+                    .and_then(|args| args.Arguments().ok());
+                if let Some(argument) = argument {
+                    if let Some(&action) = actions.iter().find(|action| argument == action.argument()) {
+                        on_action(action);
+                    }
+                }
+                Ok(())
+            },
+        ))
+        .map_err(failed)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(app_id))
+        .and_then(|notifier| notifier.Show(&toast))
+        .map_err(failed)?;
+
+    let mut shown = SHOWN.lock().unwrap();
+    shown.push(toast);
+    if shown.len() > KEPT_TOASTS {
+        shown.remove(0);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn show(
+    _app_id: &str,
+    _title: &str,
+    _body: &str,
+    _buttons: &[(Action, String)],
+    _on_action: impl Fn(Action) + Send + Sync + 'static,
+) -> Result<(), String> {
+    Err("Notifications with buttons are only supported on Windows".to_string())
+}
@@ -0,0 +1,380 @@
+use crate::clean_history;
+use crate::cleaner::{self, CleanProgress, CleanStats, DirStats};
+use crate::cli::attach_console;
+use crate::config_file;
+use crate::journal;
+use crate::locale;
+use crate::targets::{self, CacheTarget};
+use crate::undo;
+use crate::Config;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::ExecutableCommand;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{Frame, Terminal};
+use std::cell::OnceCell;
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+enum Screen {
+    Targets,
+    // Lines listing what a clean would delete, and how far they are scrolled
+    Preview(Vec<String>, u16),
+    ConfirmClean,
+}
+
+// Sent from the worker thread running a clean
+enum CleanUpdate {
+    Progress(CleanProgress),
+    // With whether it was cancelled
+    Done(CleanStats, bool),
+}
+
+struct App {
+    config: Config,
+    locale: String,
+    targets: Vec<(CacheTarget, DirStats)>,
+    selected: Vec<bool>,
+    table: TableState,
+    screen: Screen,
+    status: String,
+    cleaning: Option<Receiver<CleanUpdate>>,
+    quit: bool,
+}
+
+impl App {
+    fn new(config: Config) -> Self {
+        let locale = locale::resolve(&config.locale);
+        let mut app = Self {
+            config,
+            locale,
+            targets: Vec::new(),
+            selected: Vec::new(),
+            table: TableState::default(),
+            screen: Screen::Targets,
+            status: String::new(),
+            cleaning: None,
+            quit: false,
+        };
+        app.scan();
+        app
+    }
+
+    fn scan(&mut self) {
+        self.targets = targets::builtin_targets(&self.config)
+            .into_iter()
+            .map(|target| {
+                let stats = target.scan();
+                (target, stats)
+            })
+            .collect();
+        self.selected = vec![false; self.targets.len()];
+        self.table.select((!self.targets.is_empty()).then_some(0));
+        self.status = format!("{} targets found", self.targets.len());
+    }
+
+    fn size(&self, bytes: u64) -> String {
+        locale::format_size(bytes, &self.config)
+    }
+
+    fn count(&self, count: usize) -> String {
+        locale::format_decimal(count as f64, 0, &self.locale)
+    }
+
+    fn selected_targets(&self) -> Vec<CacheTarget> {
+        self.targets
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|((target, _), _)| target.clone())
+            .collect()
+    }
+
+    fn move_cursor(&mut self, step: isize) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let last = self.targets.len() as isize - 1;
+        self.table.select(Some((current + step).clamp(0, last) as usize));
+    }
+
+    fn toggle(&mut self, index: usize) {
+        let target = &self.targets[index].0;
+        if target.report_only {
+            return;
+        }
+        self.selected[index] = !self.selected[index];
+        if !self.selected[index] {
+            return;
+        }
+        if let Some(app) = target.running_owner(&OnceCell::new()) {
+            self.status = format!("⚠ {} is running — {} will be skipped", app, target.name);
+        } else if let Some(warning) = target.warning {
+            self.status = format!("⚠ {}", warning);
+        }
+    }
+
+    fn toggle_all(&mut self) {
+        let select = !self.selected.iter().any(|selected| *selected);
+        for (selected, (target, _)) in self.selected.iter_mut().zip(&self.targets) {
+            *selected = select && !target.report_only && target.warning.is_none();
+        }
+    }
+
+    fn preview(&mut self) {
+        let selected = self.selected_targets();
+        if selected.is_empty() {
+            self.status = "Select at least one target to preview".to_string();
+            return;
+        }
+        let mut lines = Vec::new();
+        for preview in selected.iter().map(targets::preview) {
+            lines.push(format!(
+                "{}: {} files, {}",
+                preview.name,
+                self.count(preview.files.len()),
+                self.size(preview.total_bytes)
+            ));
+            for file in &preview.files {
+                lines.push(format!("  {:>12}  {}", self.size(file.size), file.path.display()));
+            }
+        }
+        self.screen = Screen::Preview(lines, 0);
+    }
+
+    fn start_clean(&mut self) {
+        self.screen = Screen::Targets;
+        let selected = self.selected_targets();
+        if selected.is_empty() {
+            self.status = "Select at least one target to clean".to_string();
+            return;
+        }
+
+        let deletion = self.config.deletion;
+        let verify = self.config.verify_cleans;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let ids: Vec<_> = selected.iter().map(|target| target.id.clone()).collect();
+            journal::begin(&ids);
+            let batch = undo::begin(deletion);
+            let history = clean_history::Recorder::start("tui").verified(verify);
+            let progress = sender.clone();
+            let stats = cleaner::track_progress(
+                move |update| {
+                    journal::record_progress(&update);
+                    let _ = progress.send(CleanUpdate::Progress(update));
+                },
+                || targets::clean_targets(&selected, &history, |target| journal::target_done(&target.id)),
+            );
+            history.finish();
+            let cancelled = batch.is_cancelled();
+            undo::finish(batch);
+            journal::finish();
+            let _ = sender.send(CleanUpdate::Done(stats, cancelled));
+        });
+        self.cleaning = Some(receiver);
+        self.status = "Cleaning…".to_string();
+    }
+
+    // Pick up whatever the clean worker has reported since the last frame
+    fn poll_clean(&mut self) {
+        let Some(receiver) = &self.cleaning else {
+            return;
+        };
+        let mut done = None;
+        let mut latest = None;
+        while let Ok(update) = receiver.try_recv() {
+            match update {
+                CleanUpdate::Progress(progress) => latest = Some(progress),
+                CleanUpdate::Done(stats, cancelled) => done = Some((stats, cancelled)),
+            }
+        }
+
+        if let Some(progress) = latest {
+            self.status = format!(
+                "Cleaning: {} files, {} · {}",
+                self.count(progress.files_deleted as usize),
+                self.size(progress.freed_bytes),
+                progress.current_dir
+            );
+        }
+        if let Some((stats, cancelled)) = done {
+            self.cleaning = None;
+            self.scan();
+            let verb = if cancelled { "Cancelled after deleting" } else { "Deleted" };
+            self.status = format!(
+                "{} {} files ({}), {} failed ({}), {} kept as recently used ({})",
+                verb,
+                self.count(stats.files_deleted as usize),
+                self.size(stats.freed_bytes),
+                self.count(stats.files_failed as usize),
+                self.size(stats.failed_bytes),
+                self.count(stats.files_skipped as usize),
+                self.size(stats.skipped_bytes)
+            );
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyCode) {
+        if self.cleaning.is_some() {
+            if key == KeyCode::Char('x') {
+                cleaner::cancel_clean();
+                self.status = "Cancelling…".to_string();
+            }
+            return;
+        }
+
+        match &mut self.screen {
+            Screen::Targets => match key {
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+                KeyCode::Char(' ') => {
+                    if let Some(index) = self.table.selected() {
+                        self.toggle(index);
+                    }
+                }
+                KeyCode::Char('a') => self.toggle_all(),
+                KeyCode::Char('r') => self.scan(),
+                KeyCode::Char('p') => self.preview(),
+                KeyCode::Char('c') => self.screen = Screen::ConfirmClean,
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                _ => {}
+            },
+            Screen::Preview(lines, scroll) => match key {
+                KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *scroll = (*scroll + 1).min(lines.len().saturating_sub(1) as u16)
+                }
+                KeyCode::PageUp => *scroll = scroll.saturating_sub(20),
+                KeyCode::PageDown => *scroll = (*scroll + 20).min(lines.len().saturating_sub(1) as u16),
+                KeyCode::Char('c') => self.screen = Screen::ConfirmClean,
+                KeyCode::Char('q') | KeyCode::Esc => self.screen = Screen::Targets,
+                _ => {}
+            },
+            Screen::ConfirmClean => match key {
+                KeyCode::Char('y') => self.start_clean(),
+                _ => self.screen = Screen::Targets,
+            },
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3), Constraint::Length(1)])
+            .split(frame.size());
+
+        let help = match (&self.screen, self.cleaning.is_some()) {
+            (_, true) => "x cancel",
+            (Screen::Targets, _) => "↑↓ move · space select · a all · p preview · c clean · r rescan · q quit",
+            (Screen::Preview(..), _) => "↑↓ PgUp PgDn scroll · c clean · esc back",
+            (Screen::ConfirmClean, _) => "y confirm · any other key cancels",
+        };
+
+        if let Screen::Preview(lines, scroll) = &self.screen {
+            let text = lines.join("\n");
+            let preview = Paragraph::new(text)
+                .scroll((*scroll, 0))
+                .block(Block::default().borders(Borders::ALL).title("Files that would be deleted"));
+            frame.render_widget(preview, areas[0]);
+        } else {
+            let rows: Vec<_> = self
+                .targets
+                .iter()
+                .zip(&self.selected)
+                .map(|((target, stats), selected)| {
+                    let mark = match (target.report_only, selected) {
+                        (true, _) => " - ",
+                        (false, true) => "[x]",
+                        (false, false) => "[ ]",
+                    };
+                    let approximate = if stats.approximate { "~" } else { "" };
+                    Row::new(vec![
+                        mark.to_string(),
+                        target.name.clone(),
+                        self.count(stats.file_count as usize),
+                        format!("{}{}", approximate, self.size(stats.size_bytes)),
+                        target.strategy.describe().unwrap_or_default(),
+                    ])
+                })
+                .collect();
+            let widths = [
+                Constraint::Length(3),
+                Constraint::Min(24),
+                Constraint::Length(10),
+                Constraint::Length(12),
+                Constraint::Length(22),
+            ];
+            let table = Table::new(rows, widths)
+                .header(
+                    Row::new(vec!["", "Target", "Files", "Size", "Rule"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                .block(Block::default().borders(Borders::ALL).title("Cache targets"));
+            frame.render_stateful_widget(table, areas[0], &mut self.table);
+        }
+
+        let status = if let Screen::ConfirmClean = self.screen {
+            let selected = self.selected_targets();
+            let bytes: u64 = self
+                .targets
+                .iter()
+                .zip(&self.selected)
+                .filter(|(_, selected)| **selected)
+                .map(|((_, stats), _)| stats.size_bytes)
+                .sum();
+            format!("Clean {} targets (about {})? y/n", selected.len(), self.size(bytes))
+        } else {
+            self.status.clone()
+        };
+        frame.render_widget(Paragraph::new(status).block(Block::default().borders(Borders::ALL)), areas[1]);
+        frame.render_widget(Paragraph::new(help), areas[2]);
+    }
+}
+
+// Keyboard-driven target table for terminals and SSH sessions, using the same
+// scanning and cleaning code as the window
+pub fn run() -> i32 {
+    attach_console();
+    println!("Scanning cache targets…");
+
+    let mut app = App::new(config_file::load());
+    if let Err(e) = show(&mut app) {
+        eprintln!("Terminal UI failed: {}", e);
+        return 1;
+    }
+    0
+}
+
+fn show(app: &mut App) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let result = event_loop(app);
+
+    // Restore the terminal even when drawing failed
+    let _ = io::stdout().execute(LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    result
+}
+
+fn event_loop(app: &mut App) -> io::Result<()> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    while !app.quit {
+        app.poll_clean();
+        terminal.draw(|frame| app.draw(frame))?;
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use crate::cleaner::{self, CleanRun, DeletionMode};
+use crate::config_file;
+use crate::staging;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The files the most recent recycling clean moved to the recycle bin. Each
+// such clean replaces the record of the one before.
+#[derive(Serialize, Deserialize)]
+struct RecycledBatch {
+    started_at: u64,
+    files: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+pub struct UndoInfo {
+    cleaned_at: u64,
+    file_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct UndoStats {
+    pub restored: u64,
+    // No longer in the recycle bin, or something now exists at the original path
+    pub failed: u64,
+}
+
+fn batch_path() -> Option<PathBuf> {
+    config_file::app_data_dir().map(|p| p.join("last-recycled.json"))
+}
+
+fn read_batch() -> Option<RecycledBatch> {
+    let text = fs::read_to_string(batch_path()?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+// A clean begun on this thread, to pass to finish on the same thread
+pub struct Batch {
+    started_at: u64,
+    run: Arc<CleanRun>,
+}
+
+impl Batch {
+    // For a thread that stops the clean on its own, such as a time limit
+    pub fn run(&self) -> Arc<CleanRun> {
+        self.run.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.run.is_cancelled()
+    }
+}
+
+fn start(mode: DeletionMode, by_user: bool) -> Batch {
+    Batch {
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        run: cleaner::begin_batch(mode, by_user),
+    }
+}
+
+// Start a clean the user asked for, which cancel_clean stops, that deletes
+// files according to `mode`
+pub fn begin(mode: DeletionMode) -> Batch {
+    start(mode, true)
+}
+
+// Start a clean that runs by itself, such as a scheduled one; the user's
+// cancel leaves it running
+pub fn begin_automatic(mode: DeletionMode) -> Batch {
+    start(mode, false)
+}
+
+// What revert puts back of a finished clean
+#[derive(Default)]
+pub struct Undoable {
+    started_at: u64,
+    // Ids of the files it moved to the quarantine folder
+    staged: Vec<String>,
+    recycled: bool,
+}
+
+impl Undoable {
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty() && !self.recycled
+    }
+
+    pub fn staged(&self) -> &[String] {
+        &self.staged
+    }
+}
+
+// Remember what the clean recycled so it can be undone, and list what it
+// moved to the quarantine folder
+pub fn finish(batch: Batch) -> Undoable {
+    let started_at = batch.started_at;
+    let (files, staged) = cleaner::end_batch();
+    let staged = staging::record(staged);
+    let undoable = Undoable {
+        started_at,
+        staged,
+        recycled: !files.is_empty(),
+    };
+    let Some(path) = batch_path() else {
+        return undoable;
+    };
+    if files.is_empty() {
+        return undoable;
+    }
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(text) = serde_json::to_string(&RecycledBatch { started_at, files }) {
+        let _ = fs::write(path, text);
+    }
+    undoable
+}
+
+// Put back what one clean moved to the quarantine folder or recycled. Its
+// recycled files are only known until a later clean recycles some.
+pub fn revert(undoable: &Undoable) -> Result<UndoStats, String> {
+    if undoable.recycled && read_batch().is_none_or(|batch| batch.started_at != undoable.started_at) {
+        return Err("A later clean recycled files too; restore this one's from the Recycle Bin".to_string());
+    }
+    let mut stats = if undoable.recycled {
+        undo()?
+    } else {
+        UndoStats { restored: 0, failed: 0 }
+    };
+    for id in &undoable.staged {
+        match staging::restore(id) {
+            Ok(_) => stats.restored += 1,
+            Err(_) => stats.failed += 1,
+        }
+    }
+    Ok(stats)
+}
+
+pub fn last() -> Option<UndoInfo> {
+    read_batch().map(|batch| UndoInfo {
+        cleaned_at: batch.started_at,
+        file_count: batch.files.len(),
+    })
+}
+
+// Move the files of the last recycling clean back where they came from
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+pub fn undo() -> Result<UndoStats, String> {
+    use std::collections::{HashMap, HashSet};
+    use trash::TrashItem;
+
+    let batch = read_batch().ok_or("There is no clean to undo")?;
+    let wanted: HashSet<PathBuf> = batch.files.iter().map(|file| cleaner::comparable(file)).collect();
+    let items = trash::os_limited::list().map_err(|e| format!("Failed to read the recycle bin: {}", e))?;
+
+    // A file may have been recycled more than once; the newest copy is ours
+    let mut newest: HashMap<PathBuf, TrashItem> = HashMap::new();
+    for item in items {
+        if item.time_deleted < batch.started_at as i64 {
+            continue;
+        }
+        let key = cleaner::comparable(&item.original_path());
+        if !wanted.contains(&key) {
+            continue;
+        }
+        if newest.get(&key).is_none_or(|kept| kept.time_deleted < item.time_deleted) {
+            newest.insert(key, item);
+        }
+    }
+
+    let mut stats = UndoStats {
+        restored: 0,
+        failed: (wanted.len() - newest.len()) as u64,
+    };
+    for item in newest.into_values() {
+        // Directories emptied by the clean are gone and must exist again first
+        let _ = fs::create_dir_all(&item.original_parent);
+        if trash::os_limited::restore_all([item]).is_ok() {
+            stats.restored += 1;
+        } else {
+            stats.failed += 1;
+        }
+    }
+
+    if let Some(path) = batch_path() {
+        let _ = fs::remove_file(path);
+    }
+    Ok(stats)
+}
+
+// macOS offers no API to put items back from the Trash
+#[cfg(target_os = "macos")]
+pub fn undo() -> Result<UndoStats, String> {
+    Err("Restoring from the Trash is not supported on macOS; use Put Back in Finder".to_string())
+}
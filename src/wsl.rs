@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+// WSL 2 distributions keep their whole file system in an ext4.vhdx that
+// grows as files are added but never shrinks by itself, so space freed
+// inside Linux stays taken on the Windows drive until the disk is compacted
+
+pub struct Distro {
+    pub name: String,
+    pub vhdx: PathBuf,
+}
+
+// Registered distributions with a disk image; WSL 1 ones have none
+#[cfg(target_os = "windows")]
+pub fn distros() -> Vec<Distro> {
+    use windows::core::{HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::System::Registry::*;
+
+    const LXSS_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Lxss";
+    let mut lxss = HKEY::default();
+    unsafe {
+        if RegOpenKeyExW(HKEY_CURRENT_USER, &HSTRING::from(LXSS_KEY), 0, KEY_READ, &mut lxss).is_err() {
+            return Vec::new();
+        }
+    }
+
+    // One subkey per distribution, named by its GUID
+    let mut distros = Vec::new();
+    for index in 0.. {
+        let mut guid = [0u16; 256];
+        let mut len = guid.len() as u32;
+        let name = PWSTR(guid.as_mut_ptr());
+        if unsafe { RegEnumKeyExW(lxss, index, name, &mut len, None, PWSTR::null(), None, None) }.is_err() {
+            break;
+        }
+        let mut key = HKEY::default();
+        unsafe {
+            if RegOpenKeyExW(lxss, PCWSTR(guid.as_ptr()), 0, KEY_READ, &mut key).is_err() {
+                continue;
+            }
+        }
+        if let (Some(name), Some(base)) = (string_value(key, "DistributionName"), string_value(key, "BasePath")) {
+            distros.push(Distro {
+                name,
+                vhdx: PathBuf::from(base).join("ext4.vhdx"),
+            });
+        }
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+    }
+    unsafe {
+        let _ = RegCloseKey(lxss);
+    }
+    distros.retain(|distro| distro.vhdx.is_file());
+    distros
+}
+
+// A string value of an open registry key
+#[cfg(target_os = "windows")]
+fn string_value(key: windows::Win32::System::Registry::HKEY, name: &str) -> Option<String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::*;
+
+    let mut data = [0u16; 1024];
+    let mut size = std::mem::size_of_val(&data) as u32;
+    let mut kind = REG_VALUE_TYPE::default();
+    let read = unsafe {
+        RegQueryValueExW(
+            key,
+            &HSTRING::from(name),
+            None,
+            Some(&mut kind),
+            Some(data.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if read.is_err() {
+        return None;
+    }
+    if kind != REG_SZ && kind != REG_EXPAND_SZ {
+        return None;
+    }
+    let len = (size as usize / 2).min(data.len());
+    Some(String::from_utf16_lossy(&data[..len]).trim_end_matches('\0').to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn distros() -> Vec<Distro> {
+    Vec::new()
+}
+
+// Trim each distribution so freed blocks are known to be free, shut WSL
+// down and compact every disk image with diskpart, which needs
+// administrator rights
+#[cfg(target_os = "windows")]
+pub fn compact(config: &crate::Config) -> Result<String, String> {
+    use crate::targets::tool_output;
+
+    if !crate::elevation::is_elevated() {
+        return Err("Compacting WSL disks needs administrator rights; restart as administrator first".to_string());
+    }
+    let distros = distros();
+    if distros.is_empty() {
+        return Err("No WSL 2 distributions found".to_string());
+    }
+    let size = |distro: &Distro| std::fs::metadata(&distro.vhdx).map_or(0, |meta| meta.len());
+    let before: u64 = distros.iter().map(size).sum();
+    for distro in &distros {
+        // Fails for distributions without fstrim; they still compact, less so
+        let _ = tool_output("wsl.exe", &["-d", &distro.name, "-u", "root", "fstrim", "-a"]);
+    }
+    tool_output("wsl.exe", &["--shutdown"]).ok_or("Failed to shut down WSL")?;
+
+    let script_path = std::env::temp_dir().join("memory-cache-manager-compact-vhdx.txt");
+    let mut failed = Vec::new();
+    for distro in &distros {
+        let script = format!(
+            "select vdisk file=\"{}\"\nattach vdisk readonly\ncompact vdisk\ndetach vdisk\n",
+            distro.vhdx.display()
+        );
+        std::fs::write(&script_path, script).map_err(|e| format!("Failed to write the diskpart script: {}", e))?;
+        if tool_output("diskpart.exe", &["/s", &script_path.to_string_lossy()]).is_none() {
+            failed.push(distro.name.as_str());
+        }
+    }
+    let _ = std::fs::remove_file(&script_path);
+    let after: u64 = distros.iter().map(size).sum();
+
+    let mut message = format!(
+        "WSL disks compacted ({} freed)",
+        crate::locale::format_size(before.saturating_sub(after), config)
+    );
+    if !failed.is_empty() {
+        message.push_str(&format!("; could not compact {}", failed.join(", ")));
+    }
+    Ok(message)
+}